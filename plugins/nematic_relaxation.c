@@ -0,0 +1,124 @@
+/* Example plugin: every director turns towards the nematic mean of its four neighbours.
+ *
+ *     cc -shared -fPIC -O2 -o plugins/libnematic_relaxation.so plugins/nematic_relaxation.c -lm */
+#include "simula_plugin.h"
+
+#include <math.h>
+#include <stdlib.h>
+#include <string.h>
+
+typedef struct {
+    float rate;
+    float noise;
+    uint64_t rng;
+    size_t sites;
+    float *angles;
+    float *order;
+} State;
+
+static const SimulaParameter PARAMETERS[] = {{"rate", 0.5f}, {"noise", 0.05f}};
+
+static float uniform(State *state) {
+    state->rng ^= state->rng << 13;
+    state->rng ^= state->rng >> 7;
+    state->rng ^= state->rng << 17;
+    return (float)(state->rng >> 40) / (float)(1 << 24) * 2.0f - 1.0f;
+}
+
+static void *create(void) {
+    State *state = calloc(1, sizeof(State));
+    state->rate = PARAMETERS[0].value;
+    state->noise = PARAMETERS[1].value;
+    state->rng = 0x9e3779b97f4a7c15ull;
+    return state;
+}
+
+static void destroy(void *opaque) {
+    State *state = opaque;
+    free(state->angles);
+    free(state->order);
+    free(state);
+}
+
+static size_t compute(void *opaque, uint64_t iteration, const SimulaGrid *inputs, size_t input_count,
+                      SimulaGrid *outputs, size_t capacity) {
+    State *state = opaque;
+    const SimulaGrid *grid = NULL;
+    for (size_t i = 0; i < input_count; i++) {
+        if (inputs[i].kind == SIMULA_GRID_ORIENTED) grid = &inputs[i];
+    }
+    if (grid == NULL || capacity < 2) return 0;
+
+    size_t rows = grid->rows, cols = grid->cols, sites = rows * cols;
+    if (sites != state->sites) {
+        state->angles = realloc(state->angles, sites * sizeof(float));
+        state->order = realloc(state->order, sites * sizeof(float));
+        state->sites = sites;
+    }
+    for (size_t row = 0; row < rows; row++) {
+        for (size_t col = 0; col < cols; col++) {
+            size_t neighbours[4] = {
+                row * cols + (col + cols - 1) % cols, row * cols + (col + 1) % cols,
+                (row + rows - 1) % rows * cols + col, (row + 1) % rows * cols + col,
+            };
+            float c = 0.0f, s = 0.0f;
+            for (int n = 0; n < 4; n++) {
+                c += cosf(2.0f * grid->values[neighbours[n]]);
+                s += sinf(2.0f * grid->values[neighbours[n]]);
+            }
+            float angle = grid->values[row * cols + col];
+            float turn = 0.5f * atan2f(s * cosf(2.0f * angle) - c * sinf(2.0f * angle),
+                                       c * cosf(2.0f * angle) + s * sinf(2.0f * angle));
+            state->angles[row * cols + col] = angle + state->rate * turn + state->noise * uniform(state);
+            state->order[row * cols + col] = sqrtf(c * c + s * s) / 4.0f;
+        }
+    }
+    (void)iteration;
+    outputs[0] = (SimulaGrid){SIMULA_GRID_ORIENTED, rows, cols, 1, state->angles};
+    outputs[1] = (SimulaGrid){SIMULA_GRID_SCALAR, rows, cols, 1, state->order};
+    return 2;
+}
+
+static void set_parameter(void *opaque, const char *name, float value) {
+    State *state = opaque;
+    if (strcmp(name, "rate") == 0) state->rate = value;
+    if (strcmp(name, "noise") == 0) state->noise = value;
+}
+
+static void seed(void *opaque, uint64_t seed) {
+    ((State *)opaque)->rng = seed * 0x9e3779b97f4a7c15ull | 1;
+}
+
+static size_t observables(void *opaque, const SimulaGrid *grids, size_t grid_count,
+                          SimulaObservable *observables, size_t capacity) {
+    (void)opaque;
+    for (size_t i = 0; i < grid_count && capacity > 0; i++) {
+        if (grids[i].kind != SIMULA_GRID_ORIENTED) continue;
+        size_t sites = grids[i].rows * grids[i].cols;
+        double c = 0.0, s = 0.0;
+        for (size_t site = 0; site < sites; site++) {
+            c += cos(2.0 * grids[i].values[site]);
+            s += sin(2.0 * grids[i].values[site]);
+        }
+        observables[0] = (SimulaObservable){"order_parameter", sqrt(c * c + s * s) / (double)sites};
+        return 1;
+    }
+    return 0;
+}
+
+static const SimulaPlugin PLUGIN = {
+    .abi_version = SIMULA_PLUGIN_ABI_VERSION,
+    .name = "Nematic relaxation (plugin)",
+    .inputs = 1u << SIMULA_GRID_ORIENTED,
+    .outputs = 1u << SIMULA_GRID_ORIENTED | 1u << SIMULA_GRID_SCALAR,
+    .parameters = PARAMETERS,
+    .parameter_count = sizeof(PARAMETERS) / sizeof(PARAMETERS[0]),
+    .create = create,
+    .destroy = destroy,
+    .compute = compute,
+    .set_parameter = set_parameter,
+    .seed = seed,
+    .observables = observables,
+};
+
+const SimulaPlugin *simula_plugin(void) { return &PLUGIN; }
@@ -0,0 +1,60 @@
+/* C ABI of Simula algorithm plugins, mirrored by src/plugins/abi.rs.
+ *
+ * A plugin is a shared library in the `plugins` directory exporting `simula_plugin`, which
+ * returns a descriptor that stays valid while the library is loaded. Simula never unloads it. */
+#ifndef SIMULA_PLUGIN_H
+#define SIMULA_PLUGIN_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+#define SIMULA_PLUGIN_ABI_VERSION 1
+
+/* Grid kinds, and `1 << kind` for the `inputs` and `outputs` masks. */
+#define SIMULA_GRID_ORIENTED 0 /* director angles of a 2D lattice, 1 float per site */
+#define SIMULA_GRID_SCALAR 1   /* 1 float per site of a 2D lattice */
+#define SIMULA_GRID_VECTOR 2   /* (x, y) per site of a 2D lattice */
+#define SIMULA_GRID_DIRECTOR 3 /* unit (x, y, z) per site of a 3D lattice */
+
+/* Sites are row-major with row 0 at the bottom, layer after layer. */
+typedef struct {
+    uint32_t kind;
+    size_t rows;
+    size_t cols;
+    size_t layers; /* 1 for 2D grids */
+    const float *values;
+} SimulaGrid;
+
+typedef struct {
+    const char *name;
+    float value;
+} SimulaParameter;
+
+typedef struct {
+    const char *name;
+    double value;
+} SimulaObservable;
+
+typedef struct {
+    uint32_t abi_version;
+    const char *name; /* shown in the "Select algorithm" list */
+    uint32_t inputs;
+    uint32_t outputs;
+    const SimulaParameter *parameters; /* names and default values */
+    size_t parameter_count;
+    void *(*create)(void);
+    void (*destroy)(void *state);
+    /* Reads the grids of the previous step and points up to `capacity` outputs at grids owned by
+     * the plugin, valid until the next call on this state. Returns how many it wrote. */
+    size_t (*compute)(void *state, uint64_t iteration, const SimulaGrid *inputs, size_t input_count,
+                      SimulaGrid *outputs, size_t capacity);
+    /* The rest may be NULL. */
+    void (*set_parameter)(void *state, const char *name, float value);
+    void (*seed)(void *state, uint64_t seed);
+    size_t (*observables)(void *state, const SimulaGrid *grids, size_t grid_count,
+                          SimulaObservable *observables, size_t capacity);
+} SimulaPlugin;
+
+const SimulaPlugin *simula_plugin(void);
+
+#endif
@@ -1,23 +1,147 @@
-use std::boxed;
+use crate::algorithm_processor::{LatticeConditions, Particles, Table};
+use std::sync::Arc;
 
 pub enum DataType {
+    /// Director angles of a row-major lattice with row 0 at the bottom.
     OrientedGrid(Vec<f32>, (usize, usize)),
+    /// One scalar per site on the same layout, e.g. the nematic order `S`.
+    ScalarGrid(Vec<f32>, (usize, usize)),
+    /// Unit directors of a `(rows, cols, layers)` lattice, index `(layer * rows + row) * cols + col`.
+    DirectorField(Vec<[f32; 3]>, Dimensions3),
+    /// Off-lattice particles, for molecular dynamics.
+    Particles(Particles),
+    /// One `(x, y)` vector per site of a 2D lattice, e.g. a flow velocity.
+    VectorGrid(Vectors, (usize, usize)),
 }
 
-pub struct Algorithm {
-    processor: Box<dyn AlgorithmComputation>,
-    renderer: Box<dyn AlgorithmRenderer>,
-    gui: Box<dyn AlgorithmGUI>,
+/// One `(x, y)` vector per lattice site.
+pub type Vectors = Vec<[f32; 2]>;
+
+/// `(rows, cols, layers)` of a 3D lattice.
+pub type Dimensions3 = (usize, usize, usize);
+
+impl DataType {
+    /// The first oriented grid in `data`.
+    pub fn oriented(data: &[DataType]) -> Option<(&Vec<f32>, (usize, usize))> {
+        data.iter().find_map(|datum| match datum {
+            DataType::OrientedGrid(angles, dimensions) => Some((angles, *dimensions)),
+            _ => None,
+        })
+    }
+
+    /// The first 3D director field in `data`.
+    pub fn directors(data: &[DataType]) -> Option<(&Vec<[f32; 3]>, Dimensions3)> {
+        data.iter().find_map(|datum| match datum {
+            DataType::DirectorField(directors, dimensions) => Some((directors, *dimensions)),
+            _ => None,
+        })
+    }
+
+    /// The first particle set in `data`.
+    pub fn particles(data: &[DataType]) -> Option<&Particles> {
+        data.iter().find_map(|datum| match datum {
+            DataType::Particles(particles) => Some(particles),
+            _ => None,
+        })
+    }
+
+    /// The first vector grid in `data`.
+    pub fn vectors(data: &[DataType]) -> Option<(&Vectors, (usize, usize))> {
+        data.iter().find_map(|datum| match datum {
+            DataType::VectorGrid(vectors, dimensions) => Some((vectors, *dimensions)),
+            _ => None,
+        })
+    }
+
+    /// The first scalar grid in `data`.
+    pub fn scalar(data: &[DataType]) -> Option<(&Vec<f32>, (usize, usize))> {
+        data.iter().find_map(|datum| match datum {
+            DataType::ScalarGrid(values, dimensions) => Some((values, *dimensions)),
+            _ => None,
+        })
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Parameter {
+    pub name: String,
+    pub value: f32,
 }
 
-pub trait AlgorithmComputation {
-    fn compute(&mut self, iteration: usize, data: &Vec<DataType>) -> Vec<DataType>;
+impl Parameter {
+    pub fn new(name: &str, value: f32) -> Self {
+        Self {
+            name: name.to_owned(),
+            value,
+        }
+    }
+}
+
+pub type AlgorithmFactory = Arc<dyn Fn() -> Box<dyn AlgorithmComputation> + Send + Sync>;
+
+#[derive(Clone)]
+pub struct Algorithm {
+    pub name: String,
+    factory: AlgorithmFactory,
 }
 
-pub trait AlgorithmRenderer {
-    fn render(&mut self);
+impl Algorithm {
+    pub fn new(name: &str, factory: AlgorithmFactory) -> Self {
+        Self {
+            name: name.to_owned(),
+            factory,
+        }
+    }
+
+    pub fn instantiate(&self) -> Box<dyn AlgorithmComputation> {
+        (self.factory)()
+    }
 }
 
-pub trait AlgorithmGUI {
-    fn gui(&mut self);
+pub trait AlgorithmComputation: Send {
+    fn compute(&mut self, iteration: usize, data: &[DataType]) -> Vec<DataType>;
+
+    /// Tunable parameters together with their current values.
+    fn parameters(&self) -> Vec<Parameter> {
+        Vec::new()
+    }
+
+    fn set_parameter(&mut self, _name: &str, _value: f32) {}
+
+    fn seed(&mut self, _seed: u64) {}
+
+    /// Whether the algorithm steps the director fields of 3D lattices; runs with more than one
+    /// layer are refused otherwise.
+    fn supports_layers(&self) -> bool {
+        false
+    }
+
+    /// Boundaries and frozen cells, already sampled onto the lattice being simulated.
+    fn set_conditions(&mut self, _conditions: &LatticeConditions) {}
+
+    /// Settings the algorithm cannot honour as given and works around, e.g. a time step it has
+    /// to split, for the controls and batch runs to show.
+    fn warnings(&self) -> Vec<String> {
+        Vec::new()
+    }
+
+    /// Disturbs the state within `radius` cells of the fractional `position`, e.g. where the
+    /// display was clicked.
+    fn perturb(&mut self, _position: (f32, f32), _radius: f32) {}
+
+    /// Values shown at the ends of the colormap when the output is a scalar grid,
+    /// `None` to stretch the colormap over each frame.
+    fn scalar_range(&self) -> Option<(f32, f32)> {
+        None
+    }
+
+    /// Named scalar measurements of `data`, e.g. energy per site.
+    fn observables(&self, _data: &[DataType]) -> Vec<(String, f64)> {
+        Vec::new()
+    }
+
+    /// Curves derived from the run so far, plotted live and saved as `<name>.csv` with it.
+    fn tables(&self) -> Vec<Table> {
+        Vec::new()
+    }
 }
@@ -0,0 +1,40 @@
+use std::f32::consts::{FRAC_PI_2, PI, TAU};
+
+/// A topological defect of a director field.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Defect {
+    /// Plaquette centre in cells from the bottom-left corner of the lattice.
+    pub position: (f32, f32),
+    /// Winding number, +-1/2 for nematic disclinations and +-1 for XY vortices.
+    pub charge: f32,
+}
+
+/// Defects on the interior plaquettes of a row-major angle field.
+///
+/// Angle differences are taken modulo pi, so nematic half-integer disclinations are found as well
+/// as integer vortices of smooth polar fields.
+pub fn find_defects(angles: &[f32], (rows, cols): (usize, usize)) -> Vec<Defect> {
+    let wrap = |difference: f32| (difference + FRAC_PI_2).rem_euclid(PI) - FRAC_PI_2;
+    let mut defects = Vec::new();
+    for row in 0..rows.saturating_sub(1) {
+        for col in 0..cols.saturating_sub(1) {
+            let corners = [
+                angles[row * cols + col],
+                angles[row * cols + col + 1],
+                angles[(row + 1) * cols + col + 1],
+                angles[(row + 1) * cols + col],
+            ];
+            let winding = (0..4)
+                .map(|corner| wrap(corners[(corner + 1) % 4] - corners[corner]))
+                .sum::<f32>();
+            let charge = (2.0 * winding / TAU).round() / 2.0;
+            if charge != 0.0 {
+                defects.push(Defect {
+                    position: (col as f32 + 1.0, row as f32 + 1.0),
+                    charge,
+                });
+            }
+        }
+    }
+    defects
+}
@@ -0,0 +1,174 @@
+use std::f32::consts::PI;
+use std::fmt;
+
+/// Time dependence of the field amplitude. Time is measured in iterations.
+///
+/// The coupling is quadratic in the field, so only the magnitude of the waveform matters.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Waveform {
+    #[default]
+    Constant,
+    /// Off until `start`, on afterwards.
+    Step { start: f32 },
+    Sine { period: f32 },
+    /// On for the first half of every period and off for the second.
+    Square { period: f32 },
+}
+
+pub const WAVEFORM_KINDS: [&str; 4] = ["constant", "step", "sine", "square"];
+
+impl Waveform {
+    /// Parses `constant`, `step <start>`, `sine <period>` or `square <period>`.
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let (kind, numbers) = split_numbers(text)?;
+        match (kind, numbers.as_slice()) {
+            ("constant", []) => Ok(Self::Constant),
+            ("step", &[start]) => Ok(Self::Step { start }),
+            ("step", _) => Err("Expected: step <start>".to_owned()),
+            ("sine", &[period]) if period > 0.0 => Ok(Self::Sine { period }),
+            ("sine", _) => Err("Expected: sine <period>".to_owned()),
+            ("square", &[period]) if period > 0.0 => Ok(Self::Square { period }),
+            ("square", _) => Err("Expected: square <period>".to_owned()),
+            _ => Err(format!("Unknown waveform '{text}'")),
+        }
+    }
+
+    pub fn amplitude(&self, time: f32) -> f32 {
+        match *self {
+            Self::Constant => 1.0,
+            Self::Step { start } => (time >= start) as u8 as f32,
+            Self::Sine { period } => (2.0 * PI * time / period).sin(),
+            Self::Square { period } => ((time / period).fract() < 0.5) as u8 as f32,
+        }
+    }
+}
+
+impl fmt::Display for Waveform {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Constant => write!(f, "constant"),
+            Self::Step { start } => write!(f, "step {start}"),
+            Self::Sine { period } => write!(f, "sine {period}"),
+            Self::Square { period } => write!(f, "square {period}"),
+        }
+    }
+}
+
+/// Spatial dependence of the field amplitude. Positions are fractions of the lattice,
+/// `(0, 0)` being the bottom-left corner.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum FieldProfile {
+    #[default]
+    Uniform,
+    /// Grows linearly from zero at the left edge to full strength at the right edge.
+    RampX,
+    /// Grows linearly from zero at the bottom edge to full strength at the top edge.
+    RampY,
+    /// A Gaussian spot, e.g. a focused laser or a patterned electrode.
+    Spot { center: (f32, f32), radius: f32 },
+}
+
+pub const FIELD_PROFILE_KINDS: [&str; 4] = ["uniform", "ramp_x", "ramp_y", "spot"];
+
+impl FieldProfile {
+    /// Parses `uniform`, `ramp_x`, `ramp_y` or `spot <x> <y> <radius>`.
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let (kind, numbers) = split_numbers(text)?;
+        match (kind, numbers.as_slice()) {
+            ("uniform", []) => Ok(Self::Uniform),
+            ("ramp_x", []) => Ok(Self::RampX),
+            ("ramp_y", []) => Ok(Self::RampY),
+            ("spot", &[x, y, radius]) if radius > 0.0 => Ok(Self::Spot {
+                center: (x, y),
+                radius,
+            }),
+            ("spot", _) => Err("Expected: spot <x> <y> <radius>".to_owned()),
+            _ => Err(format!("Unknown field profile '{text}'")),
+        }
+    }
+
+    pub fn amplitude(&self, (x, y): (f32, f32)) -> f32 {
+        match *self {
+            Self::Uniform => 1.0,
+            Self::RampX => x,
+            Self::RampY => y,
+            Self::Spot { center, radius } => {
+                let (dx, dy) = (x - center.0, y - center.1);
+                (-(dx * dx + dy * dy) / (2.0 * radius * radius)).exp()
+            }
+        }
+    }
+}
+
+impl fmt::Display for FieldProfile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Uniform => write!(f, "uniform"),
+            Self::RampX => write!(f, "ramp_x"),
+            Self::RampY => write!(f, "ramp_y"),
+            Self::Spot { center, radius } => write!(f, "spot {} {} {radius}", center.0, center.1),
+        }
+    }
+}
+
+/// An applied electric or magnetic field in the lattice plane.
+/// Directors couple to it through `-chi (n . E)^2`, `chi` being the algorithm's anisotropy.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct ExternalField {
+    /// Direction of the field in radians.
+    pub angle: f32,
+    pub magnitude: f32,
+    pub waveform: Waveform,
+    pub profile: FieldProfile,
+}
+
+impl ExternalField {
+    pub fn is_off(&self) -> bool {
+        self.magnitude == 0.0
+    }
+
+    /// Sets `angle`, `magnitude`, `waveform` or `profile` from its text form.
+    pub fn apply(&mut self, key: &str, value: &str) -> Result<(), String> {
+        let number = || {
+            value
+                .parse::<f32>()
+                .map_err(|_| format!("Invalid value '{value}' for 'field.{key}'"))
+        };
+        match key {
+            "angle" => self.angle = number()?,
+            "magnitude" => self.magnitude = number()?,
+            "waveform" => self.waveform = Waveform::parse(value)?,
+            "profile" => self.profile = FieldProfile::parse(value)?,
+            _ => return Err(format!("Unknown field setting 'field.{key}'")),
+        }
+        Ok(())
+    }
+
+    /// Field strength at `time` and the fractional lattice position `position`.
+    pub fn strength(&self, time: f32, position: (f32, f32)) -> f32 {
+        self.magnitude * self.waveform.amplitude(time) * self.profile.amplitude(position)
+    }
+
+    /// `(n . E)^2` for a director at `angle`.
+    pub fn projection_squared(&self, time: f32, position: (f32, f32), angle: f32) -> f32 {
+        let projection = self.strength(time, position) * (angle - self.angle).cos();
+        projection * projection
+    }
+
+    /// The settings as `key = value` lines.
+    pub fn describe(&self) -> String {
+        format!(
+            "field.angle = {}\nfield.magnitude = {}\nfield.waveform = {}\nfield.profile = {}\n",
+            self.angle, self.magnitude, self.waveform, self.profile
+        )
+    }
+}
+
+pub(crate) fn split_numbers(text: &str) -> Result<(&str, Vec<f32>), String> {
+    let mut tokens = text.split_whitespace();
+    let kind = tokens.next().unwrap_or_default();
+    let numbers = tokens
+        .map(|t| t.parse::<f32>().map_err(|_| format!("Invalid number '{t}'")))
+        .collect::<Result<Vec<f32>, String>>()?;
+    Ok((kind, numbers))
+}
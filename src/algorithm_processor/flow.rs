@@ -0,0 +1,37 @@
+use crate::algorithm_processor::Vectors;
+
+/// Vectors of a row-major `(rows, cols)` lattice with its dimensions.
+pub type VectorField = (Vectors, (usize, usize));
+
+/// Vorticity `du_y/dx - du_x/dy` of a row-major velocity field by central differences on a
+/// periodic lattice.
+pub fn vorticity(velocity: &[[f32; 2]], (rows, cols): (usize, usize)) -> Vec<f32> {
+    (0..velocity.len())
+        .map(|index| {
+            let (row, col) = (index / cols, index % cols);
+            let left = velocity[row * cols + (col + cols - 1) % cols];
+            let right = velocity[row * cols + (col + 1) % cols];
+            let down = velocity[(row + rows - 1) % rows * cols + col];
+            let up = velocity[(row + 1) % rows * cols + col];
+            0.5 * ((right[1] - left[1]) - (up[0] - down[0]))
+        })
+        .collect()
+}
+
+/// Gradient of a row-major scalar field, by central differences inside and one-sided ones at
+/// the edges.
+pub fn gradient(values: &[f32], (rows, cols): (usize, usize)) -> Vectors {
+    let difference = |at: usize, len: usize, index: &dyn Fn(usize) -> usize| {
+        let (low, high) = (at.saturating_sub(1), (at + 1).min(len - 1));
+        if high == low { 0.0 } else { (values[index(high)] - values[index(low)]) / (high - low) as f32 }
+    };
+    (0..values.len())
+        .map(|index| {
+            let (row, col) = (index / cols, index % cols);
+            [
+                difference(col, cols, &|col| row * cols + col),
+                difference(row, rows, &|row| row * cols + col),
+            ]
+        })
+        .collect()
+}
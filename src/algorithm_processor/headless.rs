@@ -0,0 +1,314 @@
+use crate::algorithm_processor::*;
+use crate::analysis::{Analyses, Coarsening, DefectTracker, FrankMaps, RunStatistics};
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+/// Everything needed to reproduce a single run without the GUI.
+#[derive(Debug, Clone)]
+pub struct RunConfig {
+    pub algorithm: String,
+    pub lattice_dimension: (usize, usize),
+    /// Number of layers along z; more than one makes the lattice 3D.
+    pub layers: usize,
+    pub iterations: usize,
+    pub sample_interval: usize,
+    pub seed: u64,
+    pub initial_state: InitialState,
+    pub conditions: LatticeConditions,
+    pub parameters: Vec<Parameter>,
+    /// Parameters that follow a schedule instead of staying fixed.
+    pub protocols: Vec<Protocol>,
+    pub analyses: Analyses,
+}
+
+impl Default for RunConfig {
+    fn default() -> Self {
+        Self {
+            algorithm: String::new(),
+            lattice_dimension: (64, 64),
+            layers: 1,
+            iterations: 1000,
+            sample_interval: 1,
+            seed: 0,
+            initial_state: InitialState::default(),
+            conditions: LatticeConditions::default(),
+            parameters: Vec::new(),
+            protocols: Vec::new(),
+            analyses: Analyses::default(),
+        }
+    }
+}
+
+impl RunConfig {
+    pub fn set_parameter(&mut self, name: &str, value: f32) {
+        match self.parameters.iter_mut().find(|p| p.name == name) {
+            Some(parameter) => parameter.value = value,
+            None => self.parameters.push(Parameter::new(name, value)),
+        }
+    }
+
+    pub fn parameter(&self, name: &str) -> Option<f32> {
+        self.parameters.iter().find(|p| p.name == name).map(|p| p.value)
+    }
+
+    pub fn sites(&self) -> usize {
+        self.lattice_dimension.0 * self.lattice_dimension.1 * self.layers
+    }
+
+    /// The starting data: angles on a 2D lattice, unit directors on a 3D one.
+    pub fn initial_data(&self, rng: &mut impl rand::Rng) -> Vec<DataType> {
+        let (rows, cols) = self.lattice_dimension;
+        if self.layers > 1 {
+            let dimensions = (rows, cols, self.layers);
+            vec![DataType::DirectorField(
+                self.initial_state.generate_directors(dimensions, rng),
+                dimensions,
+            )]
+        } else {
+            vec![DataType::OrientedGrid(
+                self.initial_state.generate(self.lattice_dimension, rng),
+                self.lattice_dimension,
+            )]
+        }
+    }
+
+    /// Refuses runs `algorithm` cannot carry out, before any work starts.
+    pub fn check(&self, algorithm: &Algorithm) -> Result<(), String> {
+        if self.layers > 1 && !algorithm.instantiate().supports_layers() {
+            return Err(format!("{} runs on 2D lattices only", algorithm.name));
+        }
+        Ok(())
+    }
+
+    /// What `algorithm` reports about these settings before the run, see
+    /// `AlgorithmComputation::warnings`.
+    pub fn warnings(&self, algorithm: &Algorithm) -> Vec<String> {
+        let mut computation = algorithm.instantiate();
+        computation.set_conditions(&self.conditions.for_dimensions(self.lattice_dimension));
+        for parameter in &self.parameters {
+            computation.set_parameter(&parameter.name, parameter.value);
+        }
+        let mut warnings = protocol_warnings(&self.protocols, computation.as_ref());
+        warnings.extend(computation.warnings());
+        warnings
+    }
+
+    /// Copy of the configuration with every parameter the algorithm exposes filled in.
+    pub fn with_defaults(&self, algorithm: &Algorithm) -> RunConfig {
+        let mut config = self.clone();
+        for parameter in algorithm.instantiate().parameters() {
+            if !config.parameters.iter().any(|p| p.name == parameter.name) {
+                config.parameters.push(parameter);
+            }
+        }
+        config
+    }
+
+    /// Serialises the run as `key = value` lines, the format batch configs are written in.
+    pub fn describe(&self) -> String {
+        let mut lattice = format!("{}x{}", self.lattice_dimension.0, self.lattice_dimension.1);
+        if self.layers > 1 {
+            lattice.push_str(&format!("x{}", self.layers));
+        }
+        let mut text = format!(
+            "algorithm = {}\nlattice = {lattice}\niterations = {}\nsample_interval = {}\nseed = {}\ninitial_state = {}\n",
+            self.algorithm,
+            self.iterations,
+            self.sample_interval,
+            self.seed,
+            self.initial_state
+        );
+        for (edge, boundary) in self.conditions.boundaries.edges() {
+            if self.layers == 1 && (edge == "front" || edge == "back") {
+                continue;
+            }
+            text.push_str(&format!("boundary.{edge} = {boundary}\n"));
+        }
+        if let Some(path) = self.conditions.mask.as_ref().and_then(|mask| mask.path.as_ref()) {
+            text.push_str(&format!("mask = {}\n", path.display()));
+        }
+        text.push_str(&self.conditions.field.describe());
+        for parameter in &self.parameters {
+            text.push_str(&format!("{} = {}\n", parameter.name, parameter.value));
+        }
+        for protocol in &self.protocols {
+            text.push_str(&format!("protocol.{} = {}\n", protocol.parameter, protocol.schedule));
+        }
+        text.push_str(&self.analyses.describe());
+        text
+    }
+}
+
+/// A finished run: what was run and the observables it produced.
+pub struct RunRecord {
+    pub config: RunConfig,
+    pub samples: Vec<Observables>,
+    /// The algorithm's tables at the end of the run.
+    pub tables: Vec<Table>,
+    /// Splay, twist and bend of the last frame, while the elastic analysis is on.
+    pub elastic_maps: Option<FrankMaps>,
+    /// Defect trajectories of the whole run, while tracking is on.
+    pub defect_tracks: Option<DefectTracker>,
+}
+
+impl RunRecord {
+    /// Fluctuation responses need a fixed temperature, so they are left out when it is scheduled.
+    pub fn statistics(&self) -> RunStatistics {
+        let temperature = self
+            .config
+            .parameter("temperature")
+            .filter(|_| !self.config.protocols.iter().any(|p| p.parameter == "temperature"));
+        RunStatistics::new(&self.samples, temperature, self.config.sites())
+    }
+
+    /// Writes `config.txt`, `observables.csv`, `statistics.csv` and a `<name>.csv` per table into
+    /// `directory`, plus `mask.png` when the mask was painted rather than loaded and
+    /// `elastic_maps.csv` and the defect tracks when those analyses were on.
+    pub fn save(&self, directory: &Path) -> io::Result<RunStatistics> {
+        let statistics = self.statistics();
+        fs::create_dir_all(directory)?;
+
+        let mut config = self.config.clone();
+        if let Some(mask) = config.conditions.mask.as_mut().filter(|mask| mask.path.is_none()) {
+            mask.save(&directory.join("mask.png")).map_err(io::Error::other)?;
+        }
+        fs::write(directory.join("config.txt"), config.describe())?;
+        write_csv(&directory.join("observables.csv"), &self.samples)?;
+        statistics.write_csv(&directory.join("statistics.csv"))?;
+        for table in &self.tables {
+            table.write_csv(&directory.join(format!("{}.csv", table.name)))?;
+        }
+        if let Some(maps) = &self.elastic_maps {
+            maps.write_csv(&directory.join("elastic_maps.csv"))?;
+        }
+        if let Some(tracker) = &self.defect_tracks {
+            tracker.save(directory)?;
+        }
+        Ok(statistics)
+    }
+}
+
+/// A run in progress, stepped by `run_headless` and by the GUI worker alike.
+pub struct RunState {
+    /// The run's settings with every parameter of the algorithm filled in.
+    pub config: RunConfig,
+    pub computation: Box<dyn AlgorithmComputation>,
+    pub data: Vec<DataType>,
+    pub samples: Vec<Observables>,
+    pub tracker: Option<DefectTracker>,
+    pub coarsening: Option<Coarsening>,
+    protocol_warnings: Vec<String>,
+}
+
+impl RunState {
+    pub fn new(algorithm: &Algorithm, config: &RunConfig) -> Self {
+        let mut config = config.with_defaults(algorithm);
+        config.conditions = config.conditions.for_dimensions(config.lattice_dimension);
+        let mut computation = algorithm.instantiate();
+        computation.seed(config.seed);
+        computation.set_conditions(&config.conditions);
+        for parameter in &config.parameters {
+            computation.set_parameter(&parameter.name, parameter.value);
+        }
+        let protocol_warnings = protocol_warnings(&config.protocols, computation.as_ref());
+        let mut rng = StdRng::seed_from_u64(config.seed);
+        let data = config.initial_data(&mut rng);
+        Self {
+            samples: Vec::with_capacity(config.iterations / config.sample_interval.max(1)),
+            tracker: config.analyses.tracker(&config.conditions.boundaries),
+            coarsening: config.analyses.coarsening(),
+            protocol_warnings,
+            computation,
+            data,
+            config,
+        }
+    }
+
+    /// Applies the protocols for `iteration`, computes it and feeds the tracker. Every
+    /// `sample_interval` iterations the observables are gathered, kept and returned.
+    pub fn step(&mut self, iteration: usize) -> Option<Observables> {
+        let scheduled = apply_protocols(
+            &self.config.protocols,
+            iteration,
+            self.computation.as_mut(),
+            &mut self.config.conditions,
+        );
+        self.data = self.computation.compute(iteration, &self.data);
+        if let Some(tracker) = &mut self.tracker {
+            tracker.record(iteration, &self.data);
+        }
+        if !(iteration + 1).is_multiple_of(self.config.sample_interval.max(1)) {
+            return None;
+        }
+
+        let analyses = &self.config.analyses;
+        let mut values = self.computation.observables(&self.data);
+        values.extend(scheduled);
+        let correlation = analyses.correlation(&self.data);
+        values.extend(analyses.observables(&self.data, correlation.as_ref()));
+        values.extend(self.tracker.iter().flat_map(|tracker| tracker.observables()));
+        if let Some(coarsening) = &mut self.coarsening {
+            values.extend(coarsening.record(iteration, &self.data, analyses.spectrum, correlation.as_ref()));
+        }
+        let sample = Observables::new(iteration, values);
+        self.samples.push(sample.clone());
+        Some(sample)
+    }
+
+    /// Applies new boundaries or a new mask from the next iteration on.
+    pub fn set_conditions(&mut self, conditions: &LatticeConditions) {
+        self.config.conditions = conditions.for_dimensions(self.config.lattice_dimension);
+        self.computation.set_conditions(&self.config.conditions);
+        if let Some(tracker) = &mut self.tracker {
+            tracker.set_boundaries(&self.config.conditions.boundaries);
+        }
+    }
+
+    pub fn set_parameter(&mut self, name: &str, value: f32) {
+        self.computation.set_parameter(name, value);
+        self.config.set_parameter(name, value);
+    }
+
+    /// Protocol warnings followed by what the algorithm currently reports.
+    pub fn warnings(&self) -> Vec<String> {
+        let mut warnings = self.protocol_warnings.clone();
+        warnings.extend(self.computation.warnings());
+        warnings
+    }
+
+    /// The algorithm's tables and those of the analyses for the current data.
+    pub fn tables(&self) -> Vec<Table> {
+        let analyses = &self.config.analyses;
+        let mut tables = self.computation.tables();
+        tables.extend(analyses.tables(&self.data, analyses.correlation(&self.data).as_ref()));
+        tables.extend(self.coarsening.iter().map(|coarsening| coarsening.table()));
+        tables
+    }
+
+    pub fn finish(self) -> RunRecord {
+        RunRecord {
+            tables: self.tables(),
+            elastic_maps: elastic_maps(&self.config.analyses, &self.data),
+            config: self.config,
+            samples: self.samples,
+            defect_tracks: self.tracker,
+        }
+    }
+}
+
+/// Runs `algorithm` to completion on the calling thread.
+pub fn run_headless(algorithm: &Algorithm, config: &RunConfig) -> RunRecord {
+    let mut state = RunState::new(algorithm, config);
+    for iteration in 0..config.iterations {
+        state.step(iteration);
+    }
+    state.finish()
+}
+
+/// Frank maps of the last frame of a run with the elastic analysis on.
+pub fn elastic_maps(analyses: &Analyses, data: &[DataType]) -> Option<FrankMaps> {
+    if analyses.elastic { FrankMaps::from_data(data) } else { None }
+}
@@ -0,0 +1,273 @@
+use rand::Rng;
+use std::f32::consts::PI;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+/// Starting configuration of an oriented lattice.
+///
+/// Positions are fractions of the lattice, `(0, 0)` being the bottom-left corner,
+/// and angles are in radians.
+#[derive(Debug, Clone, Default)]
+pub enum InitialState {
+    /// Uniformly random directors in `[0, pi)`.
+    #[default]
+    Random,
+    Aligned { angle: f32 },
+    /// The director turns from `start` to `end` along the x axis.
+    Twisted { start: f32, end: f32 },
+    /// The director fans out by `spread` along the y axis, splaying around the x axis.
+    Splayed { spread: f32 },
+    /// A `+charge` defect at `first` and a `-charge` defect at `second`.
+    DefectPair {
+        charge: f32,
+        first: (f32, f32),
+        second: (f32, f32),
+    },
+    /// Upper and lower halves aligned along different angles.
+    Bilayer { top: f32, bottom: f32 },
+    /// Angles loaded from a PNG (luminance mapped to `[0, pi)`) or a 2D `.npy` array.
+    File {
+        path: PathBuf,
+        grid: Arc<Vec<f32>>,
+        dimensions: (usize, usize),
+    },
+}
+
+pub const INITIAL_STATE_KINDS: [&str; 7] = [
+    "random",
+    "aligned",
+    "twisted",
+    "splayed",
+    "defect_pair",
+    "bilayer",
+    "file",
+];
+
+impl InitialState {
+    /// Parses the `<kind> <arguments...>` form written by `Display`, e.g. `aligned 0.5`
+    /// or `defect_pair 0.5 0.3 0.5 0.7 0.5`.
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let text = text.trim();
+        let (kind, arguments) = text.split_once(char::is_whitespace).unwrap_or((text, ""));
+        if kind == "file" {
+            return Self::load(Path::new(arguments.trim()));
+        }
+
+        let numbers = arguments
+            .split_whitespace()
+            .map(|t| t.parse::<f32>().map_err(|_| format!("Invalid number '{t}'")))
+            .collect::<Result<Vec<f32>, String>>()?;
+        let usage = |arguments: &str| format!("Expected: {kind} {arguments}");
+
+        match (kind, numbers.as_slice()) {
+            ("random", []) => Ok(Self::Random),
+            ("aligned", &[angle]) => Ok(Self::Aligned { angle }),
+            ("aligned", _) => Err(usage("<angle>")),
+            ("twisted", &[start, end]) => Ok(Self::Twisted { start, end }),
+            ("twisted", _) => Err(usage("<start angle> <end angle>")),
+            ("splayed", &[spread]) => Ok(Self::Splayed { spread }),
+            ("splayed", _) => Err(usage("<spread>")),
+            ("defect_pair", &[charge, x1, y1, x2, y2]) => Ok(Self::DefectPair {
+                charge,
+                first: (x1, y1),
+                second: (x2, y2),
+            }),
+            ("defect_pair", _) => Err(usage("<charge> <x1> <y1> <x2> <y2>")),
+            ("bilayer", &[top, bottom]) => Ok(Self::Bilayer { top, bottom }),
+            ("bilayer", _) => Err(usage("<top angle> <bottom angle>")),
+            _ => Err(format!("Unknown initial state '{kind}'")),
+        }
+    }
+
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let is_npy = path.extension().is_some_and(|e| e.eq_ignore_ascii_case("npy"));
+        let (grid, dimensions) = if is_npy {
+            load_npy(path)?
+        } else {
+            load_image(path)?
+        };
+        Ok(Self::File {
+            path: path.to_owned(),
+            grid: Arc::new(grid),
+            dimensions,
+        })
+    }
+
+    pub fn generate(&self, dimensions: (usize, usize), rng: &mut impl Rng) -> Vec<f32> {
+        let (rows, cols) = dimensions;
+        let mut angles = Vec::with_capacity(rows * cols);
+        for row in 0..rows {
+            for col in 0..cols {
+                let x = (col as f32 + 0.5) / cols as f32;
+                let y = (row as f32 + 0.5) / rows as f32;
+                angles.push(self.angle_at(x, y, (row, col), dimensions, rng));
+            }
+        }
+        angles
+    }
+
+    /// Unit directors for a `(rows, cols, layers)` lattice. `random` is isotropic in 3D, the
+    /// other presets lie in the xy plane and repeat through every layer.
+    pub fn generate_directors(&self, dimensions: (usize, usize, usize), rng: &mut impl Rng) -> Vec<[f32; 3]> {
+        let (rows, cols, layers) = dimensions;
+        if let Self::Random = self {
+            return (0..rows * cols * layers)
+                .map(|_| {
+                    let z: f32 = rng.random_range(-1.0..1.0);
+                    let azimuth: f32 = rng.random_range(0.0..2.0 * PI);
+                    let planar = (1.0 - z * z).sqrt();
+                    [planar * azimuth.cos(), planar * azimuth.sin(), z]
+                })
+                .collect();
+        }
+
+        let plane = self.generate((rows, cols), rng);
+        (0..layers)
+            .flat_map(|_| plane.iter().map(|angle| [angle.cos(), angle.sin(), 0.0]))
+            .collect()
+    }
+
+    fn angle_at(
+        &self,
+        x: f32,
+        y: f32,
+        (row, col): (usize, usize),
+        (rows, cols): (usize, usize),
+        rng: &mut impl Rng,
+    ) -> f32 {
+        match self {
+            Self::Random => rng.random_range(0.0..PI),
+            Self::Aligned { angle } => *angle,
+            Self::Twisted { start, end } => start + (end - start) * x,
+            Self::Splayed { spread } => spread * (y - 0.5),
+            Self::DefectPair {
+                charge,
+                first,
+                second,
+            } => {
+                // Aspect-correct so that defects on non-square lattices stay round.
+                let aspect = cols as f32 / rows as f32;
+                let winding = |(cx, cy): (f32, f32)| ((y - cy) / aspect).atan2(x - cx);
+                charge * (winding(*first) - winding(*second))
+            }
+            Self::Bilayer { top, bottom } => {
+                if y >= 0.5 {
+                    *top
+                } else {
+                    *bottom
+                }
+            }
+            Self::File {
+                grid, dimensions, ..
+            } => {
+                let source_row = row * dimensions.0 / rows;
+                let source_col = col * dimensions.1 / cols;
+                grid[source_row * dimensions.1 + source_col]
+            }
+        }
+    }
+}
+
+impl fmt::Display for InitialState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Random => write!(f, "random"),
+            Self::Aligned { angle } => write!(f, "aligned {angle}"),
+            Self::Twisted { start, end } => write!(f, "twisted {start} {end}"),
+            Self::Splayed { spread } => write!(f, "splayed {spread}"),
+            Self::DefectPair {
+                charge,
+                first,
+                second,
+            } => write!(
+                f,
+                "defect_pair {charge} {} {} {} {}",
+                first.0, first.1, second.0, second.1
+            ),
+            Self::Bilayer { top, bottom } => write!(f, "bilayer {top} {bottom}"),
+            Self::File { path, .. } => write!(f, "file {}", path.display()),
+        }
+    }
+}
+
+/// Row-major angles with row 0 at the bottom, matching the lattice layout.
+fn load_image(path: &Path) -> Result<(Vec<f32>, (usize, usize)), String> {
+    let image = image::open(path)
+        .map_err(|e| format!("Unable to load {}: {e}", path.display()))?
+        .to_luma32f();
+    let (cols, rows) = (image.width() as usize, image.height() as usize);
+    if rows == 0 || cols == 0 {
+        return Err(format!("{} is an empty image", path.display()));
+    }
+    let mut grid = Vec::with_capacity(rows * cols);
+    for row in (0..rows).rev() {
+        for col in 0..cols {
+            let luminance = image.get_pixel(col as u32, row as u32).0[0].clamp(0.0, 1.0);
+            grid.push(luminance * PI);
+        }
+    }
+    Ok((grid, (rows, cols)))
+}
+
+/// Reads a little-endian, C-ordered 2D `float32` or `float64` array of angles,
+/// already in lattice row order.
+fn load_npy(path: &Path) -> Result<(Vec<f32>, (usize, usize)), String> {
+    let bytes = std::fs::read(path).map_err(|e| format!("Unable to read {}: {e}", path.display()))?;
+    let invalid = |reason: &str| format!("{} is not a supported .npy file: {reason}", path.display());
+    if bytes.len() < 10 || &bytes[..6] != b"\x93NUMPY" {
+        return Err(invalid("missing magic string"));
+    }
+
+    let (header_start, header_length) = match bytes[6] {
+        1 => (10, u16::from_le_bytes([bytes[8], bytes[9]]) as usize),
+        2 | 3 if bytes.len() >= 12 => (
+            12,
+            u32::from_le_bytes([bytes[8], bytes[9], bytes[10], bytes[11]]) as usize,
+        ),
+        _ => return Err(invalid("unknown format version")),
+    };
+    let data_start = header_start + header_length;
+    let header = bytes
+        .get(header_start..data_start)
+        .and_then(|h| std::str::from_utf8(h).ok())
+        .ok_or_else(|| invalid("truncated header"))?;
+
+    if header.contains("'fortran_order': True") {
+        return Err(invalid("Fortran ordered arrays are not supported"));
+    }
+    let shape = header
+        .split_once("'shape':")
+        .and_then(|(_, rest)| rest.split_once('('))
+        .and_then(|(_, rest)| rest.split_once(')'))
+        .map(|(shape, _)| {
+            shape
+                .split(',')
+                .filter(|d| !d.trim().is_empty())
+                .map(|d| d.trim().parse::<usize>())
+                .collect::<Result<Vec<usize>, _>>()
+        })
+        .and_then(Result::ok)
+        .ok_or_else(|| invalid("unreadable shape"))?;
+    let &[rows, cols] = shape.as_slice() else {
+        return Err(invalid("the array must be two dimensional"));
+    };
+
+    let data = &bytes[data_start..];
+    let values: Vec<f32> = if header.contains("'<f4'") {
+        data.chunks_exact(4)
+            .map(|c| f32::from_le_bytes([c[0], c[1], c[2], c[3]]))
+            .collect()
+    } else if header.contains("'<f8'") {
+        data.chunks_exact(8)
+            .map(|c| f64::from_le_bytes([c[0], c[1], c[2], c[3], c[4], c[5], c[6], c[7]]) as f32)
+            .collect()
+    } else {
+        return Err(invalid("only <f4 and <f8 data types are supported"));
+    };
+    if values.len() < rows * cols || rows == 0 || cols == 0 {
+        return Err(invalid("the data is shorter than its shape"));
+    }
+
+    Ok((values[..rows * cols].to_vec(), (rows, cols)))
+}
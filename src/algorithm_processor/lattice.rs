@@ -0,0 +1,420 @@
+use crate::algorithm_processor::ExternalField;
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+/// Treatment of one lattice edge.
+///
+/// `value` is what the edge holds the field at: an anchoring angle for director and spin models,
+/// a wall temperature for the heat equation. An axis wraps around only when both of its edges are
+/// `Periodic`; a lone periodic edge behaves as `Free`.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Boundary {
+    #[default]
+    Periodic,
+    /// Edge sites simply have fewer neighbours.
+    Free,
+    /// Strong anchoring: a ghost layer held at `value` and coupled like a bulk neighbour.
+    Fixed { value: f32 },
+    /// Coupling of edge sites to `value` with a surface `strength`, e.g. the Rapini-Papoular energy
+    /// `-strength * P2(cos(theta - value))` of directors.
+    Weak { value: f32, strength: f32 },
+}
+
+pub const BOUNDARY_KINDS: [&str; 4] = ["periodic", "free", "fixed", "weak"];
+
+impl Boundary {
+    /// Parses `periodic`, `free`, `fixed <value>` or `weak <value> <strength>`.
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut tokens = text.split_whitespace();
+        let kind = tokens.next().unwrap_or_default();
+        let numbers = tokens
+            .map(|t| t.parse::<f32>().map_err(|_| format!("Invalid number '{t}'")))
+            .collect::<Result<Vec<f32>, String>>()?;
+
+        match (kind, numbers.as_slice()) {
+            ("periodic", []) => Ok(Self::Periodic),
+            ("free", []) => Ok(Self::Free),
+            ("fixed", &[value]) => Ok(Self::Fixed { value }),
+            ("fixed", _) => Err("Expected: fixed <value>".to_owned()),
+            ("weak", &[value, strength]) => Ok(Self::Weak { value, strength }),
+            ("weak", _) => Err("Expected: weak <value> <strength>".to_owned()),
+            _ => Err(format!("Unknown boundary '{text}'")),
+        }
+    }
+}
+
+impl fmt::Display for Boundary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Periodic => write!(f, "periodic"),
+            Self::Free => write!(f, "free"),
+            Self::Fixed { value } => write!(f, "fixed {value}"),
+            Self::Weak { value, strength } => write!(f, "weak {value} {strength}"),
+        }
+    }
+}
+
+/// Edges of the lattice. `front` (layer 0) and `back` only matter for 3D lattices, where
+/// anchoring angles lie in the xy plane.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Boundaries {
+    pub left: Boundary,
+    pub right: Boundary,
+    pub bottom: Boundary,
+    pub top: Boundary,
+    pub front: Boundary,
+    pub back: Boundary,
+}
+
+impl Boundaries {
+    /// Whether the x and y axes wrap around.
+    pub fn periodic(&self) -> (bool, bool) {
+        (
+            self.left == Boundary::Periodic && self.right == Boundary::Periodic,
+            self.bottom == Boundary::Periodic && self.top == Boundary::Periodic,
+        )
+    }
+
+    pub fn edges(&self) -> [(&'static str, Boundary); 6] {
+        [
+            ("left", self.left),
+            ("right", self.right),
+            ("bottom", self.bottom),
+            ("top", self.top),
+            ("front", self.front),
+            ("back", self.back),
+        ]
+    }
+
+    pub fn edge_mut(&mut self, edge: &str) -> Option<&mut Boundary> {
+        match edge {
+            "left" => Some(&mut self.left),
+            "right" => Some(&mut self.right),
+            "bottom" => Some(&mut self.bottom),
+            "top" => Some(&mut self.top),
+            "front" => Some(&mut self.front),
+            "back" => Some(&mut self.back),
+            _ => None,
+        }
+    }
+}
+
+/// Cells excluded from updates, e.g. walls or colloidal inclusions.
+/// Frozen cells keep their angle but still act as neighbours.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Mask {
+    pub frozen: Vec<bool>,
+    pub dimensions: (usize, usize),
+    /// Where the mask was loaded from or last saved to.
+    pub path: Option<PathBuf>,
+}
+
+impl Mask {
+    pub fn new(dimensions: (usize, usize)) -> Self {
+        Self {
+            frozen: vec![false; dimensions.0 * dimensions.1],
+            dimensions,
+            path: None,
+        }
+    }
+
+    /// Dark pixels are frozen. The image is stretched over the lattice on use.
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let image = image::open(path)
+            .map_err(|e| format!("Unable to load {}: {e}", path.display()))?
+            .to_luma8();
+        let (cols, rows) = (image.width() as usize, image.height() as usize);
+        if rows == 0 || cols == 0 {
+            return Err(format!("{} is an empty image", path.display()));
+        }
+        let mut frozen = Vec::with_capacity(rows * cols);
+        for row in (0..rows).rev() {
+            for col in 0..cols {
+                frozen.push(image.get_pixel(col as u32, row as u32).0[0] < 128);
+            }
+        }
+        Ok(Self {
+            frozen,
+            dimensions: (rows, cols),
+            path: Some(path.to_owned()),
+        })
+    }
+
+    pub fn save(&mut self, path: &Path) -> Result<(), String> {
+        let (rows, cols) = self.dimensions;
+        let image = image::GrayImage::from_fn(cols as u32, rows as u32, |col, row| {
+            let frozen = self.frozen[(rows - 1 - row as usize) * cols + col as usize];
+            image::Luma([if frozen { 0 } else { 255 }])
+        });
+        image
+            .save(path)
+            .map_err(|e| format!("Unable to save {}: {e}", path.display()))?;
+        self.path = Some(path.to_owned());
+        Ok(())
+    }
+
+    /// The mask sampled onto a lattice of `dimensions`.
+    pub fn resampled(&self, dimensions: (usize, usize)) -> Mask {
+        if dimensions == self.dimensions {
+            return self.clone();
+        }
+        Mask {
+            frozen: resample(&self.frozen, self.dimensions, dimensions),
+            dimensions,
+            path: self.path.clone(),
+        }
+    }
+
+    /// Sets every cell within `radius` cells of the fractional position `(x, y)`.
+    pub fn paint(&mut self, position: (f32, f32), radius: f32, frozen: bool) {
+        for index in brush(self.dimensions, position, radius) {
+            self.frozen[index] = frozen;
+        }
+        self.path = None;
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Neighbour {
+    Site(usize),
+    /// A boundary that pulls towards `value` with the given coupling `strength`.
+    Anchor { value: f32, strength: f32 },
+}
+
+/// Painted heat sources (positive rates) and sinks (negative rates), one rate per cell.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SourceMap {
+    pub rates: Vec<f32>,
+    pub dimensions: (usize, usize),
+}
+
+impl SourceMap {
+    pub fn new(dimensions: (usize, usize)) -> Self {
+        Self {
+            rates: vec![0.0; dimensions.0 * dimensions.1],
+            dimensions,
+        }
+    }
+
+    /// The map sampled onto a lattice of `dimensions`.
+    pub fn resampled(&self, dimensions: (usize, usize)) -> SourceMap {
+        if dimensions == self.dimensions {
+            return self.clone();
+        }
+        SourceMap {
+            rates: resample(&self.rates, self.dimensions, dimensions),
+            dimensions,
+        }
+    }
+
+    /// Sets the rate of every cell within `radius` cells of the fractional position `(x, y)`.
+    pub fn paint(&mut self, position: (f32, f32), radius: f32, rate: f32) {
+        for index in brush(self.dimensions, position, radius) {
+            self.rates[index] = rate;
+        }
+    }
+}
+
+/// Nearest-cell sampling of a per-cell grid of `from` dimensions onto `to`.
+fn resample<T: Copy>(cells: &[T], from: (usize, usize), to: (usize, usize)) -> Vec<T> {
+    let (rows, cols) = to;
+    let mut resampled = Vec::with_capacity(rows * cols);
+    for row in 0..rows {
+        for col in 0..cols {
+            resampled.push(cells[row * from.0 / rows * from.1 + col * from.1 / cols]);
+        }
+    }
+    resampled
+}
+
+/// Indices of the cells whose centres lie within `radius` cells of the fractional position `(x, y)`.
+fn brush((rows, cols): (usize, usize), (x, y): (f32, f32), radius: f32) -> impl Iterator<Item = usize> {
+    let (center_col, center_row) = (x * cols as f32, y * rows as f32);
+    (0..rows * cols).filter(move |index| {
+        let dx = (index % cols) as f32 + 0.5 - center_col;
+        let dy = (index / cols) as f32 + 0.5 - center_row;
+        dx * dx + dy * dy <= radius * radius
+    })
+}
+
+/// Boundaries, frozen cells, applied field and sources of a lattice simulation.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct LatticeConditions {
+    pub boundaries: Boundaries,
+    pub mask: Option<Mask>,
+    pub field: ExternalField,
+    /// Only read by scalar transport algorithms such as the heat equation.
+    pub sources: Option<SourceMap>,
+}
+
+impl LatticeConditions {
+    pub fn is_frozen(&self, index: usize) -> bool {
+        self.mask
+            .as_ref()
+            .is_some_and(|mask| mask.frozen.get(index).copied().unwrap_or(false))
+    }
+
+    pub fn source_rate(&self, index: usize) -> f32 {
+        self.sources
+            .as_ref()
+            .map_or(0.0, |sources| sources.rates.get(index).copied().unwrap_or(0.0))
+    }
+
+    /// Copy with the mask and sources sampled onto a lattice of `dimensions`.
+    pub fn for_dimensions(&self, dimensions: (usize, usize)) -> Self {
+        Self {
+            boundaries: self.boundaries,
+            mask: self.mask.as_ref().map(|mask| mask.resampled(dimensions)),
+            field: self.field,
+            sources: self.sources.as_ref().map(|sources| sources.resampled(dimensions)),
+        }
+    }
+
+    /// Right, left, up and down neighbours of `index` on a row-major lattice.
+    /// `coupling` is the bulk coupling a strongly anchored ghost layer uses.
+    pub fn neighbours(
+        &self,
+        dimensions: (usize, usize),
+        index: usize,
+        coupling: f32,
+    ) -> impl Iterator<Item = Neighbour> {
+        let (rows, cols) = dimensions;
+        let (row, col) = (index / cols, index % cols);
+        let b = &self.boundaries;
+        let (periodic_x, periodic_y) = b.periodic();
+
+        let step = |at_edge, periodic, boundary, inside, wrapped| {
+            step(at_edge, periodic, boundary, inside, wrapped, coupling)
+        };
+
+        [
+            step(
+                col + 1 == cols,
+                periodic_x,
+                b.right,
+                index + 1,
+                row * cols,
+            ),
+            step(
+                col == 0,
+                periodic_x,
+                b.left,
+                index.wrapping_sub(1),
+                row * cols + cols - 1,
+            ),
+            step(
+                row + 1 == rows,
+                periodic_y,
+                b.top,
+                index + cols,
+                col,
+            ),
+            step(
+                row == 0,
+                periodic_y,
+                b.bottom,
+                index.wrapping_sub(cols),
+                (rows - 1) * cols + col,
+            ),
+        ]
+        .into_iter()
+        .flatten()
+    }
+
+    /// The in-plane neighbours followed by the back and front ones on a `(rows, cols, layers)`
+    /// lattice. The mask is shared by every layer.
+    pub fn neighbours_3d(
+        &self,
+        dimensions: (usize, usize, usize),
+        index: usize,
+        coupling: f32,
+    ) -> impl Iterator<Item = Neighbour> {
+        let (rows, cols, layers) = dimensions;
+        let plane = rows * cols;
+        let (layer, in_plane) = (index / plane, index % plane);
+        let b = &self.boundaries;
+        let periodic_z = b.front == Boundary::Periodic && b.back == Boundary::Periodic;
+
+        let lateral = self
+            .neighbours((rows, cols), in_plane, coupling)
+            .map(move |neighbour| match neighbour {
+                Neighbour::Site(site) => Neighbour::Site(layer * plane + site),
+                anchor => anchor,
+            });
+        let vertical = [
+            step(
+                layer + 1 == layers,
+                periodic_z,
+                b.back,
+                index + plane,
+                in_plane,
+                coupling,
+            ),
+            step(
+                layer == 0,
+                periodic_z,
+                b.front,
+                index.wrapping_sub(plane),
+                (layers - 1) * plane + in_plane,
+                coupling,
+            ),
+        ];
+        lateral.chain(vertical.into_iter().flatten())
+    }
+
+    /// Energy of the bonds of site `index`, `bond` giving that of the bond to one neighbour.
+    /// Bulk bonds are seen from both ends and count half, anchoring only from the lattice side.
+    pub fn bond_energy(
+        &self,
+        dimensions: (usize, usize),
+        index: usize,
+        coupling: f32,
+        bond: impl FnMut(Neighbour) -> f64,
+    ) -> f64 {
+        shared_bonds(self.neighbours(dimensions, index, coupling), bond)
+    }
+
+    /// `bond_energy` on a `(rows, cols, layers)` lattice.
+    pub fn bond_energy_3d(
+        &self,
+        dimensions: (usize, usize, usize),
+        index: usize,
+        coupling: f32,
+        bond: impl FnMut(Neighbour) -> f64,
+    ) -> f64 {
+        shared_bonds(self.neighbours_3d(dimensions, index, coupling), bond)
+    }
+}
+
+fn shared_bonds(neighbours: impl Iterator<Item = Neighbour>, mut bond: impl FnMut(Neighbour) -> f64) -> f64 {
+    neighbours
+        .map(|neighbour| match neighbour {
+            Neighbour::Site(_) => bond(neighbour) / 2.0,
+            Neighbour::Anchor { .. } => bond(neighbour),
+        })
+        .sum()
+}
+
+/// The neighbour across one edge: `inside` away from the edge, `wrapped` around a periodic axis,
+/// an anchor for fixed and weak boundaries.
+fn step(
+    at_edge: bool,
+    periodic: bool,
+    boundary: Boundary,
+    inside: usize,
+    wrapped: usize,
+    coupling: f32,
+) -> Option<Neighbour> {
+    if !at_edge {
+        return Some(Neighbour::Site(inside));
+    }
+    match boundary {
+        Boundary::Periodic if periodic => Some(Neighbour::Site(wrapped)),
+        Boundary::Periodic | Boundary::Free => None,
+        Boundary::Fixed { value } => Some(Neighbour::Anchor {
+            value,
+            strength: coupling,
+        }),
+        Boundary::Weak { value, strength } => Some(Neighbour::Anchor { value, strength }),
+    }
+}
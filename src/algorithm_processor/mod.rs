@@ -1,34 +1,112 @@
+use crate::analysis::{ElasticMap, FrankMaps, PowerSpectrum, SpectrumField};
 use crate::application::CustomEvent;
 use crate::application::CustomEventProxy;
 use crate::application::SharedContext;
 use crossbeam::channel::*;
-use rand::Rng;
 use std::thread;
 use std::time::Duration;
 pub use algorithm::*;
+pub use defects::*;
+pub use field::*;
+pub use flow::*;
+pub use headless::*;
+pub use initial_state::*;
+pub use lattice::*;
+pub use observables::*;
+pub use particles::*;
+pub use pitch::*;
+pub use protocol::*;
 pub mod algorithm;
+pub mod defects;
+pub mod field;
+pub mod flow;
+pub mod headless;
+pub mod initial_state;
+pub mod lattice;
+pub mod observables;
+pub mod particles;
+pub mod pitch;
+pub mod protocol;
 pub type Data = Vec<f32>;
-pub type ProcessedDataHandle = Receiver<Data>;
+
+/// Iterations between the tables the worker sends to the plots.
+const TABLE_INTERVAL: usize = 20;
+/// Iterations between the samples a GUI run keeps and shows, about five a second.
+pub const LIVE_SAMPLE_INTERVAL: usize = 5;
+/// Iterations a defect trail fades over.
+const TRAIL_ITERATIONS: usize = 60;
+
+/// Scalar values of a 2D lattice with the range spanned by the colormap.
+pub struct ScalarFrame {
+    pub values: Data,
+    pub dimensions: (usize, usize),
+    pub range: (f32, f32),
+}
+
+/// Overlays of the display the worker works out with every frame, so the renderer only draws.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Overlays {
+    /// Frank term sent with the frames of director fields.
+    pub elastic_map: ElasticMap,
+    /// Sends the recent tracks of the run's defect tracker with the frames of 2D lattices.
+    pub trails: bool,
+}
+
+/// What the worker hands to the renderer after every iteration. `spectrum` is the log power
+/// spectrum of the field chosen in the analyses and `elastic` the Frank term chosen in the
+/// overlays, while they are on, and `trails` the tracks of the last `TRAIL_ITERATIONS`.
+pub enum Frame {
+    /// Director angles with their defects, the scalar field, e.g. the order `S`, and the flow
+    /// velocity that came with them.
+    Angles {
+        angles: Data,
+        dimensions: (usize, usize),
+        defects: Vec<Defect>,
+        scalars: Option<ScalarFrame>,
+        flow: Option<VectorField>,
+        spectrum: Option<ScalarFrame>,
+        elastic: Option<Data>,
+        trails: Option<Data>,
+    },
+    Directors {
+        directors: Vec<[f32; 3]>,
+        dimensions: Dimensions3,
+        elastic: Option<Data>,
+    },
+    Scalars {
+        scalars: ScalarFrame,
+        spectrum: Option<ScalarFrame>,
+    },
+    Particles(Particles),
+}
+
+pub type ProcessedDataHandle = Receiver<Frame>;
 pub enum ThreadControlMessage {
     Stop,
+    UpdateConditions(LatticeConditions),
+    Perturb { position: (f32, f32), radius: f32 },
+    SetParameter(String, f32),
+    SetSpectrum(SpectrumField),
+    SetElasticMap(ElasticMap),
+    SetTrails(bool),
 }
 
 struct WorkerContext {
     controller_listener: Receiver<ThreadControlMessage>,
-    sender: Sender<Data>,
+    sender: Sender<Frame>,
     event_proxy: CustomEventProxy,
 }
 
 pub struct AlgorithmProcessor {
     ctx: Option<WorkerContext>,
-    worker: Option<thread::JoinHandle<WorkerContext>>,
+    worker: Option<thread::JoinHandle<(WorkerContext, RunRecord)>>,
     worker_controller: Sender<ThreadControlMessage>,
     shared_ctx: SharedContext,
 }
 
 impl AlgorithmProcessor {
     pub fn new(shared_ctx: SharedContext) -> (ProcessedDataHandle, Self) {
-        let (sender, receiver): (Sender<Data>, ProcessedDataHandle) = unbounded();
+        let (sender, receiver): (Sender<Frame>, ProcessedDataHandle) = unbounded();
         let (worker_controller, controller_listener): (
             Sender<ThreadControlMessage>,
             Receiver<ThreadControlMessage>,
@@ -49,9 +127,9 @@ impl AlgorithmProcessor {
         )
     }
 
-    pub fn start(&mut self, shared_ctx: SharedContext) {
+    pub fn start(&mut self, shared_ctx: SharedContext, algorithm: Algorithm, config: RunConfig, overlays: Overlays) {
         if self.ctx.is_none() {
-            self.shutdown()
+            self.shutdown();
         };
 
         let ctx = self
@@ -60,54 +138,218 @@ impl AlgorithmProcessor {
             .expect("There is serious bug the threading code in algorithm processor");
         let _ = ctx.event_proxy.send_event(CustomEvent::UpdateSharedData);
         self.worker = Some(thread::spawn(move || {
-            let mut count: u64 = 0;
-            let latice_dimentions = { shared_ctx.lock().general_params.read().unwrap().lattice_dimension };
-            let size = latice_dimentions.0 * latice_dimentions.1;
-            let mut rng = rand::rng();
-            let storage_data: Vec<f32> = (0..size).map(|_| rng.random_range(-1.0..1.0)).collect();
+            let mut count: usize = 0;
+            let (latice_dimentions, layers) = {
+                let ctx = shared_ctx.lock();
+                let params = ctx.general_params.read().unwrap();
+                (params.lattice_dimension, params.lattice_layers)
+            };
+            let config = RunConfig {
+                algorithm: algorithm.name.clone(),
+                lattice_dimension: latice_dimentions,
+                layers,
+                ..config
+            };
+            let mut state = RunState::new(&algorithm, &config);
+            let mut overlays = overlays;
+            let mut warnings = Vec::new();
 
             loop {
-                if let Ok(_) = ctx.controller_listener.try_recv() {
+                let mut stop = false;
+                for message in ctx.controller_listener.try_iter() {
+                    match message {
+                        ThreadControlMessage::Stop => stop = true,
+                        ThreadControlMessage::UpdateConditions(conditions) => state.set_conditions(&conditions),
+                        ThreadControlMessage::Perturb { position, radius } => {
+                            state.computation.perturb(position, radius)
+                        }
+                        ThreadControlMessage::SetParameter(name, value) => state.set_parameter(&name, value),
+                        ThreadControlMessage::SetSpectrum(field) => state.config.analyses.spectrum = field,
+                        ThreadControlMessage::SetElasticMap(map) => overlays.elastic_map = map,
+                        ThreadControlMessage::SetTrails(enabled) => overlays.trails = enabled,
+                    }
+                }
+                if stop {
                     println!("Received stop signal, exiting thread.");
                     break;
                 }
 
-                let data = storage_data
-                    .iter()
-                    .map(|e| e * (count as f32) * std::f32::consts::PI / 180.0)
-                    .collect();
-                ctx.sender.send(data).expect("Receiver is already closed");
+                if let Some(sample) = state.step(count) {
+                    let _ = ctx.event_proxy.send_event(CustomEvent::UpdateStatus(sample));
+                }
+                let current = state.warnings();
+                if current != warnings {
+                    warnings = current;
+                    let _ = ctx.event_proxy.send_event(CustomEvent::UpdateWarnings(warnings.clone()));
+                }
+                if let Some(frame) = frame(&state, &overlays, count) {
+                    ctx.sender.send(frame).expect("Receiver is already closed");
+                }
+                if count.is_multiple_of(TABLE_INTERVAL) {
+                    let tables = state.tables();
+                    if !tables.is_empty() {
+                        let _ = ctx.event_proxy.send_event(CustomEvent::UpdateTables(tables));
+                    }
+                }
                 let _ = ctx.event_proxy.send_event(CustomEvent::RequestRedraw);
                 count += 1;
                 thread::sleep(Duration::from_millis(40));
             }
-            ctx
+
+            state.config.iterations = count;
+            (ctx, state.finish())
         }));
 
         {
-            let mut ctx = self.shared_ctx.lock();
+            let ctx = self.shared_ctx.lock();
             ctx.general_params.write().unwrap().algorithm_started = true;
             let _ = ctx.event_proxy.send_event(CustomEvent::UpdateSharedData);
         }
     }
 
-    pub fn shutdown(&mut self) {
+    /// Applies new boundaries or a new mask to the running worker.
+    pub fn update_conditions(&self, conditions: LatticeConditions) {
+        if self.worker.is_some() {
+            self.worker_controller
+                .send(ThreadControlMessage::UpdateConditions(conditions))
+                .expect("Channel is already closed");
+        }
+    }
+
+    /// Forwards a click on the display to the running algorithm.
+    pub fn perturb(&self, position: (f32, f32), radius: f32) {
+        if self.worker.is_some() {
+            self.worker_controller
+                .send(ThreadControlMessage::Perturb { position, radius })
+                .expect("Channel is already closed");
+        }
+    }
+
+    /// Changes a parameter of the running algorithm.
+    pub fn set_parameter(&self, name: String, value: f32) {
+        if self.worker.is_some() {
+            self.worker_controller
+                .send(ThreadControlMessage::SetParameter(name, value))
+                .expect("Channel is already closed");
+        }
+    }
+
+    /// Sends the Frank term `map` with the frames from the next one on.
+    pub fn set_elastic_map(&self, map: ElasticMap) {
+        if self.worker.is_some() {
+            self.worker_controller
+                .send(ThreadControlMessage::SetElasticMap(map))
+                .expect("Channel is already closed");
+        }
+    }
+
+    /// Sends the trails of the tracked defects with the frames from the next one on, or stops.
+    pub fn set_trails(&self, enabled: bool) {
+        if self.worker.is_some() {
+            self.worker_controller
+                .send(ThreadControlMessage::SetTrails(enabled))
+                .expect("Channel is already closed");
+        }
+    }
+
+    /// Tabulates the structure factor of `field` from the next frame on.
+    pub fn set_spectrum(&self, field: SpectrumField) {
+        if self.worker.is_some() {
+            self.worker_controller
+                .send(ThreadControlMessage::SetSpectrum(field))
+                .expect("Channel is already closed");
+        }
+    }
+
+    /// Stops the worker and hands back what it ran, if anything was running.
+    pub fn shutdown(&mut self) -> Option<RunRecord> {
         self.worker_controller
             .send(ThreadControlMessage::Stop)
             .expect("Channel is already closed");
 
-        if let Some(handle) = self.worker.take() {
-            self.ctx = Some(handle.join().unwrap());
-        }
+        let record = self.worker.take().map(|handle| {
+            let (ctx, record) = handle.join().unwrap();
+            self.ctx = Some(ctx);
+            record
+        });
 
         {
-            let mut ctx = self.shared_ctx.lock();
+            let ctx = self.shared_ctx.lock();
             ctx.general_params.write().unwrap().algorithm_started = false;
             let _ = ctx.event_proxy.send_event(CustomEvent::UpdateSharedData);
         }
+
+        record
+    }
+}
+
+/// The frame of the current data of `state`, computed at `iteration`, with what `overlays` ask for.
+fn frame(state: &RunState, overlays: &Overlays, iteration: usize) -> Option<Frame> {
+    let (computation, data) = (state.computation.as_ref(), &state.data);
+    let spectrum = || {
+        let (values, dimensions) = state.config.analyses.spectrum.values_of(data)?;
+        let (values, range) = PowerSpectrum::new(&values, dimensions).log_image();
+        Some(ScalarFrame {
+            values,
+            dimensions,
+            range,
+        })
+    };
+    let elastic = || {
+        if overlays.elastic_map == ElasticMap::Off {
+            return None;
+        }
+        let maps = FrankMaps::from_data(data)?;
+        maps.map(overlays.elastic_map).map(<[f32]>::to_vec)
+    };
+    if let Some(particles) = DataType::particles(data) {
+        Some(Frame::Particles(particles.clone()))
+    } else if let Some((directors, dimensions)) = DataType::directors(data) {
+        Some(Frame::Directors {
+            directors: directors.clone(),
+            dimensions,
+            elastic: elastic(),
+        })
+    } else if let Some((angles, dimensions)) = DataType::oriented(data) {
+        Some(Frame::Angles {
+            angles: angles.clone(),
+            dimensions,
+            defects: find_defects(angles, dimensions),
+            scalars: scalar_frame(computation, data),
+            flow: DataType::vectors(data).map(|(velocity, dimensions)| (velocity.clone(), dimensions)),
+            spectrum: spectrum(),
+            elastic: elastic(),
+            trails: state
+                .tracker
+                .as_ref()
+                .filter(|_| overlays.trails)
+                .map(|tracker| tracker.trails(iteration, TRAIL_ITERATIONS, dimensions)),
+        })
+    } else {
+        scalar_frame(computation, data).map(|scalars| Frame::Scalars {
+            scalars,
+            spectrum: spectrum(),
+        })
     }
 }
 
+/// The scalar grid of `data` with the range its colormap spans.
+fn scalar_frame(computation: &dyn AlgorithmComputation, data: &[DataType]) -> Option<ScalarFrame> {
+    DataType::scalar(data).map(|(values, dimensions)| ScalarFrame {
+        values: values.clone(),
+        dimensions,
+        range: computation.scalar_range().unwrap_or_else(|| value_range(values)),
+    })
+}
+
+/// Smallest and largest value, widened when they coincide.
+fn value_range(values: &[f32]) -> (f32, f32) {
+    let (low, high) = values
+        .iter()
+        .fold((f32::INFINITY, f32::NEG_INFINITY), |(low, high), &value| (low.min(value), high.max(value)));
+    if low < high { (low, high) } else { (low - 0.5, low + 0.5) }
+}
+
 impl Drop for AlgorithmProcessor {
     fn drop(&mut self) {
         self.shutdown();
@@ -0,0 +1,111 @@
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+#[derive(Debug, Clone, Default)]
+pub struct Observables {
+    pub iteration: usize,
+    pub values: Vec<(String, f64)>,
+}
+
+impl Observables {
+    pub fn new(iteration: usize, values: Vec<(String, f64)>) -> Self {
+        Self { iteration, values }
+    }
+
+    pub fn get(&self, name: &str) -> Option<f64> {
+        self.values
+            .iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| *value)
+    }
+}
+
+/// Collects the values of one observable across a series of samples.
+pub fn series(samples: &[Observables], name: &str) -> Vec<f64> {
+    samples.iter().filter_map(|sample| sample.get(name)).collect()
+}
+
+pub fn write_csv(path: &Path, samples: &[Observables]) -> io::Result<()> {
+    let mut writer = BufWriter::new(File::create(path)?);
+    let names: Vec<&str> = samples
+        .first()
+        .map(|sample| sample.values.iter().map(|(name, _)| name.as_str()).collect())
+        .unwrap_or_default();
+
+    write!(writer, "iteration")?;
+    for name in &names {
+        write!(writer, ",{name}")?;
+    }
+    writeln!(writer)?;
+
+    for sample in samples {
+        write!(writer, "{}", sample.iteration)?;
+        for name in &names {
+            match sample.get(name) {
+                Some(value) => write!(writer, ",{value}")?,
+                None => write!(writer, ",")?,
+            }
+        }
+        writeln!(writer)?;
+    }
+
+    writer.flush()
+}
+
+/// Curves derived from the whole run or from the latest frame rather than a single number, e.g.
+/// the thermodynamics against temperature or a correlation function. The first column is the
+/// abscissa; a column `<name>_fit` is drawn over the column `<name>` in the plots.
+#[derive(Debug, Clone, Default)]
+pub struct Table {
+    pub name: String,
+    pub columns: Vec<(String, Vec<f64>)>,
+    /// Plot on log-log axes, e.g. for power laws.
+    pub log_axes: bool,
+}
+
+impl Table {
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_owned(),
+            columns: Vec::new(),
+            log_axes: false,
+        }
+    }
+
+    pub fn with_log_axes(mut self) -> Self {
+        self.log_axes = true;
+        self
+    }
+
+    pub fn with_column(mut self, name: &str, values: Vec<f64>) -> Self {
+        self.columns.push((name.to_owned(), values));
+        self
+    }
+
+    pub fn column(&self, name: &str) -> Option<&[f64]> {
+        self.columns
+            .iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, values)| values.as_slice())
+    }
+
+    /// One row per abscissa value; columns shorter than the first are left blank.
+    pub fn write_csv(&self, path: &Path) -> io::Result<()> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        let names: Vec<&str> = self.columns.iter().map(|(name, _)| name.as_str()).collect();
+        writeln!(writer, "{}", names.join(","))?;
+
+        let rows = self.columns.first().map_or(0, |(_, values)| values.len());
+        for row in 0..rows {
+            let fields: Vec<String> = self
+                .columns
+                .iter()
+                .map(|(_, values)| values.get(row).map_or_else(String::new, |value| value.to_string()))
+                .collect();
+            writeln!(writer, "{}", fields.join(","))?;
+        }
+
+        writer.flush()
+    }
+}
@@ -0,0 +1,40 @@
+/// Off-lattice particles in a periodic rectangular box with its origin at the bottom-left corner.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Particles {
+    pub positions: Vec<[f32; 2]>,
+    /// Orientation of each particle's long axis in radians.
+    pub orientations: Vec<f32>,
+    pub velocities: Vec<[f32; 2]>,
+    pub angular_velocities: Vec<f32>,
+    pub box_size: [f32; 2],
+    /// Length over width of every particle, 1 for spheres.
+    pub aspect: f32,
+}
+
+impl Particles {
+    pub fn len(&self) -> usize {
+        self.positions.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.positions.is_empty()
+    }
+
+    /// Shortest periodic image of `to - from`.
+    pub fn separation(&self, from: usize, to: usize) -> [f32; 2] {
+        std::array::from_fn(|axis| {
+            let length = self.box_size[axis];
+            let difference = self.positions[to][axis] - self.positions[from][axis];
+            difference - length * (difference / length).round()
+        })
+    }
+
+    /// Wraps every position back into the box.
+    pub fn wrap(&mut self) {
+        for position in &mut self.positions {
+            for (coordinate, length) in position.iter_mut().zip(self.box_size) {
+                *coordinate = coordinate.rem_euclid(length);
+            }
+        }
+    }
+}
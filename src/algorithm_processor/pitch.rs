@@ -0,0 +1,26 @@
+use std::f32::consts::{FRAC_PI_2, PI};
+
+/// 1 on sites where the director passes through `reference` between neighbours, 0 elsewhere.
+/// With the helix axis as `reference` these are the pitch lines of a cholesteric fingerprint
+/// texture, half a pitch apart.
+pub fn pitch_lines(angles: &[f32], (rows, cols): (usize, usize), reference: f32) -> Vec<f32> {
+    // Director angle relative to the reference, in [-pi/2, pi/2).
+    let offset = |angle: f32| (angle - reference + FRAC_PI_2).rem_euclid(PI) - FRAC_PI_2;
+    let mut lines = vec![0.0; angles.len()];
+    for row in 0..rows {
+        for col in 0..cols {
+            let index = row * cols + col;
+            let here = offset(angles[index]);
+            let right = (col + 1 < cols).then_some(index + 1);
+            let up = (row + 1 < rows).then_some(index + cols);
+            for other in [right, up].into_iter().flatten() {
+                let there = offset(angles[other]);
+                // A jump across +-pi/2 is the director turning perpendicular, not a pitch line.
+                if here.signum() != there.signum() && (here - there).abs() < FRAC_PI_2 {
+                    lines[if here.abs() <= there.abs() { index } else { other }] = 1.0;
+                }
+            }
+        }
+    }
+    lines
+}
@@ -0,0 +1,280 @@
+use crate::algorithm_processor::*;
+use std::f32::consts::PI;
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+/// Values a protocol can drive besides the algorithm's own parameters.
+const FIELD_TARGETS: [&str; 2] = ["field.magnitude", "field.angle"];
+
+/// How a scheduled value changes with the iteration.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Schedule {
+    /// `from` until `start`, linear to `to` at `end`, `to` afterwards.
+    Ramp { from: f32, to: f32, start: f32, end: f32 },
+    /// Relaxes from `from` towards `to` as `e^(-t / decay)`, e.g. annealing with `to = 0`.
+    Exponential { from: f32, to: f32, decay: f32 },
+    /// Jumps from `before` to `after` at iteration `at`.
+    Quench { before: f32, after: f32, at: f32 },
+    /// Piecewise linear through `(iteration, value)` rows of a CSV file, constant past the ends.
+    Table { path: PathBuf, points: Vec<(f32, f32)> },
+    /// `mean + amplitude * shape(t / period)`, the shape swinging between -1 and 1.
+    Periodic { shape: PeriodicShape, mean: f32, amplitude: f32, period: f32 },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PeriodicShape {
+    Sine,
+    Square,
+    Triangle,
+}
+
+impl Schedule {
+    /// Parses `ramp <from> <to> <start> <end>`, `exponential <from> <to> <decay>`,
+    /// `quench <before> <after> <at>`, `table <csv path>` or `sine|square|triangle <mean>
+    /// <amplitude> <period>`.
+    pub fn parse(text: &str) -> Result<Self, String> {
+        if let Some(path) = text.trim().strip_prefix("table") {
+            let path = PathBuf::from(path.trim());
+            let points = load_table(&path)?;
+            return Ok(Self::Table { path, points });
+        }
+        let (kind, numbers) = split_numbers(text)?;
+        let periodic = |shape, numbers: &[f32]| match *numbers {
+            [mean, amplitude, period] if period > 0.0 => Ok(Self::Periodic {
+                shape,
+                mean,
+                amplitude,
+                period,
+            }),
+            _ => Err(format!("Expected: {kind} <mean> <amplitude> <period>")),
+        };
+        match (kind, numbers.as_slice()) {
+            ("ramp", &[from, to, start, end]) if end > start => Ok(Self::Ramp { from, to, start, end }),
+            ("ramp", _) => Err("Expected: ramp <from> <to> <start> <end>, with end after start".to_owned()),
+            ("exponential", &[from, to, decay]) if decay > 0.0 => Ok(Self::Exponential { from, to, decay }),
+            ("exponential", _) => Err("Expected: exponential <from> <to> <decay>".to_owned()),
+            ("quench", &[before, after, at]) => Ok(Self::Quench { before, after, at }),
+            ("quench", _) => Err("Expected: quench <before> <after> <at>".to_owned()),
+            ("sine", numbers) => periodic(PeriodicShape::Sine, numbers),
+            ("square", numbers) => periodic(PeriodicShape::Square, numbers),
+            ("triangle", numbers) => periodic(PeriodicShape::Triangle, numbers),
+            _ => Err(format!("Unknown schedule '{text}'")),
+        }
+    }
+
+    pub fn value(&self, iteration: usize) -> f32 {
+        let time = iteration as f32;
+        match self {
+            Self::Ramp { from, to, start, end } => {
+                let progress = ((time - start) / (end - start)).clamp(0.0, 1.0);
+                from + (to - from) * progress
+            }
+            Self::Exponential { from, to, decay } => to + (from - to) * (-time / decay).exp(),
+            Self::Quench { before, after, at } => if time < *at { *before } else { *after },
+            Self::Table { points, .. } => interpolate(points, time),
+            Self::Periodic {
+                shape,
+                mean,
+                amplitude,
+                period,
+            } => {
+                let phase = (time / period).fract();
+                let swing = match shape {
+                    PeriodicShape::Sine => (2.0 * PI * phase).sin(),
+                    PeriodicShape::Square => if phase < 0.5 { 1.0 } else { -1.0 },
+                    PeriodicShape::Triangle => 4.0 * ((phase - 0.25).rem_euclid(1.0) - 0.5).abs() - 1.0,
+                };
+                mean + amplitude * swing
+            }
+        }
+    }
+}
+
+impl fmt::Display for Schedule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Ramp { from, to, start, end } => write!(f, "ramp {from} {to} {start} {end}"),
+            Self::Exponential { from, to, decay } => write!(f, "exponential {from} {to} {decay}"),
+            Self::Quench { before, after, at } => write!(f, "quench {before} {after} {at}"),
+            Self::Table { path, .. } => write!(f, "table {}", path.display()),
+            Self::Periodic {
+                shape,
+                mean,
+                amplitude,
+                period,
+            } => {
+                let kind = match shape {
+                    PeriodicShape::Sine => "sine",
+                    PeriodicShape::Square => "square",
+                    PeriodicShape::Triangle => "triangle",
+                };
+                write!(f, "{kind} {mean} {amplitude} {period}")
+            }
+        }
+    }
+}
+
+/// Reads `iteration,value` rows sorted by iteration; lines that are not two numbers, such as a
+/// header, are skipped.
+fn load_table(path: &Path) -> Result<Vec<(f32, f32)>, String> {
+    let text = std::fs::read_to_string(path).map_err(|e| format!("Unable to read {}: {e}", path.display()))?;
+    let mut points: Vec<(f32, f32)> = text
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split([',', ';', '\t', ' ']).filter(|field| !field.is_empty());
+            let iteration = fields.next()?.trim().parse::<f32>().ok()?;
+            let value = fields.next()?.trim().parse::<f32>().ok()?;
+            Some((iteration, value))
+        })
+        .collect();
+    if points.is_empty() {
+        return Err(format!("{} has no 'iteration,value' rows", path.display()));
+    }
+    points.sort_by(|a, b| a.0.total_cmp(&b.0));
+    Ok(points)
+}
+
+fn interpolate(points: &[(f32, f32)], time: f32) -> f32 {
+    let after = points.partition_point(|&(iteration, _)| iteration <= time);
+    match (after.checked_sub(1).map(|before| points[before]), points.get(after)) {
+        (Some((t0, v0)), Some(&(t1, v1))) => v0 + (v1 - v0) * (time - t0) / (t1 - t0),
+        (Some((_, value)), None) | (None, Some(&(_, value))) => value,
+        (None, None) => 0.0,
+    }
+}
+
+/// A parameter, or `field.magnitude`/`field.angle`, following a schedule over the run.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Protocol {
+    pub parameter: String,
+    pub schedule: Schedule,
+}
+
+impl Protocol {
+    /// Parses `name = schedule; name = schedule; ...`.
+    pub fn parse_list(text: &str) -> Result<Vec<Protocol>, String> {
+        text.split(';')
+            .filter(|entry| !entry.trim().is_empty())
+            .map(|entry| {
+                let (parameter, schedule) = entry
+                    .split_once('=')
+                    .ok_or_else(|| format!("Expected 'name = schedule' in '{}'", entry.trim()))?;
+                Ok(Protocol {
+                    parameter: parameter.trim().to_owned(),
+                    schedule: Schedule::parse(schedule)?,
+                })
+            })
+            .collect()
+    }
+}
+
+/// Warnings for protocols driving something the algorithm does not expose.
+pub fn protocol_warnings(protocols: &[Protocol], computation: &dyn AlgorithmComputation) -> Vec<String> {
+    let parameters = computation.parameters();
+    protocols
+        .iter()
+        .filter(|protocol| {
+            !FIELD_TARGETS.contains(&protocol.parameter.as_str())
+                && !parameters.iter().any(|p| p.name == protocol.parameter)
+        })
+        .map(|protocol| format!("the algorithm has no parameter '{}', its protocol is only logged", protocol.parameter))
+        .collect()
+}
+
+/// Sets every scheduled value for `iteration` and returns them named for the observables.
+/// `conditions` are handed to the algorithm again when a protocol moves the field to a new value.
+pub fn apply_protocols(
+    protocols: &[Protocol],
+    iteration: usize,
+    computation: &mut dyn AlgorithmComputation,
+    conditions: &mut LatticeConditions,
+) -> Vec<(String, f64)> {
+    let mut field_changed = false;
+    let values = protocols
+        .iter()
+        .map(|protocol| {
+            let value = protocol.schedule.value(iteration);
+            let field = match protocol.parameter.as_str() {
+                "field.magnitude" => Some(&mut conditions.field.magnitude),
+                "field.angle" => Some(&mut conditions.field.angle),
+                name => {
+                    computation.set_parameter(name, value);
+                    None
+                }
+            };
+            if let Some(field) = field
+                && *field != value
+            {
+                *field = value;
+                field_changed = true;
+            }
+            (protocol.parameter.clone(), value as f64)
+        })
+        .collect();
+    if field_changed {
+        computation.set_conditions(conditions);
+    }
+    values
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn values(schedule: &str, iterations: &[usize]) -> Vec<f32> {
+        let schedule = Schedule::parse(schedule).unwrap();
+        iterations.iter().map(|&iteration| schedule.value(iteration)).collect()
+    }
+
+    fn assert_close(actual: &[f32], expected: &[f32]) {
+        assert_eq!(actual.len(), expected.len());
+        for (actual, expected) in actual.iter().zip(expected) {
+            assert!((actual - expected).abs() < 1e-5, "{actual} against {expected}");
+        }
+    }
+
+    #[test]
+    fn ramp_holds_its_ends() {
+        assert_close(&values("ramp 2 4 10 20", &[0, 10, 15, 20, 100]), &[2.0, 2.0, 3.0, 4.0, 4.0]);
+        assert!(Schedule::parse("ramp 2 4 20 10").is_err());
+    }
+
+    #[test]
+    fn exponential_decays_by_e_per_decay_time() {
+        let e = std::f32::consts::E;
+        assert_close(&values("exponential 2 0 10", &[0, 10, 20]), &[2.0, 2.0 / e, 2.0 / (e * e)]);
+        assert!(Schedule::parse("exponential 2 0 0").is_err());
+    }
+
+    #[test]
+    fn quench_steps_at_its_iteration() {
+        assert_close(&values("quench 1 0.1 5", &[0, 4, 5, 6]), &[1.0, 1.0, 0.1, 0.1]);
+    }
+
+    #[test]
+    fn table_interpolates_and_clamps() {
+        let path = std::env::temp_dir().join(format!("simula_protocol_{}.csv", std::process::id()));
+        std::fs::write(&path, "iteration,value\n20,3\n10,1\n").unwrap();
+        let schedule = format!("table {}", path.display());
+        let result = values(&schedule, &[0, 10, 15, 20, 40]);
+        std::fs::remove_file(&path).unwrap();
+        assert_close(&result, &[1.0, 1.0, 2.0, 3.0, 3.0]);
+    }
+
+    #[test]
+    fn periodic_shapes_follow_their_phase() {
+        assert_close(&values("sine 1 2 8", &[0, 2, 4, 6, 8]), &[1.0, 3.0, 1.0, -1.0, 1.0]);
+        assert_close(&values("square 0 1 4", &[0, 1, 2, 3, 4]), &[1.0, 1.0, -1.0, -1.0, 1.0]);
+        assert_close(&values("triangle 0 1 4", &[0, 1, 2, 3, 4]), &[0.0, 1.0, 0.0, -1.0, 0.0]);
+        assert!(Schedule::parse("sine 1 2 0").is_err());
+    }
+
+    #[test]
+    fn parses_protocol_lists() {
+        let protocols = Protocol::parse_list("temperature = ramp 1 0 0 100; field.angle = quench 0 1 50;").unwrap();
+        assert_eq!(protocols.len(), 2);
+        assert_eq!(protocols[0].parameter, "temperature");
+        assert_eq!(protocols[1].schedule, Schedule::Quench { before: 0.0, after: 1.0, at: 50.0 });
+        assert!(Protocol::parse_list("temperature ramp 1 0 0 100").is_err());
+    }
+}
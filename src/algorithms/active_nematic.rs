@@ -0,0 +1,268 @@
+use crate::algorithm_processor::*;
+use crate::algorithms::LandauDeGennes;
+use crate::analysis::{Complex, Fft2, wavenumber};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+/// `(Q_xx, Q_xy)` of every site.
+type TensorField = Vec<[f32; 2]>;
+
+/// Velocity of every site with its gradients `(du_x/dx, du_x/dy, du_y/dx)`.
+struct Flow {
+    velocity: Vec<[f32; 2]>,
+    gradients: Vec<[f32; 3]>,
+}
+
+/// Active nematic hydrodynamics: the 2D Q-tensor of Landau-de Gennes advected and rotated by a
+/// flow driven by the active stress `-zeta Q`,
+///
+/// dQ/dt + u.grad Q = xi E + (omega x Q) + gamma H,  H = -(A + C tr Q^2) Q + L lap Q
+/// eta lap u - friction u - grad p - zeta div Q = 0,  div u = 0
+///
+/// The Stokes/Brinkman problem is solved exactly in Fourier space, so the lattice is always
+/// periodic; boundaries other than periodic are ignored, frozen cells keep their Q. `E` is the
+/// strain rate, `omega` the vorticity, and the co-rotation term is kept to linear order in Q.
+/// Positive activity is extensile. Every iteration emits the director angles, the scalar order
+/// and the velocity.
+pub struct ActiveNematic {
+    a: f32,
+    c: f32,
+    elastic: f32,
+    gamma: f32,
+    activity: f32,
+    viscosity: f32,
+    friction: f32,
+    flow_alignment: f32,
+    noise: f32,
+    time_step: f32,
+    substeps: f32,
+    conditions: LatticeConditions,
+    fft: Option<Fft2>,
+    time: f32,
+    rng: StdRng,
+}
+
+impl ActiveNematic {
+    pub fn new() -> Self {
+        Self {
+            a: -1.0,
+            c: 1.0,
+            elastic: 1.0,
+            gamma: 1.0,
+            activity: 0.3,
+            viscosity: 1.0,
+            friction: 0.01,
+            flow_alignment: 0.7,
+            noise: 0.001,
+            time_step: 0.1,
+            substeps: 10.0,
+            conditions: LatticeConditions::default(),
+            fft: None,
+            time: 0.0,
+            rng: StdRng::from_os_rng(),
+        }
+    }
+
+    fn read_tensors(&self, data: &[DataType]) -> Option<(TensorField, (usize, usize))> {
+        let (angles, dimensions) = DataType::oriented(data)?;
+        let order = DataType::scalar(data)
+            .filter(|(order, order_dimensions)| *order_dimensions == dimensions && order.len() == angles.len())
+            .map(|(order, _)| order);
+        let bulk = if self.a < 0.0 && self.c > 0.0 { (-2.0 * self.a / self.c).sqrt() } else { 1.0 };
+        let q = angles
+            .iter()
+            .enumerate()
+            .map(|(index, &angle)| LandauDeGennes::tensor(order.map_or(bulk, |order| order[index]), angle))
+            .collect();
+        Some((q, dimensions))
+    }
+
+    /// Stokes/Brinkman flow of the active force `f = -zeta div Q`, with the pressure projected out:
+    /// u_k = (I - k k / k^2) f_k / (eta k^2 + friction).
+    fn solve_flow(&self, fft: &Fft2, q: &[[f32; 2]], (rows, cols): (usize, usize)) -> Flow {
+        let component = |c: usize| fft.forward_real(&q.iter().map(|q| q[c]).collect::<Vec<_>>());
+        let (q1, q2) = (component(0), component(1));
+        // The Nyquist mode of an even length has no odd derivative on the lattice.
+        let derivative = |index: usize, len: usize| {
+            if 2 * index == len { 0.0 } else { wavenumber(index, len) }
+        };
+
+        let (zeta, eta, friction) = (self.activity as f64, self.viscosity as f64, self.friction as f64);
+        let mut spectra = vec![vec![Complex::ZERO; q.len()]; 5];
+        for row in 0..rows {
+            let ky = derivative(row, rows);
+            for col in 0..cols {
+                let kx = derivative(col, cols);
+                let k2 = kx * kx + ky * ky;
+                if k2 == 0.0 {
+                    continue;
+                }
+                let index = row * cols + col;
+                let fx = (q1[index].scale(kx) + q2[index].scale(ky)).times_i().scale(-zeta);
+                let fy = (q2[index].scale(kx) - q1[index].scale(ky)).times_i().scale(-zeta);
+                let longitudinal = (fx.scale(kx) + fy.scale(ky)).scale(1.0 / k2);
+                let response = 1.0 / (eta * k2 + friction);
+                let ux = (fx - longitudinal.scale(kx)).scale(response);
+                let uy = (fy - longitudinal.scale(ky)).scale(response);
+                spectra[0][index] = ux;
+                spectra[1][index] = uy;
+                spectra[2][index] = ux.times_i().scale(kx);
+                spectra[3][index] = ux.times_i().scale(ky);
+                spectra[4][index] = uy.times_i().scale(kx);
+            }
+        }
+
+        let fields: Vec<Vec<f32>> = spectra.iter().map(|spectrum| fft.inverse_real(spectrum)).collect();
+        Flow {
+            velocity: (0..q.len()).map(|i| [fields[0][i], fields[1][i]]).collect(),
+            gradients: (0..q.len()).map(|i| [fields[2][i], fields[3][i], fields[4][i]]).collect(),
+        }
+    }
+
+    fn step(&mut self, q: &mut [[f32; 2]], flow: &Flow, (rows, cols): (usize, usize)) {
+        let dt = self.time_step;
+        let mut next = q.to_vec();
+        for index in 0..q.len() {
+            if self.conditions.is_frozen(index) {
+                continue;
+            }
+            let (row, col) = (index / cols, index % cols);
+            let left = row * cols + (col + cols - 1) % cols;
+            let right = row * cols + (col + 1) % cols;
+            let down = (row + rows - 1) % rows * cols + col;
+            let up = (row + 1) % rows * cols + col;
+
+            let [q1, q2] = q[index];
+            let [ux, uy] = flow.velocity[index];
+            let [dx_ux, dy_ux, dx_uy] = flow.gradients[index];
+            let vorticity = dx_uy - dy_ux;
+            let strain = [dx_ux, 0.5 * (dx_uy + dy_ux)];
+            let rotation = [-vorticity * q2, vorticity * q1];
+            let bulk = self.a + 2.0 * self.c * (q1 * q1 + q2 * q2);
+
+            for component in 0..2 {
+                let value = q[index][component];
+                let laplacian = q[left][component] + q[right][component] + q[down][component]
+                    + q[up][component]
+                    - 4.0 * value;
+                let advection = 0.5
+                    * (ux * (q[right][component] - q[left][component]) + uy * (q[up][component] - q[down][component]));
+                let molecular = -bulk * value + self.elastic * laplacian;
+                let rate = self.gamma * molecular - advection + self.flow_alignment * strain[component]
+                    + rotation[component];
+                next[index][component] =
+                    value + dt * rate + self.noise * dt.sqrt() * self.rng.random_range(-1.0..1.0);
+            }
+        }
+        q.copy_from_slice(&next);
+    }
+}
+
+impl Default for ActiveNematic {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl AlgorithmComputation for ActiveNematic {
+    fn compute(&mut self, _iteration: usize, data: &[DataType]) -> Vec<DataType> {
+        let Some((mut q, dimensions)) = self.read_tensors(data) else {
+            return Vec::new();
+        };
+        let fft = match self.fft.take() {
+            Some(fft) if fft.dimensions() == dimensions => fft,
+            _ => Fft2::new(dimensions),
+        };
+        let mut flow = self.solve_flow(&fft, &q, dimensions);
+        for _ in 0..self.substeps as usize {
+            self.step(&mut q, &flow, dimensions);
+            flow = self.solve_flow(&fft, &q, dimensions);
+            self.time += self.time_step;
+        }
+        self.fft = Some(fft);
+
+        let (angles, order) = q.into_iter().map(LandauDeGennes::director).unzip();
+        vec![
+            DataType::OrientedGrid(angles, dimensions),
+            DataType::ScalarGrid(order, dimensions),
+            DataType::VectorGrid(flow.velocity, dimensions),
+        ]
+    }
+
+    fn parameters(&self) -> Vec<Parameter> {
+        vec![
+            Parameter::new("A", self.a),
+            Parameter::new("C", self.c),
+            Parameter::new("L", self.elastic),
+            Parameter::new("gamma", self.gamma),
+            Parameter::new("activity", self.activity),
+            Parameter::new("viscosity", self.viscosity),
+            Parameter::new("friction", self.friction),
+            Parameter::new("flow_alignment", self.flow_alignment),
+            Parameter::new("noise", self.noise),
+            Parameter::new("time_step", self.time_step),
+            Parameter::new("substeps", self.substeps),
+        ]
+    }
+
+    fn set_parameter(&mut self, name: &str, value: f32) {
+        match name {
+            "A" => self.a = value,
+            "C" => self.c = value,
+            "L" => self.elastic = value.max(0.0),
+            "gamma" => self.gamma = value.max(0.0),
+            "activity" => self.activity = value,
+            "viscosity" => self.viscosity = value.max(f32::EPSILON),
+            "friction" => self.friction = value.max(0.0),
+            "flow_alignment" => self.flow_alignment = value,
+            "noise" => self.noise = value.max(0.0),
+            "time_step" => self.time_step = value.max(f32::EPSILON),
+            "substeps" => self.substeps = value.max(1.0),
+            _ => {}
+        }
+    }
+
+    fn seed(&mut self, seed: u64) {
+        self.rng = StdRng::seed_from_u64(seed);
+    }
+
+    fn set_conditions(&mut self, conditions: &LatticeConditions) {
+        self.conditions = conditions.clone();
+    }
+
+    fn warnings(&self) -> Vec<String> {
+        let b = &self.conditions.boundaries;
+        if [b.left, b.right, b.bottom, b.top].iter().any(|&edge| edge != Boundary::Periodic) {
+            vec!["the flow solve is periodic, the lattice edges are treated as periodic".to_owned()]
+        } else {
+            Vec::new()
+        }
+    }
+
+    fn observables(&self, data: &[DataType]) -> Vec<(String, f64)> {
+        let (Some((angles, _)), Some((velocity, dimensions))) = (DataType::oriented(data), DataType::vectors(data))
+        else {
+            return Vec::new();
+        };
+        let sites = angles.len().max(1) as f64;
+        let order = DataType::scalar(data).map_or(0.0, |(order, _)| order.iter().map(|&s| s as f64).sum::<f64>());
+        let (cos_sum, sin_sum) = angles.iter().fold((0.0f64, 0.0f64), |(c, s), &angle| {
+            (c + (2.0 * angle as f64).cos(), s + (2.0 * angle as f64).sin())
+        });
+        let speed_squared = velocity.iter().map(|&[ux, uy]| (ux * ux + uy * uy) as f64).sum::<f64>();
+        let enstrophy = vorticity(velocity, dimensions)
+            .iter()
+            .map(|&omega| 0.5 * (omega * omega) as f64)
+            .sum::<f64>();
+        vec![
+            ("scalar_order".to_owned(), order / sites),
+            (
+                "order_parameter".to_owned(),
+                (cos_sum * cos_sum + sin_sum * sin_sum).sqrt() / sites,
+            ),
+            ("rms_velocity".to_owned(), (speed_squared / sites).sqrt()),
+            ("enstrophy".to_owned(), enstrophy / sites),
+            ("time".to_owned(), self.time as f64),
+        ]
+    }
+}
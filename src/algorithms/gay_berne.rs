@@ -0,0 +1,379 @@
+use crate::algorithm_processor::*;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::f32::consts::TAU;
+
+/// Below this reduced distance the potential continues linearly, so that overlapping starts feel
+/// a large but finite push instead of overflowing.
+const MIN_REDUCED_DISTANCE: f32 = 0.7;
+
+/// Molecular dynamics of 2D Gay-Berne ellipsoids in a periodic box, in reduced units
+/// (sigma0 = epsilon0 = mass = moment of inertia = 1).
+///
+/// U = 4 eps(r, u_i, u_j) (R^-12 - R^-6) with R = (r - sigma(r, u_i, u_j) + 1), where `kappa` is the
+/// length to width ratio, `kappa_prime` the side-by-side to end-to-end well depth ratio and `mu`,
+/// `nu` the usual exponents. `kappa = kappa_prime = 1` is the Lennard-Jones fluid.
+///
+/// Velocity Verlet with a Berendsen thermostat on translations and rotations. Particles start on
+/// a rectangular lattice, aligned along x, with Maxwell velocities.
+pub struct GayBerne {
+    count: f32,
+    density: f32,
+    temperature: f32,
+    time_step: f32,
+    substeps: f32,
+    kappa: f32,
+    kappa_prime: f32,
+    mu: f32,
+    nu: f32,
+    cutoff: f32,
+    thermostat: f32,
+    time: f32,
+    rng: StdRng,
+}
+
+struct Model {
+    chi: f32,
+    chi_prime: f32,
+    mu: f32,
+    nu: f32,
+    cutoff: f32,
+}
+
+/// Energy, force on the second particle and torques on both of one interacting pair.
+struct PairTerms {
+    energy: f32,
+    force: [f32; 2],
+    torques: [f32; 2],
+}
+
+/// `S = (a + b)^2 / (1 + x c) + (a - b)^2 / (1 - x c)` and its derivatives by a, b and c.
+fn anisotropy(x: f32, a: f32, b: f32, c: f32) -> (f32, [f32; 3]) {
+    let (plus, minus) = (1.0 + x * c, 1.0 - x * c);
+    let (sum, difference) = (a + b, a - b);
+    (
+        sum * sum / plus + difference * difference / minus,
+        [
+            2.0 * sum / plus + 2.0 * difference / minus,
+            2.0 * sum / plus - 2.0 * difference / minus,
+            -x * sum * sum / (plus * plus) + x * difference * difference / (minus * minus),
+        ],
+    )
+}
+
+impl Model {
+    /// `separation` points from the first particle to the second.
+    fn pair(&self, separation: [f32; 2], first: f32, second: f32) -> Option<PairTerms> {
+        let distance = (separation[0] * separation[0] + separation[1] * separation[1]).sqrt();
+        if distance >= self.cutoff || distance < f32::EPSILON {
+            return None;
+        }
+        let unit = [separation[0] / distance, separation[1] / distance];
+        let (ui, uj) = ([first.cos(), first.sin()], [second.cos(), second.sin()]);
+        let (ui_perp, uj_perp) = ([-ui[1], ui[0]], [-uj[1], uj[0]]);
+        let dot = |p: [f32; 2], q: [f32; 2]| p[0] * q[0] + p[1] * q[1];
+        let (a, b, c) = (dot(unit, ui), dot(unit, uj), dot(ui, uj));
+
+        let (s, ds) = anisotropy(self.chi, a, b, c);
+        let h = 1.0 - 0.5 * self.chi * s;
+        let sigma = h.powf(-0.5);
+        let dsigma = ds.map(|d| 0.25 * self.chi * h.powf(-1.5) * d);
+
+        let chi_c = 1.0 - self.chi * self.chi * c * c;
+        let eps1 = chi_c.powf(-0.5);
+        let deps1 = [0.0, 0.0, self.chi * self.chi * c * chi_c.powf(-1.5)];
+        let (s_prime, ds_prime) = anisotropy(self.chi_prime, a, b, c);
+        let eps2 = 1.0 - 0.5 * self.chi_prime * s_prime;
+        let deps2 = ds_prime.map(|d| -0.5 * self.chi_prime * d);
+        let eps = eps1.powf(self.nu) * eps2.powf(self.mu);
+        let deps: [f32; 3] = std::array::from_fn(|k| eps * (self.nu * deps1[k] / eps1 + self.mu * deps2[k] / eps2));
+
+        let reduced = distance - sigma + 1.0;
+        let clamped = reduced.max(MIN_REDUCED_DISTANCE);
+        let (r6, r12) = (clamped.powi(-6), clamped.powi(-12));
+        let slope = (-12.0 * r12 + 6.0 * r6) / clamped;
+        let shape = r12 - r6 + slope * (reduced - clamped);
+        let dv_dreduced = 4.0 * eps * slope;
+        // dU/dr at fixed orientations, then dU/da, dU/db and dU/dc at fixed r.
+        let du_dr = dv_dreduced;
+        let du: [f32; 3] = std::array::from_fn(|k| 4.0 * deps[k] * shape - dv_dreduced * dsigma[k]);
+
+        let gradient: [f32; 2] = std::array::from_fn(|axis| {
+            du_dr * unit[axis] + (du[0] * (ui[axis] - a * unit[axis]) + du[1] * (uj[axis] - b * unit[axis])) / distance
+        });
+        Some(PairTerms {
+            energy: 4.0 * eps * shape,
+            force: gradient.map(|g| -g),
+            torques: [
+                -(du[0] * dot(unit, ui_perp) + du[2] * dot(uj, ui_perp)),
+                -(du[1] * dot(unit, uj_perp) + du[2] * dot(ui, uj_perp)),
+            ],
+        })
+    }
+}
+
+/// Forces, torques and total potential energy of a configuration.
+struct Forces {
+    forces: Vec<[f32; 2]>,
+    torques: Vec<f32>,
+    energy: f32,
+}
+
+impl GayBerne {
+    pub fn new(kappa: f32, kappa_prime: f32) -> Self {
+        Self {
+            count: 400.0,
+            density: 0.3,
+            temperature: 1.0,
+            time_step: 0.002,
+            substeps: 20.0,
+            kappa,
+            kappa_prime,
+            mu: 2.0,
+            nu: 1.0,
+            cutoff: kappa + 1.0,
+            thermostat: 0.5,
+            time: 0.0,
+            rng: StdRng::from_os_rng(),
+        }
+    }
+
+    fn model(&self) -> Model {
+        let squared = self.kappa * self.kappa;
+        let root = self.kappa_prime.powf(1.0 / self.mu);
+        Model {
+            chi: (squared - 1.0) / (squared + 1.0),
+            chi_prime: (root - 1.0) / (root + 1.0),
+            mu: self.mu,
+            nu: self.nu,
+            cutoff: self.cutoff,
+        }
+    }
+
+    fn gaussian(&mut self) -> f32 {
+        let u = self.rng.random::<f32>().max(f32::MIN_POSITIVE);
+        (-2.0 * u.ln()).sqrt() * (TAU * self.rng.random::<f32>()).cos()
+    }
+
+    fn initial_particles(&mut self) -> Particles {
+        let count = (self.count as usize).max(1);
+        let side = (count as f32 / self.density.max(f32::EPSILON)).sqrt();
+        let per_row = ((side / self.kappa).floor() as usize).clamp(1, count);
+        let rows = count.div_ceil(per_row);
+        let spacing = [side / per_row as f32, side / rows as f32];
+
+        let mut particles = Particles {
+            box_size: [side, side],
+            aspect: self.kappa,
+            ..Default::default()
+        };
+        let spread = self.temperature.max(0.0).sqrt();
+        for index in 0..count {
+            let (row, col) = (index / per_row, index % per_row);
+            particles
+                .positions
+                .push([(col as f32 + 0.5) * spacing[0], (row as f32 + 0.5) * spacing[1]]);
+            particles.orientations.push(0.05 * self.gaussian());
+            let velocity = [spread * self.gaussian(), spread * self.gaussian()];
+            particles.velocities.push(velocity);
+            let spin = spread * self.gaussian();
+            particles.angular_velocities.push(spin);
+        }
+        particles
+    }
+
+    /// Pairs closer than the cutoff, through a cell list when the box is large enough.
+    fn pairs(&self, particles: &Particles) -> Vec<(usize, usize)> {
+        let cells = particles.box_size.map(|length| (length / self.cutoff).floor().max(1.0) as usize);
+        if cells[0] < 3 || cells[1] < 3 {
+            return (0..particles.len())
+                .flat_map(|i| (i + 1..particles.len()).map(move |j| (i, j)))
+                .collect();
+        }
+        let cell_of = |position: [f32; 2]| -> [usize; 2] {
+            std::array::from_fn(|axis| {
+                ((position[axis] / particles.box_size[axis] * cells[axis] as f32) as usize).min(cells[axis] - 1)
+            })
+        };
+        let mut members = vec![Vec::new(); cells[0] * cells[1]];
+        for (index, &position) in particles.positions.iter().enumerate() {
+            let [x, y] = cell_of(position);
+            members[y * cells[0] + x].push(index);
+        }
+
+        let mut pairs = Vec::new();
+        for (index, &position) in particles.positions.iter().enumerate() {
+            let [x, y] = cell_of(position);
+            for dy in [cells[1] - 1, 0, 1] {
+                for dx in [cells[0] - 1, 0, 1] {
+                    let cell = ((y + dy) % cells[1]) * cells[0] + (x + dx) % cells[0];
+                    pairs.extend(members[cell].iter().filter(|&&other| other > index).map(|&other| (index, other)));
+                }
+            }
+        }
+        pairs
+    }
+
+    fn forces(&self, particles: &Particles) -> Forces {
+        let model = self.model();
+        let mut result = Forces {
+            forces: vec![[0.0; 2]; particles.len()],
+            torques: vec![0.0; particles.len()],
+            energy: 0.0,
+        };
+        for (i, j) in self.pairs(particles) {
+            let separation = particles.separation(i, j);
+            let Some(terms) = model.pair(separation, particles.orientations[i], particles.orientations[j]) else {
+                continue;
+            };
+            result.energy += terms.energy;
+            for axis in 0..2 {
+                result.forces[j][axis] += terms.force[axis];
+                result.forces[i][axis] -= terms.force[axis];
+            }
+            result.torques[i] += terms.torques[0];
+            result.torques[j] += terms.torques[1];
+        }
+        result
+    }
+
+    fn kick(particles: &mut Particles, forces: &Forces, time_step: f32) {
+        for (velocity, force) in particles.velocities.iter_mut().zip(&forces.forces) {
+            velocity[0] += 0.5 * time_step * force[0];
+            velocity[1] += 0.5 * time_step * force[1];
+        }
+        for (spin, torque) in particles.angular_velocities.iter_mut().zip(&forces.torques) {
+            *spin += 0.5 * time_step * torque;
+        }
+    }
+
+    /// Translational and rotational temperatures, from two and one degrees of freedom.
+    fn temperatures(particles: &Particles) -> (f32, f32) {
+        let count = particles.len().max(1) as f32;
+        let translational = particles
+            .velocities
+            .iter()
+            .map(|v| v[0] * v[0] + v[1] * v[1])
+            .sum::<f32>()
+            / (2.0 * count);
+        let rotational = particles.angular_velocities.iter().map(|w| w * w).sum::<f32>() / count;
+        (translational, rotational)
+    }
+
+    fn thermostat(&self, particles: &mut Particles) {
+        let (translational, rotational) = Self::temperatures(particles);
+        let rate = self.time_step / self.thermostat.max(self.time_step);
+        let scale = |current: f32| {
+            if current <= 0.0 {
+                return 1.0;
+            }
+            (1.0 + rate * (self.temperature / current - 1.0)).max(0.0).sqrt()
+        };
+        let (translational, rotational) = (scale(translational), scale(rotational));
+        for velocity in &mut particles.velocities {
+            velocity[0] *= translational;
+            velocity[1] *= translational;
+        }
+        for spin in &mut particles.angular_velocities {
+            *spin *= rotational;
+        }
+    }
+
+    fn step(&self, particles: &mut Particles, forces: Forces) -> Forces {
+        Self::kick(particles, &forces, self.time_step);
+        for (position, velocity) in particles.positions.iter_mut().zip(&particles.velocities) {
+            position[0] += self.time_step * velocity[0];
+            position[1] += self.time_step * velocity[1];
+        }
+        for (orientation, spin) in particles.orientations.iter_mut().zip(&particles.angular_velocities) {
+            *orientation = (*orientation + self.time_step * spin).rem_euclid(TAU);
+        }
+        particles.wrap();
+        let forces = self.forces(particles);
+        Self::kick(particles, &forces, self.time_step);
+        self.thermostat(particles);
+        forces
+    }
+}
+
+impl AlgorithmComputation for GayBerne {
+    fn compute(&mut self, _iteration: usize, data: &[DataType]) -> Vec<DataType> {
+        let mut particles = match DataType::particles(data) {
+            Some(particles) => particles.clone(),
+            None => self.initial_particles(),
+        };
+        particles.aspect = self.kappa;
+        let mut forces = self.forces(&particles);
+        for _ in 0..self.substeps as usize {
+            forces = self.step(&mut particles, forces);
+            self.time += self.time_step;
+        }
+        vec![DataType::Particles(particles)]
+    }
+
+    fn parameters(&self) -> Vec<Parameter> {
+        vec![
+            Parameter::new("particles", self.count),
+            Parameter::new("density", self.density),
+            Parameter::new("temperature", self.temperature),
+            Parameter::new("time_step", self.time_step),
+            Parameter::new("substeps", self.substeps),
+            Parameter::new("kappa", self.kappa),
+            Parameter::new("kappa_prime", self.kappa_prime),
+            Parameter::new("mu", self.mu),
+            Parameter::new("nu", self.nu),
+            Parameter::new("cutoff", self.cutoff),
+            Parameter::new("thermostat", self.thermostat),
+        ]
+    }
+
+    fn set_parameter(&mut self, name: &str, value: f32) {
+        match name {
+            "particles" => self.count = value.max(1.0),
+            "density" => self.density = value.max(f32::EPSILON),
+            "temperature" => self.temperature = value.max(0.0),
+            "time_step" => self.time_step = value.max(f32::EPSILON),
+            "substeps" => self.substeps = value.max(1.0),
+            "kappa" => self.kappa = value.max(1.0),
+            "kappa_prime" => self.kappa_prime = value.max(f32::EPSILON),
+            "mu" => self.mu = value,
+            "nu" => self.nu = value,
+            "cutoff" => self.cutoff = value.max(1.0),
+            "thermostat" => self.thermostat = value.max(f32::EPSILON),
+            _ => {}
+        }
+    }
+
+    fn seed(&mut self, seed: u64) {
+        self.rng = StdRng::seed_from_u64(seed);
+    }
+
+    /// Particles move in a box of their own, whatever the lattice.
+    fn supports_layers(&self) -> bool {
+        true
+    }
+
+    fn observables(&self, data: &[DataType]) -> Vec<(String, f64)> {
+        let Some(particles) = DataType::particles(data).filter(|particles| !particles.is_empty()) else {
+            return Vec::new();
+        };
+        let count = particles.len().max(1) as f64;
+        let (translational, rotational) = Self::temperatures(particles);
+        let kinetic = (2.0 * translational + rotational) as f64 / 2.0;
+        let potential = self.forces(particles).energy as f64 / count;
+        let (cos_sum, sin_sum) = particles.orientations.iter().fold((0.0f64, 0.0f64), |(c, s), &angle| {
+            (c + (2.0 * angle as f64).cos(), s + (2.0 * angle as f64).sin())
+        });
+
+        vec![
+            ("energy".to_owned(), kinetic + potential),
+            ("potential_energy".to_owned(), potential),
+            ("kinetic_temperature".to_owned(), translational as f64),
+            (
+                "order_parameter".to_owned(),
+                (cos_sum * cos_sum + sin_sum * sin_sum).sqrt() / count,
+            ),
+            ("time".to_owned(), self.time as f64),
+        ]
+    }
+}
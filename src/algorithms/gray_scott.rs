@@ -0,0 +1,206 @@
+use crate::algorithm_processor::*;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+/// Gray-Scott reaction-diffusion, U + 2V -> 3V with U fed at rate F and V removed at F + k:
+///
+/// du/dt = Du lap u - u v^2 + F (1 - u)
+/// dv/dt = Dv lap v + u v^2 - (F + k) v
+///
+/// integrated with forward Euler on a unit-spaced grid. The run starts from the unreacted state
+/// u = 1, v = 0 with a seeded square in the middle, as in Pearson's 1993 patterns. Free and
+/// periodic boundaries behave as usual; fixed and weak ones hold the edge at the unreacted state.
+pub struct GrayScott {
+    feed: f32,
+    kill: f32,
+    diffusion_u: f32,
+    diffusion_v: f32,
+    time_step: f32,
+    substeps: f32,
+    display_u: f32,
+    conditions: LatticeConditions,
+    state: Option<Concentrations>,
+    /// Clicks waiting to be seeded before the next step, as position and radius in cells.
+    perturbations: Vec<((f32, f32), f32)>,
+    time: f32,
+    rng: StdRng,
+}
+
+struct Concentrations {
+    u: Vec<f32>,
+    v: Vec<f32>,
+    dimensions: (usize, usize),
+}
+
+impl GrayScott {
+    pub fn new() -> Self {
+        Self {
+            feed: 0.037,
+            kill: 0.06,
+            diffusion_u: 0.2,
+            diffusion_v: 0.1,
+            time_step: 1.0,
+            substeps: 20.0,
+            display_u: 0.0,
+            conditions: LatticeConditions::default(),
+            state: None,
+            perturbations: Vec::new(),
+            time: 0.0,
+            rng: StdRng::from_os_rng(),
+        }
+    }
+
+    fn initial_state(&mut self, dimensions: (usize, usize)) -> Concentrations {
+        let (rows, cols) = dimensions;
+        let mut state = Concentrations {
+            u: vec![1.0; rows * cols],
+            v: vec![0.0; rows * cols],
+            dimensions,
+        };
+        let half = (rows.min(cols) / 20).max(1) as f32;
+        Self::seed_region(&mut state, (0.5, 0.5), |dx, dy| dx.abs() <= half && dy.abs() <= half);
+        for (u, v) in state.u.iter_mut().zip(&mut state.v) {
+            *u += self.rng.random_range(-0.01..0.01);
+            *v += self.rng.random_range(-0.01..0.01);
+            *v = v.max(0.0);
+        }
+        state
+    }
+
+    /// Sets u = 0.5, v = 0.25 where `inside` holds for the offset in cells from the fractional `center`.
+    fn seed_region(state: &mut Concentrations, (x, y): (f32, f32), inside: impl Fn(f32, f32) -> bool) {
+        let (rows, cols) = state.dimensions;
+        let (center_col, center_row) = (x * cols as f32, y * rows as f32);
+        for row in 0..rows {
+            for col in 0..cols {
+                let dx = col as f32 + 0.5 - center_col;
+                let dy = row as f32 + 0.5 - center_row;
+                if inside(dx, dy) {
+                    state.u[row * cols + col] = 0.5;
+                    state.v[row * cols + col] = 0.25;
+                }
+            }
+        }
+    }
+
+    fn laplacian(&self, values: &[f32], unreacted: f32, dimensions: (usize, usize), index: usize) -> f32 {
+        self.conditions
+            .neighbours(dimensions, index, 1.0)
+            .map(|neighbour| match neighbour {
+                Neighbour::Site(other) => values[other] - values[index],
+                Neighbour::Anchor { .. } => unreacted - values[index],
+            })
+            .sum()
+    }
+
+    fn step(&self, state: &mut Concentrations) {
+        let dimensions = state.dimensions;
+        let (u, v) = (&state.u, &state.v);
+        let mut next_u = u.clone();
+        let mut next_v = v.clone();
+        for index in 0..u.len() {
+            if self.conditions.is_frozen(index) {
+                continue;
+            }
+            let reaction = u[index] * v[index] * v[index];
+            next_u[index] += self.time_step
+                * (self.diffusion_u * self.laplacian(u, 1.0, dimensions, index) - reaction
+                    + self.feed * (1.0 - u[index]));
+            next_v[index] += self.time_step
+                * (self.diffusion_v * self.laplacian(v, 0.0, dimensions, index) + reaction
+                    - (self.feed + self.kill) * v[index]);
+        }
+        state.u = next_u;
+        state.v = next_v;
+    }
+}
+
+impl Default for GrayScott {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl AlgorithmComputation for GrayScott {
+    fn compute(&mut self, _iteration: usize, data: &[DataType]) -> Vec<DataType> {
+        let Some(dimensions) = data.first().and_then(|data| match data {
+            DataType::OrientedGrid(_, dimensions)
+            | DataType::ScalarGrid(_, dimensions)
+            | DataType::VectorGrid(_, dimensions) => Some(*dimensions),
+            DataType::DirectorField(_, (rows, cols, _)) => Some((*rows, *cols)),
+            DataType::Particles(_) => None,
+        }) else {
+            return Vec::new();
+        };
+        let mut state = match self.state.take() {
+            Some(state) if state.dimensions == dimensions => state,
+            _ => self.initial_state(dimensions),
+        };
+        for (position, radius) in self.perturbations.drain(..) {
+            Self::seed_region(&mut state, position, |dx, dy| dx * dx + dy * dy <= radius * radius);
+        }
+        for _ in 0..self.substeps as usize {
+            self.step(&mut state);
+            self.time += self.time_step;
+        }
+
+        let shown = if self.display_u != 0.0 { &state.u } else { &state.v };
+        let output = vec![DataType::ScalarGrid(shown.clone(), dimensions)];
+        self.state = Some(state);
+        output
+    }
+
+    fn parameters(&self) -> Vec<Parameter> {
+        vec![
+            Parameter::new("feed", self.feed),
+            Parameter::new("kill", self.kill),
+            Parameter::new("diffusion_u", self.diffusion_u),
+            Parameter::new("diffusion_v", self.diffusion_v),
+            Parameter::new("time_step", self.time_step),
+            Parameter::new("substeps", self.substeps),
+            Parameter::new("display_u", self.display_u),
+        ]
+    }
+
+    fn set_parameter(&mut self, name: &str, value: f32) {
+        match name {
+            "feed" => self.feed = value.max(0.0),
+            "kill" => self.kill = value.max(0.0),
+            "diffusion_u" => self.diffusion_u = value.max(0.0),
+            "diffusion_v" => self.diffusion_v = value.max(0.0),
+            "time_step" => self.time_step = value.max(f32::EPSILON),
+            "substeps" => self.substeps = value.max(1.0),
+            "display_u" => self.display_u = value,
+            _ => {}
+        }
+    }
+
+    fn seed(&mut self, seed: u64) {
+        self.rng = StdRng::seed_from_u64(seed);
+    }
+
+    fn set_conditions(&mut self, conditions: &LatticeConditions) {
+        self.conditions = conditions.clone();
+    }
+
+    fn perturb(&mut self, position: (f32, f32), radius: f32) {
+        self.perturbations.push((position, radius.max(0.5)));
+    }
+
+    fn scalar_range(&self) -> Option<(f32, f32)> {
+        Some(if self.display_u != 0.0 { (0.0, 1.0) } else { (0.0, 0.5) })
+    }
+
+    fn observables(&self, _data: &[DataType]) -> Vec<(String, f64)> {
+        let Some(state) = &self.state else {
+            return Vec::new();
+        };
+        let sites = state.u.len().max(1) as f64;
+        let mean = |values: &[f32]| values.iter().map(|&value| value as f64).sum::<f64>() / sites;
+        vec![
+            ("mean_u".to_owned(), mean(&state.u)),
+            ("mean_v".to_owned(), mean(&state.v)),
+            ("time".to_owned(), self.time as f64),
+        ]
+    }
+}
@@ -0,0 +1,321 @@
+use crate::algorithm_processor::*;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HeatScheme {
+    /// Forward Euler, split into smaller steps when the time step breaks the CFL limit.
+    Explicit,
+    /// Trapezoidal in time, unconditionally stable; solved by conjugate gradients.
+    CrankNicolson,
+}
+
+const SOLVER_TOLERANCE: f64 = 1e-7;
+const SOLVER_ITERATIONS: usize = 1000;
+
+/// The heat equation dT/dt = D lap T + s(r) on a unit-spaced grid, starting from T = 0.
+///
+/// Free edges are insulating (Neumann), `fixed <T>` edges hold a ghost layer at T (Dirichlet)
+/// and `weak <T> <h>` edges exchange heat at rate h (Robin). Frozen cells keep their temperature
+/// and painted sources add heat at their rate, sinks remove it.
+pub struct HeatEquation {
+    scheme: HeatScheme,
+    diffusivity: f32,
+    time_step: f32,
+    substeps: f32,
+    conditions: LatticeConditions,
+    time: f64,
+}
+
+impl HeatEquation {
+    pub fn new(scheme: HeatScheme) -> Self {
+        Self {
+            scheme,
+            diffusivity: 1.0,
+            time_step: match scheme {
+                HeatScheme::Explicit => 0.2,
+                HeatScheme::CrankNicolson => 1.0,
+            },
+            substeps: 10.0,
+            conditions: LatticeConditions::default(),
+            time: 0.0,
+        }
+    }
+
+    /// Largest stable explicit step, D dt <= 2 / r with r the largest row sum of |lap|: 8 in the
+    /// bulk, 6 + h beside an edge exchanging heat at rate h and 4 + h1 + h2 in a corner.
+    fn cfl_limit(&self) -> f32 {
+        let b = &self.conditions.boundaries;
+        let rate = |boundary: Boundary| match boundary {
+            Boundary::Fixed { .. } => 1.0,
+            Boundary::Weak { strength, .. } => strength.max(0.0),
+            Boundary::Periodic | Boundary::Free => 0.0,
+        };
+        let (left, right, bottom, top) = (rate(b.left), rate(b.right), rate(b.bottom), rate(b.top));
+        let row_sum = [8.0, 6.0 + left.max(right).max(bottom).max(top), 4.0 + left.max(right) + bottom.max(top)]
+            .into_iter()
+            .fold(0.0f32, f32::max);
+        2.0 / (row_sum * self.diffusivity.max(f32::EPSILON))
+    }
+
+    /// Stable explicit steps each `time_step` is split into.
+    fn explicit_split(&self) -> usize {
+        (self.time_step / self.cfl_limit()).ceil().max(1.0) as usize
+    }
+
+    /// Laplacian including the boundary terms, lap T_i = sum_j (T_j - T_i) + sum_b h_b (T_b - T_i).
+    fn laplacian(&self, temperatures: &[f32], dimensions: (usize, usize), index: usize) -> f32 {
+        self.conditions
+            .neighbours(dimensions, index, 1.0)
+            .map(|neighbour| match neighbour {
+                Neighbour::Site(other) => temperatures[other] - temperatures[index],
+                Neighbour::Anchor { value: boundary, strength } => strength * (boundary - temperatures[index]),
+            })
+            .sum()
+    }
+
+    fn explicit_step(&self, temperatures: &mut Vec<f32>, dimensions: (usize, usize), time_step: f32) {
+        let next = (0..temperatures.len())
+            .map(|index| {
+                if self.conditions.is_frozen(index) {
+                    return temperatures[index];
+                }
+                temperatures[index]
+                    + time_step
+                        * (self.diffusivity * self.laplacian(temperatures, dimensions, index)
+                            + self.conditions.source_rate(index))
+            })
+            .collect();
+        *temperatures = next;
+    }
+
+    /// `(1 - c lap) x` over the free cells with `c = D dt / 2`, frozen cells and boundary values
+    /// moved to the right-hand side. Frozen rows are the identity, which keeps it symmetric.
+    fn implicit_operator(&self, x: &[f64], dimensions: (usize, usize), c: f64) -> Vec<f64> {
+        (0..x.len())
+            .map(|index| {
+                if self.conditions.is_frozen(index) {
+                    return x[index];
+                }
+                let mut value = x[index];
+                for neighbour in self.conditions.neighbours(dimensions, index, 1.0) {
+                    match neighbour {
+                        Neighbour::Site(other) => {
+                            value += c * x[index];
+                            if !self.conditions.is_frozen(other) {
+                                value -= c * x[other];
+                            }
+                        }
+                        Neighbour::Anchor { strength, .. } => value += c * strength as f64 * x[index],
+                    }
+                }
+                value
+            })
+            .collect()
+    }
+
+    fn crank_nicolson_step(&self, temperatures: &mut [f32], dimensions: (usize, usize), time_step: f32) {
+        let c = (self.diffusivity * time_step) as f64 / 2.0;
+        let rhs: Vec<f64> = (0..temperatures.len())
+            .map(|index| {
+                let temperature = temperatures[index] as f64;
+                if self.conditions.is_frozen(index) {
+                    return temperature;
+                }
+                let mut known = 0.0;
+                for neighbour in self.conditions.neighbours(dimensions, index, 1.0) {
+                    match neighbour {
+                        Neighbour::Site(other) if self.conditions.is_frozen(other) => {
+                            known += temperatures[other] as f64
+                        }
+                        Neighbour::Site(_) => {}
+                        Neighbour::Anchor { value: boundary, strength } => known += (strength * boundary) as f64,
+                    }
+                }
+                temperature
+                    + c * (self.laplacian(temperatures, dimensions, index) as f64 + known)
+                    + (time_step * self.conditions.source_rate(index)) as f64
+            })
+            .collect();
+
+        let initial: Vec<f64> = temperatures.iter().map(|&t| t as f64).collect();
+        let solution = conjugate_gradient(|x| self.implicit_operator(x, dimensions, c), &rhs, initial);
+        for (temperature, value) in temperatures.iter_mut().zip(solution) {
+            *temperature = value as f32;
+        }
+    }
+}
+
+/// Solves `A x = b` for a symmetric positive definite `A`, starting from `x`.
+fn conjugate_gradient(apply: impl Fn(&[f64]) -> Vec<f64>, b: &[f64], mut x: Vec<f64>) -> Vec<f64> {
+    let dot = |a: &[f64], b: &[f64]| a.iter().zip(b).map(|(a, b)| a * b).sum::<f64>();
+    let mut residual: Vec<f64> = b.iter().zip(apply(&x)).map(|(b, ax)| b - ax).collect();
+    let mut direction = residual.clone();
+    let mut residual_norm = dot(&residual, &residual);
+    let target = SOLVER_TOLERANCE * SOLVER_TOLERANCE * dot(b, b).max(f64::MIN_POSITIVE);
+
+    for _ in 0..SOLVER_ITERATIONS {
+        if residual_norm <= target {
+            break;
+        }
+        let applied = apply(&direction);
+        let alpha = residual_norm / dot(&direction, &applied);
+        for i in 0..x.len() {
+            x[i] += alpha * direction[i];
+            residual[i] -= alpha * applied[i];
+        }
+        let next_norm = dot(&residual, &residual);
+        let beta = next_norm / residual_norm;
+        for (direction, residual) in direction.iter_mut().zip(&residual) {
+            *direction = residual + beta * *direction;
+        }
+        residual_norm = next_norm;
+    }
+    x
+}
+
+impl AlgorithmComputation for HeatEquation {
+    fn compute(&mut self, _iteration: usize, data: &[DataType]) -> Vec<DataType> {
+        let (mut temperatures, dimensions) = match (DataType::scalar(data), DataType::oriented(data)) {
+            (Some((temperatures, dimensions)), _) => (temperatures.clone(), dimensions),
+            (None, Some((angles, dimensions))) => (vec![0.0; angles.len()], dimensions),
+            (None, None) => return Vec::new(),
+        };
+
+        let (steps, time_step) = match self.scheme {
+            HeatScheme::CrankNicolson => (1, self.time_step),
+            HeatScheme::Explicit => {
+                let split = self.explicit_split();
+                (split, self.time_step / split as f32)
+            }
+        };
+        for _ in 0..self.substeps as usize {
+            for _ in 0..steps {
+                match self.scheme {
+                    HeatScheme::Explicit => self.explicit_step(&mut temperatures, dimensions, time_step),
+                    HeatScheme::CrankNicolson => self.crank_nicolson_step(&mut temperatures, dimensions, time_step),
+                }
+            }
+            self.time += self.time_step as f64;
+        }
+        vec![DataType::ScalarGrid(temperatures, dimensions)]
+    }
+
+    fn parameters(&self) -> Vec<Parameter> {
+        vec![
+            Parameter::new("diffusivity", self.diffusivity),
+            Parameter::new("time_step", self.time_step),
+            Parameter::new("substeps", self.substeps),
+        ]
+    }
+
+    fn set_parameter(&mut self, name: &str, value: f32) {
+        match name {
+            "diffusivity" => self.diffusivity = value.max(0.0),
+            "time_step" => self.time_step = value.max(f32::EPSILON),
+            "substeps" => self.substeps = value.max(1.0),
+            _ => {}
+        }
+    }
+
+    fn set_conditions(&mut self, conditions: &LatticeConditions) {
+        self.conditions = conditions.clone();
+    }
+
+    fn warnings(&self) -> Vec<String> {
+        let split = self.explicit_split();
+        if self.scheme == HeatScheme::Explicit && split > 1 {
+            vec![format!(
+                "time_step {} exceeds the CFL limit {}, each step is split into {split}",
+                self.time_step,
+                self.cfl_limit()
+            )]
+        } else {
+            Vec::new()
+        }
+    }
+
+    fn observables(&self, data: &[DataType]) -> Vec<(String, f64)> {
+        let Some((temperatures, _)) = DataType::scalar(data) else {
+            return Vec::new();
+        };
+        let heat = temperatures.iter().map(|&t| t as f64).sum::<f64>();
+        let maximum = temperatures.iter().fold(f32::NEG_INFINITY, |max, &t| max.max(t));
+        vec![
+            ("mean_temperature".to_owned(), heat / temperatures.len().max(1) as f64),
+            ("max_temperature".to_owned(), maximum as f64),
+            ("heat".to_owned(), heat),
+            ("time".to_owned(), self.time),
+        ]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f64::consts::PI;
+
+    /// Heat kernel of a unit point source released at time zero.
+    fn gaussian(dimensions: (usize, usize), diffusivity: f64, time: f64) -> Vec<f32> {
+        let (rows, cols) = dimensions;
+        let mut values = Vec::with_capacity(rows * cols);
+        for row in 0..rows {
+            for col in 0..cols {
+                let dx = col as f64 - (cols / 2) as f64;
+                let dy = row as f64 - (rows / 2) as f64;
+                let spread = 4.0 * diffusivity * time;
+                values.push(((-(dx * dx + dy * dy) / spread).exp() / (PI * spread)) as f32);
+            }
+        }
+        values
+    }
+
+    fn spreads_like_gaussian(scheme: HeatScheme, time_step: f32) {
+        let dimensions = (64, 64);
+        let (start, duration) = (5.0, 10.0);
+        let mut solver = HeatEquation::new(scheme);
+        solver.set_parameter("time_step", time_step);
+        solver.set_parameter("substeps", (duration / time_step as f64).round() as f32);
+
+        let initial = vec![DataType::ScalarGrid(gaussian(dimensions, 1.0, start), dimensions)];
+        let result = solver.compute(0, &initial);
+        let (temperatures, _) = DataType::scalar(&result).unwrap();
+
+        let expected = gaussian(dimensions, 1.0, start + duration);
+        let peak = expected.iter().fold(0.0f32, |max, &value| max.max(value));
+        let error = temperatures
+            .iter()
+            .zip(&expected)
+            .map(|(value, expected)| (value - expected).abs())
+            .fold(0.0f32, f32::max);
+        assert!(error < 0.01 * peak, "{scheme:?}: error {error} against peak {peak}");
+
+        let heat = |values: &[f32]| values.iter().map(|&value| value as f64).sum::<f64>();
+        let initial_heat = heat(DataType::scalar(&initial).unwrap().0);
+        assert!((heat(temperatures) - initial_heat).abs() < 1e-4, "{scheme:?}: heat not conserved");
+    }
+
+    #[test]
+    fn explicit_spreads_like_gaussian() {
+        spreads_like_gaussian(HeatScheme::Explicit, 0.2);
+    }
+
+    #[test]
+    fn crank_nicolson_spreads_like_gaussian() {
+        spreads_like_gaussian(HeatScheme::CrankNicolson, 0.5);
+    }
+
+    #[test]
+    fn explicit_stays_bounded_at_strong_weak_edges() {
+        let dimensions = (16, 16);
+        let mut solver = HeatEquation::new(HeatScheme::Explicit);
+        let mut conditions = LatticeConditions::default();
+        conditions.boundaries.left = Boundary::Weak { value: 1.0, strength: 10.0 };
+        conditions.boundaries.right = conditions.boundaries.left;
+        solver.set_conditions(&conditions);
+        solver.set_parameter("time_step", 0.25);
+        solver.set_parameter("substeps", 200.0);
+
+        let result = solver.compute(0, &[DataType::ScalarGrid(vec![0.0; 256], dimensions)]);
+        let (temperatures, _) = DataType::scalar(&result).unwrap();
+        assert!(temperatures.iter().all(|&t| (-1e-4..=1.0 + 1e-4).contains(&t)), "{temperatures:?}");
+    }
+}
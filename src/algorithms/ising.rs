@@ -0,0 +1,190 @@
+use crate::algorithm_processor::*;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SpinUpdate {
+    /// Single spin flips.
+    Metropolis,
+    /// Wolff cluster flips, which avoid critical slowing down near T_c.
+    Wolff,
+}
+
+/// The 2D Ising model, E = -J sum s_i s_j - h sum s_i with s = +-1.
+///
+/// Spins start from the sign of cos(angle) of the initial orientations, so `aligned 0` is all up.
+/// Anchored boundaries act as fixed spins of the sign of their anchoring angle.
+pub struct Ising {
+    update: SpinUpdate,
+    temperature: f32,
+    coupling: f32,
+    field: f32,
+    conditions: LatticeConditions,
+    rng: StdRng,
+}
+
+impl Ising {
+    pub fn new(update: SpinUpdate) -> Self {
+        Self {
+            update,
+            temperature: 2.269,
+            coupling: 1.0,
+            field: 0.0,
+            conditions: LatticeConditions::default(),
+            rng: StdRng::from_os_rng(),
+        }
+    }
+
+    fn spin_of(angle: f32) -> f32 {
+        if angle.cos() >= 0.0 { 1.0 } else { -1.0 }
+    }
+
+    /// Sum of the couplings times spins around `index`, including the applied field.
+    fn local_field(&self, spins: &[f32], dimensions: (usize, usize), index: usize) -> f32 {
+        self.conditions
+            .neighbours(dimensions, index, self.coupling)
+            .map(|neighbour| match neighbour {
+                Neighbour::Site(other) => self.coupling * spins[other],
+                Neighbour::Anchor { value: angle, strength } => strength * Self::spin_of(angle),
+            })
+            .sum::<f32>()
+            + self.field
+    }
+
+    fn metropolis_sweep(&mut self, spins: &mut [f32], dimensions: (usize, usize)) {
+        for _ in 0..spins.len() {
+            let index = self.rng.random_range(0..spins.len());
+            if self.conditions.is_frozen(index) {
+                continue;
+            }
+            let delta = 2.0 * spins[index] * self.local_field(spins, dimensions, index);
+            if delta <= 0.0 || self.rng.random::<f32>() < (-delta / self.temperature).exp() {
+                spins[index] = -spins[index];
+            }
+        }
+    }
+
+    /// Grows one cluster from `seed` and flips it. Bonds to frozen sites, anchors and the
+    /// field are left out of the cluster and enter through a Metropolis acceptance instead.
+    /// Returns the size of the cluster.
+    fn wolff_flip(&mut self, spins: &mut [f32], dimensions: (usize, usize), seed: usize) -> usize {
+        let add_probability = 1.0 - (-2.0 * self.coupling.abs() / self.temperature).exp();
+        let mut in_cluster = vec![false; spins.len()];
+        let mut cluster = vec![seed];
+        let mut stack = vec![seed];
+        in_cluster[seed] = true;
+        let mut external = 0.0;
+
+        while let Some(index) = stack.pop() {
+            let spin = spins[index];
+            external += 2.0 * spin * self.field;
+            for neighbour in self.conditions.neighbours(dimensions, index, self.coupling) {
+                match neighbour {
+                    Neighbour::Site(other) if self.conditions.is_frozen(other) => {
+                        external += 2.0 * self.coupling * spin * spins[other];
+                    }
+                    Neighbour::Site(other) => {
+                        if !in_cluster[other]
+                            && self.coupling * spin * spins[other] > 0.0
+                            && self.rng.random::<f32>() < add_probability
+                        {
+                            in_cluster[other] = true;
+                            cluster.push(other);
+                            stack.push(other);
+                        }
+                    }
+                    Neighbour::Anchor { value: angle, strength } => {
+                        external += 2.0 * strength * spin * Self::spin_of(angle);
+                    }
+                }
+            }
+        }
+
+        if external <= 0.0 || self.rng.random::<f32>() < (-external / self.temperature).exp() {
+            for &index in &cluster {
+                spins[index] = -spins[index];
+            }
+        }
+        cluster.len()
+    }
+
+    /// Flips clusters until about as many spins were visited as there are sites.
+    fn wolff_sweep(&mut self, spins: &mut [f32], dimensions: (usize, usize)) {
+        let free = (0..spins.len()).filter(|&index| !self.conditions.is_frozen(index)).count();
+        let mut visited = 0;
+        while visited < free {
+            let seed = self.rng.random_range(0..spins.len());
+            if self.conditions.is_frozen(seed) {
+                continue;
+            }
+            visited += self.wolff_flip(spins, dimensions, seed);
+        }
+    }
+}
+
+impl AlgorithmComputation for Ising {
+    fn compute(&mut self, _iteration: usize, data: &[DataType]) -> Vec<DataType> {
+        let (mut spins, dimensions) = match (DataType::scalar(data), DataType::oriented(data)) {
+            (Some((spins, dimensions)), _) => (spins.clone(), dimensions),
+            (None, Some((angles, dimensions))) => (angles.iter().map(|&a| Self::spin_of(a)).collect(), dimensions),
+            (None, None) => return Vec::new(),
+        };
+        match self.update {
+            SpinUpdate::Metropolis => self.metropolis_sweep(&mut spins, dimensions),
+            SpinUpdate::Wolff => self.wolff_sweep(&mut spins, dimensions),
+        }
+        vec![DataType::ScalarGrid(spins, dimensions)]
+    }
+
+    fn parameters(&self) -> Vec<Parameter> {
+        vec![
+            Parameter::new("temperature", self.temperature),
+            Parameter::new("coupling", self.coupling),
+            Parameter::new("field", self.field),
+        ]
+    }
+
+    fn set_parameter(&mut self, name: &str, value: f32) {
+        match name {
+            "temperature" => self.temperature = value.max(f32::EPSILON),
+            "coupling" => self.coupling = value,
+            "field" => self.field = value,
+            _ => {}
+        }
+    }
+
+    fn seed(&mut self, seed: u64) {
+        self.rng = StdRng::seed_from_u64(seed);
+    }
+
+    fn set_conditions(&mut self, conditions: &LatticeConditions) {
+        self.conditions = conditions.clone();
+    }
+
+    fn scalar_range(&self) -> Option<(f32, f32)> {
+        Some((-1.0, 1.0))
+    }
+
+    fn observables(&self, data: &[DataType]) -> Vec<(String, f64)> {
+        let Some((spins, dimensions)) = DataType::scalar(data) else {
+            return Vec::new();
+        };
+        let sites = spins.len().max(1) as f64;
+
+        let mut energy = 0.0f64;
+        for (index, &spin) in spins.iter().enumerate() {
+            energy -= (self.field * spin) as f64;
+            energy -= self.conditions.bond_energy(dimensions, index, self.coupling, |neighbour| match neighbour {
+                Neighbour::Site(other) => (self.coupling * spin * spins[other]) as f64,
+                Neighbour::Anchor { value: angle, strength } => (strength * spin * Self::spin_of(angle)) as f64,
+            });
+        }
+        let magnetization = spins.iter().map(|&spin| spin as f64).sum::<f64>() / sites;
+
+        vec![
+            ("energy".to_owned(), energy / sites),
+            ("order_parameter".to_owned(), magnetization.abs()),
+            ("magnetization".to_owned(), magnetization),
+        ]
+    }
+}
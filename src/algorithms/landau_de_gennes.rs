@@ -0,0 +1,367 @@
+use crate::algorithm_processor::*;
+
+/// How the elastic term is advanced in time.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TimeStepping {
+    /// Forward Euler, stable for `time_step * L <= 1/4`.
+    Explicit,
+    /// Elastic term backward Euler, solved by Gauss-Seidel sweeps, bulk terms forward Euler.
+    SemiImplicit,
+}
+
+const GAUSS_SEIDEL_SWEEPS: usize = 10;
+
+/// `(Q_xx, Q_xy)` of every site.
+type TensorField = Vec<[f32; 2]>;
+
+/// Gradient flow `dQ/dt = -dF/dQ` of the 2D Landau-de Gennes free energy
+///
+/// F = sum A/2 tr(Q^2) + B/3 tr(Q^3) + C/4 tr(Q^2)^2 + L/2 |grad Q|^2 - chi E.Q.E
+///
+/// on the lattice with unit spacing. `Q = S (n n - I/2)` is stored as `q = (Q_xx, Q_xy)`.
+/// tr(Q^3) vanishes for 2x2 traceless tensors, so `B` has no effect in 2D; it is accepted so
+/// that parameter sets can be shared with 3D work.
+///
+/// Each iteration emits the director angles and the scalar order `S` and reads them back on
+/// the next one. Missing scalar order starts at the bulk value `S0 = sqrt(-2A/C)`.
+pub struct LandauDeGennes {
+    a: f32,
+    b: f32,
+    c: f32,
+    elastic: f32,
+    time_step: f32,
+    substeps: usize,
+    anisotropy: f32,
+    stepping: TimeStepping,
+    conditions: LatticeConditions,
+    /// Iteration the field is evaluated at.
+    time: f32,
+}
+
+impl LandauDeGennes {
+    pub fn new(stepping: TimeStepping) -> Self {
+        Self {
+            a: -1.0,
+            b: 0.0,
+            c: 1.0,
+            elastic: 1.0,
+            time_step: match stepping {
+                TimeStepping::Explicit => 0.1,
+                TimeStepping::SemiImplicit => 0.5,
+            },
+            substeps: 10,
+            anisotropy: 1.0,
+            stepping,
+            conditions: LatticeConditions::default(),
+            time: 0.0,
+        }
+    }
+
+    /// Bulk scalar order, or 1 above the transition where the bulk is isotropic.
+    fn equilibrium_order(&self) -> f32 {
+        if self.a < 0.0 && self.c > 0.0 {
+            (-2.0 * self.a / self.c).sqrt()
+        } else {
+            1.0
+        }
+    }
+
+    pub(crate) fn tensor(order: f32, angle: f32) -> [f32; 2] {
+        let half = 0.5 * order;
+        [half * (2.0 * angle).cos(), half * (2.0 * angle).sin()]
+    }
+
+    /// Director angle in `[0, pi)` and scalar order `S`.
+    pub(crate) fn director([q1, q2]: [f32; 2]) -> (f32, f32) {
+        let angle = 0.5 * q2.atan2(q1);
+        let angle = if angle < 0.0 { angle + std::f32::consts::PI } else { angle };
+        (angle, 2.0 * (q1 * q1 + q2 * q2).sqrt())
+    }
+
+    fn read_tensors(&self, data: &[DataType]) -> Option<(TensorField, (usize, usize))> {
+        let (angles, dimensions) = DataType::oriented(data)?;
+        let order = DataType::scalar(data)
+            .filter(|(order, order_dimensions)| *order_dimensions == dimensions && order.len() == angles.len())
+            .map(|(order, _)| order);
+        let bulk = self.equilibrium_order();
+        let q = angles
+            .iter()
+            .enumerate()
+            .map(|(index, &angle)| Self::tensor(order.map_or(bulk, |order| order[index]), angle))
+            .collect();
+        Some((q, dimensions))
+    }
+
+    fn position(dimensions: (usize, usize), index: usize) -> (f32, f32) {
+        let (rows, cols) = dimensions;
+        (
+            ((index % cols) as f32 + 0.5) / cols as f32,
+            ((index / cols) as f32 + 0.5) / rows as f32,
+        )
+    }
+
+    /// Neighbours of `index` as `(weight, q)`; anchoring acts as a ghost site held at the bulk order.
+    fn neighbours<'a>(
+        &'a self,
+        q: &'a [[f32; 2]],
+        dimensions: (usize, usize),
+        index: usize,
+    ) -> impl Iterator<Item = (f32, [f32; 2])> + 'a {
+        let bulk = self.equilibrium_order();
+        self.conditions
+            .neighbours(dimensions, index, 1.0)
+            .map(move |neighbour| match neighbour {
+                Neighbour::Site(site) => (1.0, q[site]),
+                Neighbour::Anchor { value: angle, strength } => (strength, Self::tensor(bulk, angle)),
+            })
+    }
+
+    /// `-dF/dQ` without the elastic term.
+    fn local_rate(&self, dimensions: (usize, usize), index: usize, [q1, q2]: [f32; 2]) -> [f32; 2] {
+        let bulk = self.a + 2.0 * self.c * (q1 * q1 + q2 * q2);
+        let mut rate = [-bulk * q1, -bulk * q2];
+
+        let field = &self.conditions.field;
+        if !field.is_off() {
+            let strength = field.strength(self.time, Self::position(dimensions, index));
+            let torque = 0.5 * self.anisotropy * strength * strength;
+            rate[0] += torque * (2.0 * field.angle).cos();
+            rate[1] += torque * (2.0 * field.angle).sin();
+        }
+        rate
+    }
+
+    fn step(&self, q: &mut [[f32; 2]], dimensions: (usize, usize)) {
+        let dt = self.time_step;
+        let mut next = q.to_vec();
+        for index in 0..q.len() {
+            if self.conditions.is_frozen(index) {
+                continue;
+            }
+            let rate = self.local_rate(dimensions, index, q[index]);
+            let mut laplacian = [0.0; 2];
+            if self.stepping == TimeStepping::Explicit {
+                for (weight, neighbour) in self.neighbours(q, dimensions, index) {
+                    laplacian[0] += weight * (neighbour[0] - q[index][0]);
+                    laplacian[1] += weight * (neighbour[1] - q[index][1]);
+                }
+            }
+            for component in 0..2 {
+                next[index][component] =
+                    q[index][component] + dt * (rate[component] + self.elastic * laplacian[component]);
+            }
+        }
+
+        if self.stepping == TimeStepping::SemiImplicit {
+            // Solve (1 - dt L lap) q' = next in place, starting from the current field.
+            let right_hand_side = next;
+            let mut solution = q.to_vec();
+            for _ in 0..GAUSS_SEIDEL_SWEEPS {
+                for index in 0..q.len() {
+                    if self.conditions.is_frozen(index) {
+                        continue;
+                    }
+                    let mut diagonal = 1.0;
+                    let mut value = right_hand_side[index];
+                    for (weight, neighbour) in self.neighbours(&solution, dimensions, index) {
+                        let coefficient = dt * self.elastic * weight;
+                        diagonal += coefficient;
+                        value[0] += coefficient * neighbour[0];
+                        value[1] += coefficient * neighbour[1];
+                    }
+                    solution[index] = [value[0] / diagonal, value[1] / diagonal];
+                }
+            }
+            q.copy_from_slice(&solution);
+        } else {
+            q.copy_from_slice(&next);
+        }
+    }
+}
+
+impl AlgorithmComputation for LandauDeGennes {
+    fn compute(&mut self, iteration: usize, data: &[DataType]) -> Vec<DataType> {
+        self.time = iteration as f32;
+        let Some((mut q, dimensions)) = self.read_tensors(data) else {
+            return Vec::new();
+        };
+        for _ in 0..self.substeps {
+            self.step(&mut q, dimensions);
+        }
+
+        let (angles, order) = q.into_iter().map(Self::director).unzip();
+        vec![
+            DataType::OrientedGrid(angles, dimensions),
+            DataType::ScalarGrid(order, dimensions),
+        ]
+    }
+
+    fn parameters(&self) -> Vec<Parameter> {
+        vec![
+            Parameter::new("A", self.a),
+            Parameter::new("B", self.b),
+            Parameter::new("C", self.c),
+            Parameter::new("L", self.elastic),
+            Parameter::new("time_step", self.time_step),
+            Parameter::new("substeps", self.substeps as f32),
+            Parameter::new("anisotropy", self.anisotropy),
+        ]
+    }
+
+    fn set_parameter(&mut self, name: &str, value: f32) {
+        match name {
+            "A" => self.a = value,
+            "B" => self.b = value,
+            "C" => self.c = value,
+            "L" => self.elastic = value.max(0.0),
+            "time_step" => self.time_step = value.max(f32::EPSILON),
+            "substeps" => self.substeps = value.max(1.0) as usize,
+            "anisotropy" => self.anisotropy = value,
+            _ => {}
+        }
+    }
+
+    fn set_conditions(&mut self, conditions: &LatticeConditions) {
+        self.conditions = conditions.clone();
+    }
+
+    fn observables(&self, data: &[DataType]) -> Vec<(String, f64)> {
+        let Some((q, dimensions)) = self.read_tensors(data) else {
+            return Vec::new();
+        };
+        let sites = q.len().max(1) as f64;
+        let field = &self.conditions.field;
+
+        let (mut free_energy, mut order) = (0.0f64, 0.0f64);
+        let (mut cos_sum, mut sin_sum) = (0.0f64, 0.0f64);
+        for (index, &[q1, q2]) in q.iter().enumerate() {
+            let magnitude = (q1 * q1 + q2 * q2) as f64;
+            free_energy += self.a as f64 * magnitude + self.c as f64 * magnitude * magnitude;
+            free_energy += self.conditions.bond_energy(dimensions, index, 1.0, |neighbour| {
+                let (weight, other) = match neighbour {
+                    Neighbour::Site(site) => (1.0, q[site]),
+                    Neighbour::Anchor { value: angle, strength } => {
+                        (strength, Self::tensor(self.equilibrium_order(), angle))
+                    }
+                };
+                let gradient = ((other[0] - q1).powi(2) + (other[1] - q2).powi(2)) as f64;
+                weight as f64 * self.elastic as f64 * gradient
+            });
+            if !field.is_off() {
+                let strength = field.strength(self.time, Self::position(dimensions, index)) as f64;
+                let projection = q1 as f64 * (2.0 * field.angle as f64).cos()
+                    + q2 as f64 * (2.0 * field.angle as f64).sin();
+                free_energy -= self.anisotropy as f64 * strength * strength * projection;
+            }
+
+            let (angle, scalar_order) = Self::director([q1, q2]);
+            order += scalar_order as f64;
+            cos_sum += (2.0 * angle as f64).cos();
+            sin_sum += (2.0 * angle as f64).sin();
+        }
+
+        vec![
+            ("free_energy".to_owned(), free_energy / sites),
+            ("scalar_order".to_owned(), order / sites),
+            (
+                "order_parameter".to_owned(),
+                (cos_sum * cos_sum + sin_sum * sin_sum).sqrt() / sites,
+            ),
+            (
+                "time".to_owned(),
+                ((self.time + 1.0) * self.substeps as f32 * self.time_step) as f64,
+            ),
+        ]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f32::consts::PI;
+
+    /// Far field of the +1/2 defect: with `psi = Q_xx + i Q_xy = (S/2) e^(i phi)` the profile
+    /// `f = S/S0` solves the Ginzburg-Landau vortex equation, whose asymptotic expansion is
+    /// `f ~ 1 - 1/(2 rho^2) - 9/(8 rho^4) - 161/(16 rho^6)` with `rho = r / xi` and `xi^2 = L / -A`.
+    fn far_field(r: f32, xi: f32) -> f32 {
+        let rho2 = (r / xi).powi(2);
+        1.0 - 1.0 / (2.0 * rho2) - 9.0 / (8.0 * rho2.powi(2)) - 161.0 / (16.0 * rho2.powi(3))
+    }
+
+    #[test]
+    fn relaxes_to_half_defect_profile() {
+        let size = 41;
+        let dimensions = (size, size);
+        let center = (size / 2) as f32;
+        let (a, c, elastic) = (-1.0f32, 1.0f32, 4.0f32);
+        let xi = (elastic / -a).sqrt();
+        let bulk = (-2.0 * a / c).sqrt();
+
+        let mut solver = LandauDeGennes::new(TimeStepping::SemiImplicit);
+        solver.set_parameter("A", a);
+        solver.set_parameter("C", c);
+        solver.set_parameter("L", elastic);
+        solver.set_parameter("substeps", 50.0);
+
+        // The outermost ring is frozen at the far-field solution and acts as a Dirichlet boundary.
+        let mut mask = Mask::new(dimensions);
+        let mut angles = Vec::new();
+        let mut order = Vec::new();
+        for row in 0..size {
+            for col in 0..size {
+                let (x, y) = (col as f32 - center, row as f32 - center);
+                let r = (x * x + y * y).sqrt();
+                angles.push(0.5 * y.atan2(x));
+                order.push(bulk * far_field(r, xi).max(0.0));
+                mask.frozen[row * size + col] = row == 0 || col == 0 || row == size - 1 || col == size - 1;
+            }
+        }
+        solver.set_conditions(&LatticeConditions {
+            boundaries: Boundaries {
+                left: Boundary::Free,
+                right: Boundary::Free,
+                bottom: Boundary::Free,
+                top: Boundary::Free,
+                ..Default::default()
+            },
+            mask: Some(mask),
+            ..Default::default()
+        });
+
+        let mut data = vec![
+            DataType::OrientedGrid(angles, dimensions),
+            DataType::ScalarGrid(order, dimensions),
+        ];
+        for iteration in 0..40 {
+            data = solver.compute(iteration, &data);
+        }
+        let (angles, _) = DataType::oriented(&data).unwrap();
+        let (order, _) = DataType::scalar(&data).unwrap();
+
+        let core = (size / 2) * size + size / 2;
+        assert!(order[core] < 0.05 * bulk, "core order {}", order[core]);
+
+        for row in 1..size - 1 {
+            for col in 1..size - 1 {
+                let index = row * size + col;
+                let (x, y) = (col as f32 - center, row as f32 - center);
+                let r = (x * x + y * y).sqrt();
+                if r >= 3.0 {
+                    // Directors are only defined modulo pi.
+                    let expected = 0.5 * y.atan2(x);
+                    let difference = (angles[index] - expected).rem_euclid(PI);
+                    let difference = difference.min(PI - difference);
+                    assert!(difference < 0.02, "director off by {difference} at r = {r}");
+                }
+                if (8.0..=16.0).contains(&r) {
+                    let profile = order[index] / bulk;
+                    let expected = far_field(r, xi);
+                    assert!(
+                        (profile - expected).abs() < 0.005,
+                        "S/S0 = {profile}, expected {expected} at r = {r}"
+                    );
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,261 @@
+use crate::algorithm_processor::*;
+use crate::algorithms::Mesophase;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::f32::consts::{PI, TAU};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Integrator {
+    /// theta += dt F(theta) + noise.
+    EulerMaruyama,
+    /// Predicts with Euler-Maruyama, then steps with the mean of both drifts and the same noise.
+    Heun,
+}
+
+/// Overdamped (model A) rotor dynamics of the orientation field,
+///
+/// d theta_i/dt = -Gamma dE/d theta_i + sqrt(2 Gamma T) eta_i(t),
+///
+/// with Gaussian white noise eta and the Lebwohl-Lasher energy, E = -J sum P2(cos(theta_i -
+/// theta_j)) - chi sum (n_i . E)^2, or with `polar` set the XY energy, E = -J sum cos(theta_i -
+/// theta_j) - sum h cos(theta_i - phi), plus the cholesteric and smectic terms of `Mesophase`.
+/// Anchored boundaries pull with their strength and frozen cells keep their angle. Unlike a Monte
+/// Carlo sweep an iteration advances a real time of `substeps * time_step`, reported as the `time`
+/// observable.
+pub struct LangevinRotors {
+    integrator: Integrator,
+    mobility: f32,
+    time_step: f32,
+    temperature: f32,
+    coupling: f32,
+    anisotropy: f32,
+    polar: f32,
+    substeps: f32,
+    mesophase: Mesophase,
+    conditions: LatticeConditions,
+    /// Iteration the field is evaluated at.
+    field_time: f32,
+    time: f64,
+    rng: StdRng,
+}
+
+impl LangevinRotors {
+    pub fn new(integrator: Integrator) -> Self {
+        Self {
+            integrator,
+            mobility: 1.0,
+            time_step: 0.05,
+            temperature: 0.3,
+            coupling: 1.0,
+            anisotropy: 1.0,
+            polar: 0.0,
+            substeps: 10.0,
+            mesophase: Mesophase::default(),
+            conditions: LatticeConditions::default(),
+            field_time: 0.0,
+            time: 0.0,
+            rng: StdRng::from_os_rng(),
+        }
+    }
+
+    fn is_polar(&self) -> bool {
+        self.polar > 0.0
+    }
+
+    /// Bond energy of `angle` with a neighbour at `other`.
+    fn bond(&self, strength: f32, angle: f32, other: f32) -> f32 {
+        let cos = (angle - other).cos();
+        if self.is_polar() { -strength * cos } else { -strength * (1.5 * cos * cos - 0.5) }
+    }
+
+    /// -dE/d theta of that bond.
+    fn bond_torque(&self, strength: f32, angle: f32, other: f32) -> f32 {
+        let difference = angle - other;
+        if self.is_polar() { -strength * difference.sin() } else { -1.5 * strength * (2.0 * difference).sin() }
+    }
+
+    fn field_strength(&self, (rows, cols): (usize, usize), index: usize) -> f32 {
+        let position = (
+            ((index % cols) as f32 + 0.5) / cols as f32,
+            ((index / cols) as f32 + 0.5) / rows as f32,
+        );
+        self.conditions.field.strength(self.field_time, position)
+    }
+
+    fn field_energy(&self, dimensions: (usize, usize), index: usize, angle: f32) -> f32 {
+        let field = &self.conditions.field;
+        if field.is_off() {
+            return 0.0;
+        }
+        let strength = self.field_strength(dimensions, index);
+        let cos = (angle - field.angle).cos();
+        if self.is_polar() { -strength * cos } else { -self.anisotropy * strength * strength * cos * cos }
+    }
+
+    fn field_torque(&self, dimensions: (usize, usize), index: usize, angle: f32) -> f32 {
+        let field = &self.conditions.field;
+        if field.is_off() {
+            return 0.0;
+        }
+        let strength = self.field_strength(dimensions, index);
+        let difference = angle - field.angle;
+        if self.is_polar() {
+            -strength * difference.sin()
+        } else {
+            -self.anisotropy * strength * strength * (2.0 * difference).sin()
+        }
+    }
+
+    /// -Gamma dE/d theta of every site, zero on frozen ones.
+    fn drift(&self, angles: &[f32], dimensions: (usize, usize)) -> Vec<f32> {
+        (0..angles.len())
+            .map(|index| {
+                if self.conditions.is_frozen(index) {
+                    return 0.0;
+                }
+                let angle = angles[index];
+                let torque = self
+                    .conditions
+                    .neighbours(dimensions, index, self.coupling)
+                    .map(|neighbour| match neighbour {
+                        Neighbour::Site(other) => {
+                            let twist = self.mesophase.twist(dimensions, index, other);
+                            self.bond_torque(self.coupling, angle + twist, angles[other])
+                        }
+                        Neighbour::Anchor { value: anchor, strength } => self.bond_torque(strength, angle, anchor),
+                    })
+                    .sum::<f32>()
+                    + self.field_torque(dimensions, index, angle)
+                    + self.mesophase.smectic_torque(dimensions, index, angle);
+                self.mobility * torque
+            })
+            .collect()
+    }
+
+    fn gaussian(&mut self) -> f32 {
+        let u = self.rng.random::<f32>().max(f32::MIN_POSITIVE);
+        (-2.0 * u.ln()).sqrt() * (TAU * self.rng.random::<f32>()).cos()
+    }
+
+    fn step(&mut self, angles: &mut [f32], dimensions: (usize, usize)) {
+        let dt = self.time_step;
+        let amplitude = (2.0 * self.mobility * self.temperature * dt).sqrt();
+        let mut noise = vec![0.0; angles.len()];
+        for (index, noise) in noise.iter_mut().enumerate() {
+            if !self.conditions.is_frozen(index) {
+                *noise = amplitude * self.gaussian();
+            }
+        }
+        let drift = self.drift(angles, dimensions);
+        let predicted: Vec<f32> = angles
+            .iter()
+            .zip(drift.iter().zip(&noise))
+            .map(|(angle, (drift, noise))| angle + dt * drift + noise)
+            .collect();
+        match self.integrator {
+            Integrator::EulerMaruyama => angles.copy_from_slice(&predicted),
+            Integrator::Heun => {
+                let corrected = self.drift(&predicted, dimensions);
+                let updates = drift.iter().zip(&corrected).zip(&noise);
+                for (angle, ((drift, corrected), noise)) in angles.iter_mut().zip(updates) {
+                    *angle += 0.5 * dt * (drift + corrected) + noise;
+                }
+            }
+        }
+        let period = if self.is_polar() { TAU } else { PI };
+        for angle in angles.iter_mut() {
+            *angle = angle.rem_euclid(period);
+        }
+        self.time += dt as f64;
+    }
+}
+
+impl AlgorithmComputation for LangevinRotors {
+    fn compute(&mut self, iteration: usize, data: &[DataType]) -> Vec<DataType> {
+        self.field_time = iteration as f32;
+        let Some((angles, dimensions)) = DataType::oriented(data) else {
+            return Vec::new();
+        };
+        let mut angles = angles.clone();
+        for _ in 0..self.substeps as usize {
+            self.step(&mut angles, dimensions);
+        }
+        let density = self.mesophase.layer_density(&angles, dimensions);
+        let mut output = vec![DataType::OrientedGrid(angles, dimensions)];
+        output.extend(density.map(|density| DataType::ScalarGrid(density, dimensions)));
+        output
+    }
+
+    fn parameters(&self) -> Vec<Parameter> {
+        let mut parameters = vec![
+            Parameter::new("temperature", self.temperature),
+            Parameter::new("mobility", self.mobility),
+            Parameter::new("time_step", self.time_step),
+            Parameter::new("substeps", self.substeps),
+            Parameter::new("coupling", self.coupling),
+            Parameter::new("anisotropy", self.anisotropy),
+            Parameter::new("polar", self.polar),
+        ];
+        parameters.extend(self.mesophase.parameters());
+        parameters
+    }
+
+    fn set_parameter(&mut self, name: &str, value: f32) {
+        if self.mesophase.set_parameter(name, value) {
+            return;
+        }
+        match name {
+            "temperature" => self.temperature = value.max(0.0),
+            "mobility" => self.mobility = value.max(0.0),
+            "time_step" => self.time_step = value.max(f32::EPSILON),
+            "substeps" => self.substeps = value.round().max(1.0),
+            "coupling" => self.coupling = value,
+            "anisotropy" => self.anisotropy = value,
+            "polar" => self.polar = if value > 0.0 { 1.0 } else { 0.0 },
+            _ => {}
+        }
+    }
+
+    fn seed(&mut self, seed: u64) {
+        self.rng = StdRng::seed_from_u64(seed);
+    }
+
+    fn set_conditions(&mut self, conditions: &LatticeConditions) {
+        self.conditions = conditions.clone();
+    }
+
+    fn scalar_range(&self) -> Option<(f32, f32)> {
+        self.mesophase.is_smectic().then_some((0.0, 1.0))
+    }
+
+    fn observables(&self, data: &[DataType]) -> Vec<(String, f64)> {
+        let Some((angles, dimensions)) = DataType::oriented(data) else {
+            return Vec::new();
+        };
+        let sites = angles.len().max(1) as f64;
+        let multiple = if self.is_polar() { 1.0 } else { 2.0 };
+        let mut energy = 0.0f64;
+        let (mut cos_sum, mut sin_sum) = (0.0f64, 0.0f64);
+        for (index, &angle) in angles.iter().enumerate() {
+            energy += self.field_energy(dimensions, index, angle) as f64;
+            energy += self.mesophase.smectic_energy(dimensions, index, angle) as f64;
+            energy += self.conditions.bond_energy(dimensions, index, self.coupling, |neighbour| match neighbour {
+                Neighbour::Site(other) => {
+                    let twist = self.mesophase.twist(dimensions, index, other);
+                    self.bond(self.coupling, angle + twist, angles[other]) as f64
+                }
+                Neighbour::Anchor { value: anchor, strength } => self.bond(strength, angle, anchor) as f64,
+            });
+            cos_sum += (multiple * angle as f64).cos();
+            sin_sum += (multiple * angle as f64).sin();
+        }
+        vec![
+            ("energy".to_owned(), energy / sites),
+            (
+                "order_parameter".to_owned(),
+                (cos_sum * cos_sum + sin_sum * sin_sum).sqrt() / sites,
+            ),
+            ("time".to_owned(), self.time),
+        ]
+    }
+}
@@ -0,0 +1,323 @@
+use crate::algorithm_processor::*;
+use crate::algorithms::Mesophase;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+/// Metropolis Monte Carlo for the Lebwohl-Lasher model, E = -J sum P2(n_i . n_j), plus
+/// -chi sum (n_i . E)^2 when a field is applied. Planar angles on 2D lattices, unit directors
+/// on 3D ones. 2D lattices can add the cholesteric and smectic terms of `Mesophase`, the
+/// smectic one emitting the layer density as a scalar grid.
+pub struct LebwohlLasher {
+    temperature: f32,
+    coupling: f32,
+    max_step: f32,
+    anisotropy: f32,
+    mesophase: Mesophase,
+    conditions: LatticeConditions,
+    /// Iteration the field is evaluated at.
+    time: f32,
+    rng: StdRng,
+}
+
+impl LebwohlLasher {
+    pub fn new() -> Self {
+        Self {
+            temperature: 0.5,
+            coupling: 1.0,
+            max_step: 0.5,
+            anisotropy: 1.0,
+            mesophase: Mesophase::default(),
+            conditions: LatticeConditions::default(),
+            time: 0.0,
+            rng: StdRng::from_os_rng(),
+        }
+    }
+
+    fn p2(a: f32, b: f32) -> f32 {
+        let cos = (a - b).cos();
+        1.5 * cos * cos - 0.5
+    }
+
+    fn neighbour_energy(
+        &self,
+        angles: &[f32],
+        dimensions: (usize, usize),
+        index: usize,
+        angle: f32,
+        neighbour: Neighbour,
+    ) -> f32 {
+        match neighbour {
+            Neighbour::Site(other) => {
+                let twist = self.mesophase.twist(dimensions, index, other);
+                -self.coupling * Self::p2(angle + twist, angles[other])
+            }
+            Neighbour::Anchor { value: anchor, strength } => -strength * Self::p2(angle, anchor),
+        }
+    }
+
+    fn field_energy(&self, dimensions: (usize, usize), index: usize, angle: f32) -> f32 {
+        let field = &self.conditions.field;
+        if field.is_off() {
+            return 0.0;
+        }
+        let (rows, cols) = dimensions;
+        let position = (
+            ((index % cols) as f32 + 0.5) / cols as f32,
+            ((index / cols) as f32 + 0.5) / rows as f32,
+        );
+        -self.anisotropy * field.projection_squared(self.time, position, angle)
+    }
+
+    fn site_energy(&self, angles: &[f32], dimensions: (usize, usize), index: usize, angle: f32) -> f32 {
+        self.conditions
+            .neighbours(dimensions, index, self.coupling)
+            .map(|neighbour| self.neighbour_energy(angles, dimensions, index, angle, neighbour))
+            .sum::<f32>()
+            + self.field_energy(dimensions, index, angle)
+            + self.mesophase.smectic_energy(dimensions, index, angle)
+    }
+
+    fn sweep(&mut self, angles: &mut [f32], dimensions: (usize, usize)) {
+        for _ in 0..angles.len() {
+            let index = self.rng.random_range(0..angles.len());
+            if self.conditions.is_frozen(index) {
+                continue;
+            }
+            let current = angles[index];
+            let proposed = current + self.rng.random_range(-self.max_step..self.max_step);
+            let delta = self.site_energy(angles, dimensions, index, proposed)
+                - self.site_energy(angles, dimensions, index, current);
+
+            if delta <= 0.0 || self.rng.random::<f32>() < (-delta / self.temperature).exp() {
+                angles[index] = proposed;
+            }
+        }
+    }
+}
+
+/// The 3D model, where directors are unit vectors and anchoring angles lie in the xy plane.
+impl LebwohlLasher {
+    fn p2_3d(n: [f32; 3], m: [f32; 3]) -> f32 {
+        let cos = n[0] * m[0] + n[1] * m[1] + n[2] * m[2];
+        1.5 * cos * cos - 0.5
+    }
+
+    fn neighbour_energy_3d(&self, directors: &[[f32; 3]], director: [f32; 3], neighbour: Neighbour) -> f32 {
+        match neighbour {
+            Neighbour::Site(index) => -self.coupling * Self::p2_3d(director, directors[index]),
+            Neighbour::Anchor { value: angle, strength } => {
+                -strength * Self::p2_3d(director, [angle.cos(), angle.sin(), 0.0])
+            }
+        }
+    }
+
+    fn field_energy_3d(&self, dimensions: Dimensions3, index: usize, director: [f32; 3]) -> f32 {
+        let field = &self.conditions.field;
+        if field.is_off() {
+            return 0.0;
+        }
+        let (rows, cols, _) = dimensions;
+        let in_plane = index % (rows * cols);
+        let position = (
+            ((in_plane % cols) as f32 + 0.5) / cols as f32,
+            ((in_plane / cols) as f32 + 0.5) / rows as f32,
+        );
+        let projection = field.strength(self.time, position)
+            * (director[0] * field.angle.cos() + director[1] * field.angle.sin());
+        -self.anisotropy * projection * projection
+    }
+
+    fn site_energy_3d(
+        &self,
+        directors: &[[f32; 3]],
+        dimensions: Dimensions3,
+        index: usize,
+        director: [f32; 3],
+    ) -> f32 {
+        self.conditions
+            .neighbours_3d(dimensions, index, self.coupling)
+            .map(|neighbour| self.neighbour_energy_3d(directors, director, neighbour))
+            .sum::<f32>()
+            + self.field_energy_3d(dimensions, index, director)
+    }
+
+    fn sweep_3d(&mut self, directors: &mut [[f32; 3]], dimensions: Dimensions3) {
+        let plane = dimensions.0 * dimensions.1;
+        for _ in 0..directors.len() {
+            let index = self.rng.random_range(0..directors.len());
+            if self.conditions.is_frozen(index % plane) {
+                continue;
+            }
+            let current = directors[index];
+            let mut proposed = current.map(|c| c + self.rng.random_range(-self.max_step..self.max_step));
+            let length = proposed.iter().map(|c| c * c).sum::<f32>().sqrt();
+            if length < f32::EPSILON {
+                continue;
+            }
+            proposed = proposed.map(|c| c / length);
+            let delta = self.site_energy_3d(directors, dimensions, index, proposed)
+                - self.site_energy_3d(directors, dimensions, index, current);
+
+            if delta <= 0.0 || self.rng.random::<f32>() < (-delta / self.temperature).exp() {
+                directors[index] = proposed;
+            }
+        }
+    }
+
+    fn observables_3d(&self, directors: &[[f32; 3]], dimensions: Dimensions3) -> Vec<(String, f64)> {
+        let sites = directors.len().max(1) as f64;
+        let field = &self.conditions.field;
+        let field_direction = [field.angle.cos() as f64, field.angle.sin() as f64];
+
+        let mut energy = 0.0f64;
+        let mut alignment = 0.0f64;
+        let mut q = [[0.0f64; 3]; 3];
+        for (index, &director) in directors.iter().enumerate() {
+            energy += self.field_energy_3d(dimensions, index, director) as f64;
+            energy += self.conditions.bond_energy_3d(dimensions, index, self.coupling, |neighbour| {
+                self.neighbour_energy_3d(directors, director, neighbour) as f64
+            });
+            let n = director.map(|c| c as f64);
+            alignment += (n[0] * field_direction[0] + n[1] * field_direction[1]).powi(2);
+            for (i, row) in q.iter_mut().enumerate() {
+                for (j, entry) in row.iter_mut().enumerate() {
+                    *entry += 1.5 * n[i] * n[j] - if i == j { 0.5 } else { 0.0 };
+                }
+            }
+        }
+        let q = q.map(|row| row.map(|entry| entry / sites));
+
+        vec![
+            ("energy".to_owned(), energy / sites),
+            ("order_parameter".to_owned(), largest_eigenvalue(q)),
+            (
+                "field_strength".to_owned(),
+                (field.magnitude * field.waveform.amplitude(self.time)) as f64,
+            ),
+            ("field_alignment".to_owned(), alignment / sites),
+        ]
+    }
+}
+
+/// Largest eigenvalue of a symmetric 3x3 matrix, by the trigonometric solution of its
+/// characteristic cubic.
+fn largest_eigenvalue(m: [[f64; 3]; 3]) -> f64 {
+    let off_diagonal = m[0][1].powi(2) + m[0][2].powi(2) + m[1][2].powi(2);
+    let trace = m[0][0] + m[1][1] + m[2][2];
+    if off_diagonal == 0.0 {
+        return m[0][0].max(m[1][1]).max(m[2][2]);
+    }
+    let mean = trace / 3.0;
+    let p = ((0..3).map(|i| (m[i][i] - mean).powi(2)).sum::<f64>() + 2.0 * off_diagonal) / 6.0;
+    let p = p.sqrt();
+    let b = |i: usize, j: usize| (m[i][j] - if i == j { mean } else { 0.0 }) / p;
+    let determinant = b(0, 0) * (b(1, 1) * b(2, 2) - b(1, 2) * b(2, 1))
+        - b(0, 1) * (b(1, 0) * b(2, 2) - b(1, 2) * b(2, 0))
+        + b(0, 2) * (b(1, 0) * b(2, 1) - b(1, 1) * b(2, 0));
+    let phi = (determinant / 2.0).clamp(-1.0, 1.0).acos() / 3.0;
+    mean + 2.0 * p * phi.cos()
+}
+
+impl Default for LebwohlLasher {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl AlgorithmComputation for LebwohlLasher {
+    fn compute(&mut self, iteration: usize, data: &[DataType]) -> Vec<DataType> {
+        self.time = iteration as f32;
+        if let Some((directors, dimensions)) = DataType::directors(data) {
+            let mut directors = directors.clone();
+            self.sweep_3d(&mut directors, dimensions);
+            return vec![DataType::DirectorField(directors, dimensions)];
+        }
+        let Some((angles, dimensions)) = DataType::oriented(data) else {
+            return Vec::new();
+        };
+        let mut angles = angles.clone();
+        self.sweep(&mut angles, dimensions);
+        let density = self.mesophase.layer_density(&angles, dimensions);
+        let mut output = vec![DataType::OrientedGrid(angles, dimensions)];
+        output.extend(density.map(|density| DataType::ScalarGrid(density, dimensions)));
+        output
+    }
+
+    fn parameters(&self) -> Vec<Parameter> {
+        let mut parameters = vec![
+            Parameter::new("temperature", self.temperature),
+            Parameter::new("coupling", self.coupling),
+            Parameter::new("max_step", self.max_step),
+            Parameter::new("anisotropy", self.anisotropy),
+        ];
+        parameters.extend(self.mesophase.parameters());
+        parameters
+    }
+
+    fn set_parameter(&mut self, name: &str, value: f32) {
+        if self.mesophase.set_parameter(name, value) {
+            return;
+        }
+        match name {
+            "temperature" => self.temperature = value.max(f32::EPSILON),
+            "coupling" => self.coupling = value,
+            "max_step" => self.max_step = value.max(f32::EPSILON),
+            "anisotropy" => self.anisotropy = value,
+            _ => {}
+        }
+    }
+
+    fn seed(&mut self, seed: u64) {
+        self.rng = StdRng::seed_from_u64(seed);
+    }
+
+    fn supports_layers(&self) -> bool {
+        true
+    }
+
+    fn set_conditions(&mut self, conditions: &LatticeConditions) {
+        self.conditions = conditions.clone();
+    }
+
+    fn scalar_range(&self) -> Option<(f32, f32)> {
+        self.mesophase.is_smectic().then_some((0.0, 1.0))
+    }
+
+    fn observables(&self, data: &[DataType]) -> Vec<(String, f64)> {
+        if let Some((directors, dimensions)) = DataType::directors(data) {
+            return self.observables_3d(directors, dimensions);
+        }
+        let Some((angles, dimensions)) = DataType::oriented(data) else {
+            return Vec::new();
+        };
+        let sites = angles.len().max(1) as f64;
+
+        let field = &self.conditions.field;
+        let mut energy = 0.0f64;
+        let (mut cos_sum, mut sin_sum) = (0.0f64, 0.0f64);
+        let mut alignment = 0.0f64;
+        for (index, &angle) in angles.iter().enumerate() {
+            energy += self.field_energy(dimensions, index, angle) as f64;
+            energy += self.mesophase.smectic_energy(dimensions, index, angle) as f64;
+            alignment += ((angle - field.angle) as f64).cos().powi(2);
+            energy += self.conditions.bond_energy(dimensions, index, self.coupling, |neighbour| {
+                self.neighbour_energy(angles, dimensions, index, angle, neighbour) as f64
+            });
+            cos_sum += (2.0 * angle as f64).cos();
+            sin_sum += (2.0 * angle as f64).sin();
+        }
+
+        vec![
+            ("energy".to_owned(), energy / sites),
+            (
+                "order_parameter".to_owned(),
+                (cos_sum * cos_sum + sin_sum * sin_sum).sqrt() / sites,
+            ),
+            (
+                "field_strength".to_owned(),
+                (field.magnitude * field.waveform.amplitude(self.time)) as f64,
+            ),
+            ("field_alignment".to_owned(), alignment / sites),
+        ]
+    }
+}
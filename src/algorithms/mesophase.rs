@@ -0,0 +1,131 @@
+use crate::algorithm_processor::*;
+use std::f32::consts::TAU;
+
+/// Cholesteric and smectic terms of the 2D nematic lattice models, each switched on by its
+/// toggle parameter.
+///
+/// The cholesteric term twists every bond: neighbours at offset `d` prefer to differ by
+/// `q0 d . h`, with `q0 = 2 pi / pitch` and `h` the helix axis at `helix_angle`, so the director
+/// turns by pi every half pitch along the axis. The smectic term is a McMillan-like coupling to a
+/// density wave of period `layer_spacing` along the layer normal at `layer_angle`,
+/// `-smectic_coupling cos(2 pi z / layer_spacing) P2(cos(theta - layer_angle))`: directors stand
+/// along the normal inside the layers and lie flat between them.
+#[derive(Debug, Clone)]
+pub struct Mesophase {
+    cholesteric: f32,
+    pitch: f32,
+    helix_angle: f32,
+    smectic: f32,
+    smectic_coupling: f32,
+    layer_spacing: f32,
+    layer_angle: f32,
+}
+
+impl Default for Mesophase {
+    fn default() -> Self {
+        Self {
+            cholesteric: 0.0,
+            pitch: 20.0,
+            helix_angle: 0.0,
+            smectic: 0.0,
+            smectic_coupling: 1.0,
+            layer_spacing: 8.0,
+            layer_angle: 0.0,
+        }
+    }
+}
+
+impl Mesophase {
+    pub fn is_cholesteric(&self) -> bool {
+        self.cholesteric > 0.0
+    }
+
+    pub fn is_smectic(&self) -> bool {
+        self.smectic > 0.0
+    }
+
+    pub fn parameters(&self) -> Vec<Parameter> {
+        vec![
+            Parameter::new("cholesteric", self.cholesteric),
+            Parameter::new("pitch", self.pitch),
+            Parameter::new("helix_angle", self.helix_angle),
+            Parameter::new("smectic", self.smectic),
+            Parameter::new("smectic_coupling", self.smectic_coupling),
+            Parameter::new("layer_spacing", self.layer_spacing),
+            Parameter::new("layer_angle", self.layer_angle),
+        ]
+    }
+
+    /// Returns whether `name` is one of these parameters.
+    pub fn set_parameter(&mut self, name: &str, value: f32) -> bool {
+        let toggle = if value > 0.0 { 1.0 } else { 0.0 };
+        match name {
+            "cholesteric" => self.cholesteric = toggle,
+            "pitch" => self.pitch = value.max(1.0),
+            "helix_angle" => self.helix_angle = value,
+            "smectic" => self.smectic = toggle,
+            "smectic_coupling" => self.smectic_coupling = value,
+            "layer_spacing" => self.layer_spacing = value.max(1.0),
+            "layer_angle" => self.layer_angle = value,
+            _ => return false,
+        }
+        true
+    }
+
+    /// Preferred angle of the neighbour `other` of `index` relative to `index`'s own.
+    pub fn twist(&self, (_, cols): (usize, usize), index: usize, other: usize) -> f32 {
+        if !self.is_cholesteric() {
+            return 0.0;
+        }
+        // Offsets of more than one cell are periodic wraps.
+        let wrap = |delta: isize| match delta {
+            d if d > 1 => -1.0,
+            d if d < -1 => 1.0,
+            d => d as f32,
+        };
+        let dx = wrap((other % cols) as isize - (index % cols) as isize);
+        let dy = wrap((other / cols) as isize - (index / cols) as isize);
+        TAU / self.pitch * (dx * self.helix_angle.cos() + dy * self.helix_angle.sin())
+    }
+
+    /// cos(2 pi z / layer_spacing) at the centre of site `index`.
+    fn density_wave(&self, (_, cols): (usize, usize), index: usize) -> f32 {
+        let (x, y) = ((index % cols) as f32 + 0.5, (index / cols) as f32 + 0.5);
+        let z = x * self.layer_angle.cos() + y * self.layer_angle.sin();
+        (TAU * z / self.layer_spacing).cos()
+    }
+
+    pub fn smectic_energy(&self, dimensions: (usize, usize), index: usize, angle: f32) -> f32 {
+        if !self.is_smectic() {
+            return 0.0;
+        }
+        let cos = (angle - self.layer_angle).cos();
+        -self.smectic_coupling * self.density_wave(dimensions, index) * (1.5 * cos * cos - 0.5)
+    }
+
+    /// -dE/d theta of the smectic term.
+    pub fn smectic_torque(&self, dimensions: (usize, usize), index: usize, angle: f32) -> f32 {
+        if !self.is_smectic() {
+            return 0.0;
+        }
+        -1.5 * self.smectic_coupling * self.density_wave(dimensions, index) * (2.0 * (angle - self.layer_angle)).sin()
+    }
+
+    /// Layer density `(1 + cos(2 pi z / d) P2(cos(theta - layer_angle))) / 2` of every site: high
+    /// where directors stand in a layer, `None` while the smectic term is off.
+    pub fn layer_density(&self, angles: &[f32], dimensions: (usize, usize)) -> Option<Vec<f32>> {
+        if !self.is_smectic() {
+            return None;
+        }
+        Some(
+            angles
+                .iter()
+                .enumerate()
+                .map(|(index, &angle)| {
+                    let cos = (angle - self.layer_angle).cos();
+                    0.5 * (1.0 + self.density_wave(dimensions, index) * (1.5 * cos * cos - 0.5))
+                })
+                .collect(),
+        )
+    }
+}
@@ -0,0 +1,25 @@
+pub mod active_nematic;
+pub mod gay_berne;
+pub mod gray_scott;
+pub mod heat;
+pub mod ising;
+pub mod landau_de_gennes;
+pub mod langevin;
+pub mod lebwohl_lasher;
+pub mod mesophase;
+pub mod parallel_tempering;
+pub mod wang_landau;
+pub mod xy;
+
+pub use active_nematic::*;
+pub use gay_berne::*;
+pub use gray_scott::*;
+pub use heat::*;
+pub use ising::*;
+pub use landau_de_gennes::*;
+pub use langevin::*;
+pub use lebwohl_lasher::*;
+pub use mesophase::*;
+pub use parallel_tempering::*;
+pub use wang_landau::*;
+pub use xy::*;
@@ -0,0 +1,326 @@
+use crate::algorithm_processor::*;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::thread;
+
+/// Value of `displayed_replica` that shows every replica side by side.
+const ALL_REPLICAS: f32 = -1.0;
+
+struct Replica {
+    computation: Box<dyn AlgorithmComputation>,
+    data: Vec<DataType>,
+    /// Observables of `data`, measured after every block of sweeps.
+    observables: Vec<(String, f64)>,
+    temperature: f32,
+}
+
+impl Replica {
+    /// Total energy from the per-site `energy` observable.
+    fn energy(&self) -> Option<f64> {
+        let energy = self.observables.iter().find(|(name, _)| name == "energy")?.1;
+        Some(energy * sites(&self.data) as f64)
+    }
+}
+
+/// Replica exchange: copies of a lattice Monte Carlo algorithm at a geometric ladder of
+/// temperatures, each on its own thread, that try to swap configurations with their neighbours
+/// on the ladder every `swap_interval` sweeps. A swap is accepted with probability
+/// `min(1, e^((1/T_i - 1/T_j)(E_i - E_j)))`, which lets configurations stuck at low temperature
+/// melt and anneal again.
+///
+/// The output is the configuration at ladder position `displayed_replica`, or a grid of all of
+/// them, coldest at the top left, when it is negative. Observables are those of every replica
+/// suffixed with its ladder position, its temperature, and the swap acceptance of every pair
+/// `(i, i + 1)` as `acceptance_i`.
+pub struct ParallelTempering {
+    factory: AlgorithmFactory,
+    /// Holds the parameters new replicas start with and answers for the inner algorithm.
+    template: Box<dyn AlgorithmComputation>,
+    replicas: Vec<Replica>,
+    replica_count: f32,
+    min_temperature: f32,
+    max_temperature: f32,
+    swap_interval: f32,
+    displayed: f32,
+    /// Attempted and accepted swaps of every neighbouring pair.
+    swaps: Vec<(u64, u64)>,
+    sweeps: usize,
+    conditions: Option<LatticeConditions>,
+    seed: u64,
+    rng: StdRng,
+}
+
+impl ParallelTempering {
+    pub fn new(factory: AlgorithmFactory, (min_temperature, max_temperature): (f32, f32)) -> Self {
+        Self {
+            template: factory(),
+            factory,
+            replicas: Vec::new(),
+            replica_count: 8.0,
+            min_temperature,
+            max_temperature,
+            swap_interval: 1.0,
+            displayed: 0.0,
+            swaps: Vec::new(),
+            sweeps: 0,
+            conditions: None,
+            seed: rand::random(),
+            rng: StdRng::from_os_rng(),
+        }
+    }
+
+    fn temperature(&self, position: usize) -> f32 {
+        let count = self.replicas.len().max(1);
+        if count == 1 {
+            return self.min_temperature;
+        }
+        let ratio = self.max_temperature / self.min_temperature;
+        self.min_temperature * ratio.powf(position as f32 / (count - 1) as f32)
+    }
+
+    /// Grows or shrinks the ladder to `replica_count`, new replicas starting from `data`, and
+    /// spaces the temperatures again.
+    fn arrange(&mut self, data: &[DataType]) {
+        let count = self.replica_count as usize;
+        self.replicas.truncate(count);
+        while self.replicas.len() < count {
+            let mut computation = (self.factory)();
+            for parameter in self.template.parameters() {
+                computation.set_parameter(&parameter.name, parameter.value);
+            }
+            computation.seed(self.seed.wrapping_add(self.replicas.len() as u64));
+            if let Some(conditions) = &self.conditions {
+                computation.set_conditions(conditions);
+            }
+            let start = self.replicas.last().map_or(data, |replica| &replica.data);
+            let data = copy_data(start);
+            self.replicas.push(Replica {
+                computation,
+                data,
+                observables: Vec::new(),
+                temperature: 0.0,
+            });
+        }
+        self.swaps.resize(count.saturating_sub(1), (0, 0));
+        for position in 0..self.replicas.len() {
+            let temperature = self.temperature(position);
+            let replica = &mut self.replicas[position];
+            replica.temperature = temperature;
+            replica.computation.set_parameter("temperature", temperature);
+        }
+    }
+
+    /// Runs every replica for `swap_interval` sweeps on its own thread.
+    fn sweep(&mut self) {
+        let (sweeps, start) = (self.swap_interval as usize, self.sweeps);
+        thread::scope(|scope| {
+            for replica in &mut self.replicas {
+                scope.spawn(move || {
+                    for iteration in start..start + sweeps {
+                        replica.data = replica.computation.compute(iteration, &replica.data);
+                    }
+                    replica.observables = replica.computation.observables(&replica.data);
+                });
+            }
+        });
+        self.sweeps += sweeps;
+    }
+
+    /// Tries to swap the configurations of neighbouring replicas, alternating between even and
+    /// odd pairs.
+    fn exchange(&mut self) {
+        let parity = (self.sweeps / self.swap_interval as usize) % 2;
+        for lower in (parity..self.replicas.len().saturating_sub(1)).step_by(2) {
+            let (cold, hot) = (&self.replicas[lower], &self.replicas[lower + 1]);
+            let (Some(cold_energy), Some(hot_energy)) = (cold.energy(), hot.energy()) else {
+                continue;
+            };
+            let exponent = (1.0 / cold.temperature as f64 - 1.0 / hot.temperature as f64) * (cold_energy - hot_energy);
+            self.swaps[lower].0 += 1;
+            if exponent >= 0.0 || self.rng.random::<f64>() < exponent.exp() {
+                self.swaps[lower].1 += 1;
+                let (left, right) = self.replicas.split_at_mut(lower + 1);
+                std::mem::swap(&mut left[lower].data, &mut right[0].data);
+                std::mem::swap(&mut left[lower].observables, &mut right[0].observables);
+            }
+        }
+    }
+
+    /// The displayed replica, or the 2D grids of all replicas tiled into one.
+    fn output(&self) -> Vec<DataType> {
+        let displayed = self.displayed as usize;
+        if self.displayed >= 0.0 || self.replicas.len() == 1 {
+            return copy_data(&self.replicas[displayed.min(self.replicas.len() - 1)].data);
+        }
+        let tiled: Vec<DataType> = self.replicas[0]
+            .data
+            .iter()
+            .enumerate()
+            .filter_map(|(index, datum)| match datum {
+                DataType::OrientedGrid(_, dimensions) => Some(DataType::OrientedGrid(
+                    self.tile(index, *dimensions),
+                    self.tiled_dimensions(*dimensions),
+                )),
+                DataType::ScalarGrid(_, dimensions) => Some(DataType::ScalarGrid(
+                    self.tile(index, *dimensions),
+                    self.tiled_dimensions(*dimensions),
+                )),
+                _ => None,
+            })
+            .collect();
+        if tiled.is_empty() { copy_data(&self.replicas[0].data) } else { tiled }
+    }
+
+    /// Tiles per row and rows of tiles of the replica grid.
+    fn tiles(&self) -> (usize, usize) {
+        let columns = (self.replicas.len() as f32).sqrt().ceil() as usize;
+        (columns, self.replicas.len().div_ceil(columns))
+    }
+
+    fn tiled_dimensions(&self, (rows, cols): (usize, usize)) -> (usize, usize) {
+        let (columns, tile_rows) = self.tiles();
+        (rows * tile_rows, cols * columns)
+    }
+
+    /// Datum `index` of every replica side by side; row 0 is at the bottom, so the coldest
+    /// replica goes into the top row of tiles.
+    fn tile(&self, index: usize, (rows, cols): (usize, usize)) -> Vec<f32> {
+        let (columns, tile_rows) = self.tiles();
+        let (total_rows, total_cols) = self.tiled_dimensions((rows, cols));
+        let mut values = vec![0.0; total_rows * total_cols];
+        for (position, replica) in self.replicas.iter().enumerate() {
+            let grid = match replica.data.get(index) {
+                Some(DataType::OrientedGrid(grid, _) | DataType::ScalarGrid(grid, _)) if grid.len() == rows * cols => grid,
+                _ => continue,
+            };
+            let row_offset = (tile_rows - 1 - position / columns) * rows;
+            let col_offset = (position % columns) * cols;
+            for row in 0..rows {
+                let start = (row_offset + row) * total_cols + col_offset;
+                values[start..start + cols].copy_from_slice(&grid[row * cols..(row + 1) * cols]);
+            }
+        }
+        values
+    }
+}
+
+/// Number of lattice sites of the first grid in `data`.
+fn sites(data: &[DataType]) -> usize {
+    data.iter()
+        .find_map(|datum| match datum {
+            DataType::OrientedGrid(values, _) | DataType::ScalarGrid(values, _) => Some(values.len()),
+            DataType::DirectorField(directors, _) => Some(directors.len()),
+            _ => None,
+        })
+        .unwrap_or(1)
+}
+
+fn copy_data(data: &[DataType]) -> Vec<DataType> {
+    data.iter()
+        .filter_map(|datum| match datum {
+            DataType::OrientedGrid(values, dimensions) => Some(DataType::OrientedGrid(values.clone(), *dimensions)),
+            DataType::ScalarGrid(values, dimensions) => Some(DataType::ScalarGrid(values.clone(), *dimensions)),
+            DataType::DirectorField(directors, dimensions) => {
+                Some(DataType::DirectorField(directors.clone(), *dimensions))
+            }
+            DataType::VectorGrid(vectors, dimensions) => Some(DataType::VectorGrid(vectors.clone(), *dimensions)),
+            DataType::Particles(_) => None,
+        })
+        .collect()
+}
+
+impl AlgorithmComputation for ParallelTempering {
+    fn compute(&mut self, _iteration: usize, data: &[DataType]) -> Vec<DataType> {
+        if self.replicas.len() != self.replica_count as usize || self.replicas.is_empty() {
+            self.arrange(data);
+        }
+        self.sweep();
+        self.exchange();
+        self.output()
+    }
+
+    fn parameters(&self) -> Vec<Parameter> {
+        let mut parameters = vec![
+            Parameter::new("replicas", self.replica_count),
+            Parameter::new("min_temperature", self.min_temperature),
+            Parameter::new("max_temperature", self.max_temperature),
+            Parameter::new("swap_interval", self.swap_interval),
+            Parameter::new("displayed_replica", self.displayed),
+        ];
+        parameters.extend(
+            self.template
+                .parameters()
+                .into_iter()
+                .filter(|parameter| parameter.name != "temperature"),
+        );
+        parameters
+    }
+
+    fn set_parameter(&mut self, name: &str, value: f32) {
+        match name {
+            "replicas" => self.replica_count = value.round().max(1.0),
+            "min_temperature" => self.min_temperature = value.max(f32::EPSILON),
+            "max_temperature" => self.max_temperature = value.max(f32::EPSILON),
+            "swap_interval" => self.swap_interval = value.round().max(1.0),
+            "displayed_replica" => self.displayed = if value < 0.0 { ALL_REPLICAS } else { value.round() },
+            "temperature" => {}
+            _ => {
+                self.template.set_parameter(name, value);
+                for replica in &mut self.replicas {
+                    replica.computation.set_parameter(name, value);
+                }
+            }
+        }
+        if matches!(name, "min_temperature" | "max_temperature") {
+            for position in 0..self.replicas.len() {
+                let temperature = self.temperature(position);
+                self.replicas[position].temperature = temperature;
+                self.replicas[position].computation.set_parameter("temperature", temperature);
+            }
+        }
+    }
+
+    fn seed(&mut self, seed: u64) {
+        self.seed = seed;
+        self.rng = StdRng::seed_from_u64(seed);
+    }
+
+    fn supports_layers(&self) -> bool {
+        self.template.supports_layers()
+    }
+
+    fn set_conditions(&mut self, conditions: &LatticeConditions) {
+        for replica in &mut self.replicas {
+            replica.computation.set_conditions(conditions);
+        }
+        self.template.set_conditions(conditions);
+        self.conditions = Some(conditions.clone());
+    }
+
+    fn perturb(&mut self, position: (f32, f32), radius: f32) {
+        for replica in &mut self.replicas {
+            replica.computation.perturb(position, radius);
+        }
+    }
+
+    fn scalar_range(&self) -> Option<(f32, f32)> {
+        self.template.scalar_range()
+    }
+
+    fn observables(&self, _data: &[DataType]) -> Vec<(String, f64)> {
+        let mut observables = Vec::new();
+        for (position, replica) in self.replicas.iter().enumerate() {
+            observables.push((format!("temperature_{position}"), replica.temperature as f64));
+            observables.extend(
+                replica
+                    .observables
+                    .iter()
+                    .map(|(name, value)| (format!("{name}_{position}"), *value)),
+            );
+        }
+        for (lower, &(attempts, accepted)) in self.swaps.iter().enumerate() {
+            observables.push((format!("acceptance_{lower}"), accepted as f64 / attempts.max(1) as f64));
+        }
+        observables
+    }
+}
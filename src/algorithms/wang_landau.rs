@@ -0,0 +1,477 @@
+use crate::algorithm_processor::*;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::f32::consts::{PI, TAU};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WangLandauModel {
+    /// E = -sum s_i s_j with s = +-1.
+    Ising,
+    /// E = -sum cos(theta_i - theta_j).
+    XY,
+    /// E = -sum P2(cos(theta_i - theta_j)), the Lebwohl-Lasher nematic.
+    LebwohlLasher,
+}
+
+impl WangLandauModel {
+    fn bond(self, a: f32, b: f32) -> f64 {
+        match self {
+            Self::Ising => -(a * b) as f64,
+            Self::XY => -((a - b) as f64).cos(),
+            Self::LebwohlLasher => {
+                let cos = ((a - b) as f64).cos();
+                -(1.5 * cos * cos - 0.5)
+            }
+        }
+    }
+
+    /// Lowest and highest energy per site, two bonds per site.
+    fn energy_bounds(self) -> (f64, f64) {
+        match self {
+            Self::Ising | Self::XY => (-2.0, 2.0),
+            Self::LebwohlLasher => (-2.0, 1.0),
+        }
+    }
+
+    /// ln of the configuration space of one site: two spins, or the angles a site can take.
+    fn ln_states_per_site(self) -> f64 {
+        match self {
+            Self::Ising => 2f64.ln(),
+            Self::XY => (TAU as f64).ln(),
+            Self::LebwohlLasher => (PI as f64).ln(),
+        }
+    }
+}
+
+/// Random walk in energy space together with the density of states it has built up.
+struct Walk {
+    sites: usize,
+    /// Total energies covered by the bins.
+    range: (f64, f64),
+    bins: usize,
+    ln_g: Vec<f64>,
+    histogram: Vec<u64>,
+    ln_f: f64,
+    stage: usize,
+    moves: u64,
+    /// Whether `ln_f` follows 1/t instead of the flatness criterion.
+    inverse_time: bool,
+    converged: bool,
+}
+
+impl Walk {
+    fn bin(&self, energy: f64) -> usize {
+        let position = (energy - self.range.0) / (self.range.1 - self.range.0) * self.bins as f64;
+        (position.max(0.0) as usize).min(self.bins - 1)
+    }
+
+    fn bin_energy(&self, bin: usize) -> f64 {
+        self.range.0 + (bin as f64 + 0.5) * (self.range.1 - self.range.0) / self.bins as f64
+    }
+
+    /// Bins the walk has reached since it started.
+    fn visited(&self) -> impl Iterator<Item = usize> + '_ {
+        (0..self.bins).filter(|&bin| self.ln_g[bin] > 0.0)
+    }
+
+    /// Smallest histogram entry over the mean, over the visited bins.
+    fn flatness(&self) -> f64 {
+        let (count, sum, min) = self.visited().fold((0usize, 0u64, u64::MAX), |(count, sum, min), bin| {
+            (count + 1, sum + self.histogram[bin], min.min(self.histogram[bin]))
+        });
+        if count == 0 || sum == 0 {
+            return 0.0;
+        }
+        min as f64 / (sum as f64 / count as f64)
+    }
+}
+
+/// Wang-Landau sampling of the density of states g(E) of a small periodic lattice.
+///
+/// The walk accepts a move from E to E' with probability min(1, g(E)/g(E')) and raises
+/// ln g(E) of the energy it is at by ln f, so every energy ends up visited equally often.
+/// Whenever the histogram of the visited bins is flat, i.e. its minimum reaches `flatness`
+/// times its mean, ln f is multiplied by `reduction` and the histogram starts again. With
+/// `inverse_time` set, ln f follows 1/t (in sweeps) once it would drop below it. The walk stops
+/// refining once ln f is below `final_modification`.
+///
+/// The thermodynamics per site, F = -T ln Z, U = <E>, S = (U - F)/T and C = (<E^2> - <E>^2)/T^2,
+/// follow from ln g at every temperature between `min_temperature` and `max_temperature`.
+/// g is normalised to the volume of the configuration space, 2^N spins or (2pi)^N (pi^N for
+/// the nematic) angles. J = 1; boundaries, the mask and the field are ignored.
+pub struct WangLandau {
+    model: WangLandauModel,
+    sweeps: f32,
+    bins: f32,
+    flatness: f32,
+    initial_modification: f32,
+    final_modification: f32,
+    reduction: f32,
+    inverse_time: f32,
+    max_step: f32,
+    /// Energy window per site the walk is restricted to.
+    energy_min: f32,
+    energy_max: f32,
+    min_temperature: f32,
+    max_temperature: f32,
+    temperature_points: f32,
+    walk: Option<Walk>,
+    /// Energy per site of the last configuration.
+    energy: f64,
+    /// Whether the lattice conditions ask for more than the periodic, unmasked lattice it samples.
+    ignores_conditions: bool,
+    rng: StdRng,
+}
+
+impl WangLandau {
+    pub fn new(model: WangLandauModel) -> Self {
+        let (energy_min, energy_max) = model.energy_bounds();
+        let (min_temperature, max_temperature) = match model {
+            WangLandauModel::Ising => (1.0, 4.0),
+            WangLandauModel::XY | WangLandauModel::LebwohlLasher => (0.2, 2.0),
+        };
+        Self {
+            model,
+            sweeps: 10.0,
+            bins: 200.0,
+            flatness: 0.8,
+            initial_modification: 1.0,
+            final_modification: 1e-6,
+            reduction: 0.5,
+            inverse_time: 0.0,
+            max_step: 1.0,
+            energy_min: energy_min as f32,
+            energy_max: energy_max as f32,
+            min_temperature,
+            max_temperature,
+            temperature_points: 100.0,
+            walk: None,
+            energy: 0.0,
+            ignores_conditions: false,
+            rng: StdRng::from_os_rng(),
+        }
+    }
+
+    /// `energy_min` and `energy_max` in order, so that an inverted pair still bounds a window.
+    fn energy_window(&self) -> (f32, f32) {
+        (self.energy_min.min(self.energy_max), self.energy_min.max(self.energy_max))
+    }
+
+    /// A walk over the energy window of `sites` sites. Ising levels are 4 apart, so every
+    /// level gets a bin of its own and `bins` only applies to the continuous models.
+    fn new_walk(&self, sites: usize) -> Walk {
+        let n = sites as f64;
+        let (energy_min, energy_max) = self.energy_window();
+        let (range, bins) = match self.model {
+            WangLandauModel::Ising => {
+                let low = (energy_min as f64 * n / 4.0).round() * 4.0;
+                let high = (energy_max as f64 * n / 4.0).round() * 4.0;
+                ((low - 2.0, high + 2.0), ((high - low) / 4.0) as usize + 1)
+            }
+            _ => ((energy_min as f64 * n, energy_max as f64 * n), self.bins as usize),
+        };
+        Walk {
+            sites,
+            range,
+            bins,
+            ln_g: vec![0.0; bins],
+            histogram: vec![0; bins],
+            ln_f: self.initial_modification as f64,
+            stage: 0,
+            moves: 0,
+            inverse_time: false,
+            converged: false,
+        }
+    }
+
+    /// Spins or angles of the configuration in `data`.
+    fn read_state(&self, data: &[DataType]) -> Option<(Vec<f32>, (usize, usize))> {
+        if self.model == WangLandauModel::Ising {
+            if let Some((spins, dimensions)) = DataType::scalar(data) {
+                return Some((spins.clone(), dimensions));
+            }
+            let (angles, dimensions) = DataType::oriented(data)?;
+            let spins = angles.iter().map(|angle| if angle.cos() >= 0.0 { 1.0 } else { -1.0 }).collect();
+            return Some((spins, dimensions));
+        }
+        DataType::oriented(data).map(|(angles, dimensions)| (angles.clone(), dimensions))
+    }
+
+    fn neighbours((rows, cols): (usize, usize), index: usize) -> [usize; 4] {
+        let (row, col) = (index / cols, index % cols);
+        [
+            row * cols + (col + cols - 1) % cols,
+            row * cols + (col + 1) % cols,
+            (row + rows - 1) % rows * cols + col,
+            (row + 1) % rows * cols + col,
+        ]
+    }
+
+    fn total_energy(&self, values: &[f32], dimensions: (usize, usize)) -> f64 {
+        (0..values.len())
+            .map(|index| {
+                let [_, right, _, up] = Self::neighbours(dimensions, index);
+                self.model.bond(values[index], values[right]) + self.model.bond(values[index], values[up])
+            })
+            .sum()
+    }
+
+    fn local_energy(&self, values: &[f32], dimensions: (usize, usize), index: usize, value: f32) -> f64 {
+        Self::neighbours(dimensions, index)
+            .iter()
+            .map(|&other| self.model.bond(value, values[other]))
+            .sum()
+    }
+
+    fn propose(&mut self, value: f32) -> f32 {
+        match self.model {
+            WangLandauModel::Ising => -value,
+            WangLandauModel::XY => {
+                (value + self.rng.random_range(-self.max_step..=self.max_step)).rem_euclid(TAU)
+            }
+            WangLandauModel::LebwohlLasher => {
+                (value + self.rng.random_range(-self.max_step..=self.max_step)).rem_euclid(PI)
+            }
+        }
+    }
+
+    /// Lowers ln f when the histogram is flat, or along 1/t once it has switched over.
+    fn refine(&mut self) {
+        let (flatness, reduction, one_over_t) = (self.flatness as f64, self.reduction as f64, self.inverse_time > 0.0);
+        let final_modification = self.final_modification as f64;
+        let Some(walk) = self.walk.as_mut().filter(|walk| !walk.converged) else {
+            return;
+        };
+        let time = walk.moves as f64 / walk.sites as f64;
+        if walk.inverse_time {
+            walk.ln_f = 1.0 / time;
+        } else if walk.flatness() >= flatness {
+            walk.ln_f *= reduction;
+            walk.stage += 1;
+            walk.histogram.fill(0);
+            if one_over_t && walk.ln_f < 1.0 / time {
+                walk.inverse_time = true;
+                walk.ln_f = 1.0 / time;
+            }
+        }
+        if walk.ln_f < final_modification {
+            walk.converged = true;
+        }
+    }
+
+    /// Energy per site and ln g of the visited bins, normalised to the configuration space.
+    fn density_of_states(&self) -> Option<(Vec<f64>, Vec<f64>)> {
+        let walk = self.walk.as_ref()?;
+        let (energies, ln_g): (Vec<f64>, Vec<f64>) = walk
+            .visited()
+            .map(|bin| (walk.bin_energy(bin), walk.ln_g[bin]))
+            .unzip();
+        if ln_g.is_empty() {
+            return None;
+        }
+        let shift = log_sum_exp(ln_g.iter().copied()) - walk.sites as f64 * self.model.ln_states_per_site();
+        let sites = walk.sites as f64;
+        Some((
+            energies.iter().map(|energy| energy / sites).collect(),
+            ln_g.iter().map(|value| value - shift).collect(),
+        ))
+    }
+
+    fn thermodynamics(&self) -> Option<Table> {
+        let walk = self.walk.as_ref()?;
+        let sites = walk.sites as f64;
+        let (energies, ln_g) = self.density_of_states()?;
+        let points = (self.temperature_points as usize).max(2);
+        let (low, high) = (self.min_temperature as f64, self.max_temperature as f64);
+
+        let mut columns: Vec<Vec<f64>> = (0..5).map(|_| Vec::with_capacity(points)).collect();
+        for point in 0..points {
+            let temperature = low + (high - low) * point as f64 / (points - 1) as f64;
+            let weights: Vec<f64> = energies
+                .iter()
+                .zip(&ln_g)
+                .map(|(energy, ln_g)| ln_g - energy * sites / temperature)
+                .collect();
+            let ln_z = log_sum_exp(weights.iter().copied());
+            let (mean, mean_square) = energies.iter().zip(&weights).fold((0.0, 0.0), |(mean, square), (energy, weight)| {
+                let probability = (weight - ln_z).exp();
+                (mean + probability * energy, square + probability * energy * energy)
+            });
+            let free_energy = -temperature * ln_z / sites;
+            columns[0].push(temperature);
+            columns[1].push(free_energy);
+            columns[2].push(mean);
+            columns[3].push((mean - free_energy) / temperature);
+            columns[4].push(sites * (mean_square - mean * mean) / (temperature * temperature));
+        }
+        let mut columns = columns.into_iter();
+        let mut next = || columns.next().unwrap_or_default();
+        Some(
+            Table::new("thermodynamics")
+                .with_column("temperature", next())
+                .with_column("free_energy", next())
+                .with_column("internal_energy", next())
+                .with_column("entropy", next())
+                .with_column("specific_heat", next()),
+        )
+    }
+}
+
+/// ln sum e^x without overflow.
+fn log_sum_exp(values: impl Iterator<Item = f64> + Clone) -> f64 {
+    let max = values.clone().fold(f64::NEG_INFINITY, f64::max);
+    if !max.is_finite() {
+        return max;
+    }
+    max + values.map(|value| (value - max).exp()).sum::<f64>().ln()
+}
+
+impl AlgorithmComputation for WangLandau {
+    fn compute(&mut self, _iteration: usize, data: &[DataType]) -> Vec<DataType> {
+        let Some((mut values, dimensions)) = self.read_state(data) else {
+            return Vec::new();
+        };
+        if self.walk.as_ref().is_none_or(|walk| walk.sites != values.len()) {
+            self.walk = Some(self.new_walk(values.len()));
+        }
+        let mut walk = self.walk.take().expect("The walk was just created");
+
+        let mut energy = self.total_energy(&values, dimensions);
+        let window = walk.range;
+        // How far outside the window an energy is; a start outside it is walked in.
+        let distance = |energy: f64| (window.0 - energy).max(energy - window.1).max(0.0);
+        let mut bin = walk.bin(energy);
+        for _ in 0..self.sweeps as usize * values.len() {
+            let index = self.rng.random_range(0..values.len());
+            let (old, new) = (values[index], self.propose(values[index]));
+            let proposed = energy + self.local_energy(&values, dimensions, index, new)
+                - self.local_energy(&values, dimensions, index, old);
+            let proposed_bin = walk.bin(proposed);
+            let accept = if distance(energy) > 0.0 {
+                distance(proposed) <= distance(energy)
+            } else if distance(proposed) > 0.0 {
+                false
+            } else {
+                let exponent = walk.ln_g[bin] - walk.ln_g[proposed_bin];
+                exponent >= 0.0 || self.rng.random::<f64>() < exponent.exp()
+            };
+            if accept {
+                values[index] = new;
+                energy = proposed;
+                bin = proposed_bin;
+            }
+            if !walk.converged && distance(energy) == 0.0 {
+                walk.ln_g[bin] += walk.ln_f;
+                walk.histogram[bin] += 1;
+            }
+            walk.moves += 1;
+        }
+        self.energy = energy / values.len() as f64;
+        self.walk = Some(walk);
+        self.refine();
+
+        match self.model {
+            WangLandauModel::Ising => vec![DataType::ScalarGrid(values, dimensions)],
+            _ => vec![DataType::OrientedGrid(values, dimensions)],
+        }
+    }
+
+    fn parameters(&self) -> Vec<Parameter> {
+        let mut parameters = vec![
+            Parameter::new("sweeps", self.sweeps),
+            Parameter::new("flatness", self.flatness),
+            Parameter::new("initial_modification", self.initial_modification),
+            Parameter::new("final_modification", self.final_modification),
+            Parameter::new("reduction", self.reduction),
+            Parameter::new("inverse_time", self.inverse_time),
+            Parameter::new("energy_min", self.energy_min),
+            Parameter::new("energy_max", self.energy_max),
+            Parameter::new("min_temperature", self.min_temperature),
+            Parameter::new("max_temperature", self.max_temperature),
+            Parameter::new("temperature_points", self.temperature_points),
+        ];
+        if self.model != WangLandauModel::Ising {
+            parameters.push(Parameter::new("bins", self.bins));
+            parameters.push(Parameter::new("max_step", self.max_step));
+        }
+        parameters
+    }
+
+    fn set_parameter(&mut self, name: &str, value: f32) {
+        let (lowest, highest) = self.model.energy_bounds();
+        match name {
+            "sweeps" => self.sweeps = value.round().max(1.0),
+            "flatness" => self.flatness = value.clamp(0.0, 1.0),
+            "initial_modification" => self.initial_modification = value.max(f32::EPSILON),
+            "final_modification" => self.final_modification = value.max(0.0),
+            "reduction" => self.reduction = value.clamp(f32::EPSILON, 0.99),
+            "inverse_time" => self.inverse_time = if value > 0.0 { 1.0 } else { 0.0 },
+            "energy_min" => self.energy_min = value.clamp(lowest as f32, highest as f32),
+            "energy_max" => self.energy_max = value.clamp(lowest as f32, highest as f32),
+            "min_temperature" => self.min_temperature = value.max(f32::EPSILON),
+            "max_temperature" => self.max_temperature = value.max(f32::EPSILON),
+            "temperature_points" => self.temperature_points = value.round().max(2.0),
+            "bins" => self.bins = value.round().max(2.0),
+            "max_step" => self.max_step = value.clamp(f32::EPSILON, PI),
+            _ => {}
+        }
+        // The binning changed, so the density of states starts over.
+        if matches!(name, "bins" | "energy_min" | "energy_max") {
+            self.walk = None;
+        }
+    }
+
+    fn seed(&mut self, seed: u64) {
+        self.rng = StdRng::seed_from_u64(seed);
+    }
+
+    fn set_conditions(&mut self, conditions: &LatticeConditions) {
+        let b = &conditions.boundaries;
+        self.ignores_conditions = [b.left, b.right, b.bottom, b.top].iter().any(|&edge| edge != Boundary::Periodic)
+            || conditions.mask.is_some()
+            || !conditions.field.is_off();
+    }
+
+    fn warnings(&self) -> Vec<String> {
+        let mut warnings = Vec::new();
+        if self.ignores_conditions {
+            warnings.push("the lattice is sampled periodic, without mask or field".to_owned());
+        }
+        if self.energy_min > self.energy_max {
+            warnings.push(format!(
+                "energy_min {} is above energy_max {}, the walk uses the window between them",
+                self.energy_min, self.energy_max
+            ));
+        } else if self.energy_min == self.energy_max {
+            warnings.push(format!("energy_min and energy_max are both {}, the window is empty", self.energy_min));
+        }
+        warnings
+    }
+
+    fn scalar_range(&self) -> Option<(f32, f32)> {
+        (self.model == WangLandauModel::Ising).then_some((-1.0, 1.0))
+    }
+
+    fn observables(&self, _data: &[DataType]) -> Vec<(String, f64)> {
+        let Some(walk) = &self.walk else {
+            return Vec::new();
+        };
+        vec![
+            ("energy".to_owned(), self.energy),
+            ("ln_f".to_owned(), walk.ln_f),
+            ("stage".to_owned(), walk.stage as f64),
+            ("flatness".to_owned(), walk.flatness()),
+            ("visited_bins".to_owned(), walk.visited().count() as f64),
+        ]
+    }
+
+    fn tables(&self) -> Vec<Table> {
+        let Some((energies, ln_g)) = self.density_of_states() else {
+            return Vec::new();
+        };
+        let density = Table::new("density_of_states")
+            .with_column("energy", energies)
+            .with_column("ln_g", ln_g);
+        std::iter::once(density).chain(self.thermodynamics()).collect()
+    }
+}
@@ -0,0 +1,207 @@
+use crate::algorithm_processor::*;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::f32::consts::{PI, TAU};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RotorUpdate {
+    Metropolis,
+    /// Energy-conserving reflections about the local field, with one Metropolis sweep after
+    /// every `overrelaxation` of them to keep the chain ergodic.
+    OverRelaxation,
+}
+
+/// The 2D XY model, E = -J sum cos(theta_i - theta_j) - sum h(r) cos(theta_i - phi).
+///
+/// Spins are polar, unlike the nematic Lebwohl-Lasher directors. Anchored boundaries pull
+/// towards their angle with their strength, and the external field couples linearly.
+pub struct XY {
+    update: RotorUpdate,
+    temperature: f32,
+    coupling: f32,
+    max_step: f32,
+    overrelaxation: f32,
+    conditions: LatticeConditions,
+    /// Iteration the field is evaluated at.
+    time: f32,
+    rng: StdRng,
+}
+
+impl XY {
+    pub fn new(update: RotorUpdate) -> Self {
+        Self {
+            update,
+            temperature: 0.89,
+            coupling: 1.0,
+            max_step: 1.0,
+            overrelaxation: 4.0,
+            conditions: LatticeConditions::default(),
+            time: 0.0,
+            rng: StdRng::from_os_rng(),
+        }
+    }
+
+    /// Sum over neighbours and field of `strength * (cos, sin)`, so that E_i = -H . s_i.
+    fn local_field(&self, angles: &[f32], dimensions: (usize, usize), index: usize) -> (f32, f32) {
+        let mut field = (0.0, 0.0);
+        for neighbour in self.conditions.neighbours(dimensions, index, self.coupling) {
+            let (strength, angle) = match neighbour {
+                Neighbour::Site(other) => (self.coupling, angles[other]),
+                Neighbour::Anchor { value: angle, strength } => (strength, angle),
+            };
+            field.0 += strength * angle.cos();
+            field.1 += strength * angle.sin();
+        }
+        let external = &self.conditions.field;
+        if !external.is_off() {
+            let (rows, cols) = dimensions;
+            let position = (
+                ((index % cols) as f32 + 0.5) / cols as f32,
+                ((index / cols) as f32 + 0.5) / rows as f32,
+            );
+            let strength = external.strength(self.time, position);
+            field.0 += strength * external.angle.cos();
+            field.1 += strength * external.angle.sin();
+        }
+        field
+    }
+
+    fn metropolis_sweep(&mut self, angles: &mut [f32], dimensions: (usize, usize)) {
+        for _ in 0..angles.len() {
+            let index = self.rng.random_range(0..angles.len());
+            if self.conditions.is_frozen(index) {
+                continue;
+            }
+            let (hx, hy) = self.local_field(angles, dimensions, index);
+            let current = angles[index];
+            let proposed = current + self.rng.random_range(-self.max_step..self.max_step);
+            let delta = -hx * (proposed.cos() - current.cos()) - hy * (proposed.sin() - current.sin());
+
+            if delta <= 0.0 || self.rng.random::<f32>() < (-delta / self.temperature).exp() {
+                angles[index] = proposed.rem_euclid(TAU);
+            }
+        }
+    }
+
+    /// Reflects every spin about its local field, theta -> 2 phi - theta.
+    fn overrelaxation_sweep(&self, angles: &mut [f32], dimensions: (usize, usize)) {
+        for index in 0..angles.len() {
+            if self.conditions.is_frozen(index) {
+                continue;
+            }
+            let (hx, hy) = self.local_field(angles, dimensions, index);
+            if hx == 0.0 && hy == 0.0 {
+                continue;
+            }
+            angles[index] = (2.0 * hy.atan2(hx) - angles[index]).rem_euclid(TAU);
+        }
+    }
+
+    /// Plaquettes with a net winding of +-2 pi, per site. Only interior plaquettes are counted.
+    fn vortex_density(angles: &[f32], (rows, cols): (usize, usize)) -> f64 {
+        let wrap = |difference: f32| (difference + PI).rem_euclid(TAU) - PI;
+        let mut vortices = 0usize;
+        for row in 0..rows.saturating_sub(1) {
+            for col in 0..cols.saturating_sub(1) {
+                let corners = [
+                    angles[row * cols + col],
+                    angles[row * cols + col + 1],
+                    angles[(row + 1) * cols + col + 1],
+                    angles[(row + 1) * cols + col],
+                ];
+                let winding = (0..4)
+                    .map(|corner| wrap(corners[(corner + 1) % 4] - corners[corner]))
+                    .sum::<f32>();
+                if winding.abs() > PI {
+                    vortices += 1;
+                }
+            }
+        }
+        vortices as f64 / (rows * cols).max(1) as f64
+    }
+}
+
+impl AlgorithmComputation for XY {
+    fn compute(&mut self, iteration: usize, data: &[DataType]) -> Vec<DataType> {
+        self.time = iteration as f32;
+        let Some((angles, dimensions)) = DataType::oriented(data) else {
+            return Vec::new();
+        };
+        let mut angles = angles.clone();
+        if self.update == RotorUpdate::OverRelaxation {
+            for _ in 0..self.overrelaxation as usize {
+                self.overrelaxation_sweep(&mut angles, dimensions);
+            }
+        }
+        self.metropolis_sweep(&mut angles, dimensions);
+        vec![DataType::OrientedGrid(angles, dimensions)]
+    }
+
+    fn parameters(&self) -> Vec<Parameter> {
+        let mut parameters = vec![
+            Parameter::new("temperature", self.temperature),
+            Parameter::new("coupling", self.coupling),
+            Parameter::new("max_step", self.max_step),
+        ];
+        if self.update == RotorUpdate::OverRelaxation {
+            parameters.push(Parameter::new("overrelaxation", self.overrelaxation));
+        }
+        parameters
+    }
+
+    fn set_parameter(&mut self, name: &str, value: f32) {
+        match name {
+            "temperature" => self.temperature = value.max(f32::EPSILON),
+            "coupling" => self.coupling = value,
+            "max_step" => self.max_step = value.max(f32::EPSILON),
+            "overrelaxation" => self.overrelaxation = value.max(0.0),
+            _ => {}
+        }
+    }
+
+    fn seed(&mut self, seed: u64) {
+        self.rng = StdRng::seed_from_u64(seed);
+    }
+
+    fn set_conditions(&mut self, conditions: &LatticeConditions) {
+        self.conditions = conditions.clone();
+    }
+
+    fn observables(&self, data: &[DataType]) -> Vec<(String, f64)> {
+        let Some((angles, dimensions)) = DataType::oriented(data) else {
+            return Vec::new();
+        };
+        let sites = angles.len().max(1) as f64;
+
+        let mut energy = 0.0f64;
+        let (mut cos_sum, mut sin_sum) = (0.0f64, 0.0f64);
+        for (index, &angle) in angles.iter().enumerate() {
+            energy -= self.conditions.bond_energy(dimensions, index, self.coupling, |neighbour| match neighbour {
+                Neighbour::Site(other) => (self.coupling * (angle - angles[other]).cos()) as f64,
+                Neighbour::Anchor { value: anchor, strength } => (strength * (angle - anchor).cos()) as f64,
+            });
+            cos_sum += (angle as f64).cos();
+            sin_sum += (angle as f64).sin();
+        }
+        let external = &self.conditions.field;
+        if !external.is_off() {
+            let (rows, cols) = dimensions;
+            for (index, &angle) in angles.iter().enumerate() {
+                let position = (
+                    ((index % cols) as f32 + 0.5) / cols as f32,
+                    ((index / cols) as f32 + 0.5) / rows as f32,
+                );
+                energy -= (external.strength(self.time, position) * (angle - external.angle).cos()) as f64;
+            }
+        }
+
+        vec![
+            ("energy".to_owned(), energy / sites),
+            (
+                "order_parameter".to_owned(),
+                (cos_sum * cos_sum + sin_sum * sin_sum).sqrt() / sites,
+            ),
+            ("vortex_density".to_owned(), Self::vortex_density(angles, dimensions)),
+        ]
+    }
+}
@@ -0,0 +1,113 @@
+use crate::algorithm_processor::{Boundaries, DataType, Table};
+use crate::analysis::{Coarsening, Correlation, DefectTracker, FrankMaps, PowerSpectrum, SpectrumField};
+
+/// Measurements made on every sampled frame on top of the algorithm's own observables, switched
+/// on with `analysis.<name> = 1` in batch configs, or `analysis.spectrum = <field>`.
+#[derive(Debug, Clone, Default)]
+pub struct Analyses {
+    /// Lattice sums of the Frank splay, twist and bend terms.
+    pub elastic: bool,
+    /// Orientational correlation function of 2D lattices and the correlation length fitted to it.
+    pub correlation: bool,
+    /// Defects linked into trajectories on every iteration.
+    pub tracking: bool,
+    /// Domain lengths L(t) with their power-law growth fits.
+    pub coarsening: bool,
+    /// Field whose radially averaged structure factor is tabulated.
+    pub spectrum: SpectrumField,
+}
+
+impl Analyses {
+    pub fn apply(&mut self, name: &str, value: &str) -> Result<(), String> {
+        if name == "spectrum" {
+            self.spectrum = SpectrumField::parse(value)?;
+            return Ok(());
+        }
+        let enabled = match value {
+            "1" | "true" | "on" => true,
+            "0" | "false" | "off" => false,
+            _ => return Err(format!("Invalid value '{value}' for 'analysis.{name}'")),
+        };
+        match name {
+            "elastic" => self.elastic = enabled,
+            "correlation" => self.correlation = enabled,
+            "tracking" => self.tracking = enabled,
+            "coarsening" => self.coarsening = enabled,
+            _ => return Err(format!("Unknown analysis '{name}'")),
+        }
+        Ok(())
+    }
+
+    pub fn describe(&self) -> String {
+        let mut text = String::new();
+        if self.elastic {
+            text.push_str("analysis.elastic = 1\n");
+        }
+        if self.correlation {
+            text.push_str("analysis.correlation = 1\n");
+        }
+        if self.tracking {
+            text.push_str("analysis.tracking = 1\n");
+        }
+        if self.coarsening {
+            text.push_str("analysis.coarsening = 1\n");
+        }
+        if self.spectrum != SpectrumField::Off {
+            text.push_str(&format!("analysis.spectrum = {}\n", self.spectrum));
+        }
+        text
+    }
+
+    /// The correlation function of `data` when the correlation or coarsening analysis needs it,
+    /// computed once per frame and handed to `observables`, `tables` and `Coarsening::record`.
+    pub fn correlation(&self, data: &[DataType]) -> Option<Correlation> {
+        if let Some((angles, dimensions)) = DataType::oriented(data) {
+            (self.correlation || self.coarsening).then(|| Correlation::new(angles, dimensions))
+        } else if let Some((values, dimensions)) = DataType::scalar(data) {
+            self.coarsening.then(|| Correlation::scalar(values, dimensions))
+        } else {
+            None
+        }
+    }
+
+    pub fn observables(&self, data: &[DataType], correlation: Option<&Correlation>) -> Vec<(String, f64)> {
+        let mut values = Vec::new();
+        if self.elastic
+            && let Some(maps) = FrankMaps::from_data(data)
+        {
+            values.extend(maps.totals());
+        }
+        if let Some(correlation) = self.orientational(data, correlation) {
+            values.push(("correlation_length".to_owned(), correlation.length));
+        }
+        values
+    }
+
+    /// A tracker to feed every frame of a run with `boundaries`, while tracking is on.
+    pub fn tracker(&self, boundaries: &Boundaries) -> Option<DefectTracker> {
+        self.tracking.then(|| DefectTracker::new(boundaries))
+    }
+
+    /// Length history to feed every sampled frame, while coarsening is on.
+    pub fn coarsening(&self) -> Option<Coarsening> {
+        self.coarsening.then(Coarsening::default)
+    }
+
+    /// Curves of the latest frame, plotted live and saved with the run.
+    pub fn tables(&self, data: &[DataType], correlation: Option<&Correlation>) -> Vec<Table> {
+        let mut tables: Vec<Table> = self
+            .orientational(data, correlation)
+            .map(|correlation| correlation.table())
+            .into_iter()
+            .collect();
+        if let Some((values, dimensions)) = self.spectrum.values_of(data) {
+            tables.push(PowerSpectrum::new(&values, dimensions).table());
+        }
+        tables
+    }
+
+    /// `correlation` while the correlation analysis is on and it is that of director angles.
+    fn orientational<'a>(&self, data: &[DataType], correlation: Option<&'a Correlation>) -> Option<&'a Correlation> {
+        correlation.filter(|_| self.correlation && DataType::oriented(data).is_some())
+    }
+}
@@ -0,0 +1,143 @@
+use crate::algorithm_processor::{DataType, Table, find_defects};
+use crate::analysis::{Correlation, PowerSpectrum, SpectrumField};
+use std::f64::consts::TAU;
+
+/// Domain length L(t) of a coarsening run, recorded on every sampled frame by up to three
+/// estimators: `defects`, the inverse square root of the defect density, `NaN` once they are
+/// gone; `correlation`, the first zero of the correlation function; and `structure`, `2 pi / k1`
+/// with `k1` the first moment of S(k). Frames without director angles have no defects, and their
+/// correlation and S(k) come from the scalar field.
+#[derive(Debug, Clone, Default)]
+pub struct Coarsening {
+    /// Iteration plus one, so that the first frame sits on log axes.
+    times: Vec<f64>,
+    lengths: Vec<(String, Vec<f64>)>,
+}
+
+impl Coarsening {
+    /// Measures the lengths of `data` and returns them as `length_<estimator>` observables.
+    /// `spectrum` chooses the field of S(k) when set, `correlation` is that of `data`.
+    pub fn record(
+        &mut self,
+        iteration: usize,
+        data: &[DataType],
+        spectrum: SpectrumField,
+        correlation: Option<&Correlation>,
+    ) -> Vec<(String, f64)> {
+        let lengths = lengths(data, spectrum, correlation);
+        if lengths.is_empty() {
+            return Vec::new();
+        }
+        if self.lengths.is_empty() {
+            self.lengths = lengths.iter().map(|(name, _)| (name.clone(), Vec::new())).collect();
+        }
+        self.times.push(iteration as f64 + 1.0);
+        for ((_, values), (_, length)) in self.lengths.iter_mut().zip(&lengths) {
+            values.push(*length);
+        }
+        lengths
+            .into_iter()
+            .map(|(name, length)| (format!("length_{name}"), length))
+            .collect()
+    }
+
+    /// `t` against `L_<estimator>` and its power-law fit `L_<estimator>_fit`, on log axes.
+    pub fn table(&self) -> Table {
+        let mut table = Table::new("coarsening").with_log_axes().with_column("t", self.times.clone());
+        for (name, values) in &self.lengths {
+            let fit = match growth_fit(&self.times, values) {
+                Some((prefactor, exponent, start)) => self
+                    .times
+                    .iter()
+                    .map(|&t| if t >= start { prefactor * t.powf(exponent) } else { f64::NAN })
+                    .collect(),
+                None => vec![f64::NAN; self.times.len()],
+            };
+            table = table
+                .with_column(&format!("L_{name}"), values.clone())
+                .with_column(&format!("L_{name}_fit"), fit);
+        }
+        table
+    }
+}
+
+/// The lengths every estimator that applies to `data` finds.
+fn lengths(data: &[DataType], spectrum: SpectrumField, correlation: Option<&Correlation>) -> Vec<(String, f64)> {
+    let mut lengths = Vec::new();
+    let field = if let Some((angles, dimensions)) = DataType::oriented(data) {
+        // Without defects the length is undefined, and the fit skips it.
+        let defects = find_defects(angles, dimensions).len();
+        let sites = (dimensions.0 * dimensions.1) as f64;
+        let length = if defects > 0 { (sites / defects as f64).sqrt() } else { f64::NAN };
+        lengths.push(("defects".to_owned(), length));
+        spectrum
+            .values_of(data)
+            .or_else(|| SpectrumField::Cos2Theta.values_of(data))
+    } else if let Some((values, dimensions)) = DataType::scalar(data) {
+        Some((values.clone(), dimensions))
+    } else {
+        return lengths;
+    };
+    if let Some(correlation) = correlation {
+        lengths.push(("correlation".to_owned(), correlation.first_zero()));
+    }
+    if let Some((values, dimensions)) = field {
+        let (wavenumbers, structure_factor) = PowerSpectrum::new(&values, dimensions).radial();
+        let total: f64 = structure_factor.iter().sum();
+        let moment: f64 = wavenumbers.iter().zip(&structure_factor).map(|(k, s)| k * s).sum();
+        let length = if moment > 0.0 { TAU * total / moment } else { f64::NAN };
+        lengths.push(("structure".to_owned(), length));
+    }
+    lengths
+}
+
+/// Least squares fit of `ln L = ln A + alpha ln t` over the later half of the run in log time,
+/// `t >= sqrt(t_first t_last)`, returned as `(A, alpha, t_first_fitted)`. Needs three points.
+pub fn growth_fit(times: &[f64], lengths: &[f64]) -> Option<(f64, f64, f64)> {
+    let (first, last) = (*times.first()?, *times.last()?);
+    let start = (first * last).sqrt();
+    let points: Vec<(f64, f64)> = times
+        .iter()
+        .zip(lengths)
+        .filter(|&(&t, &length)| t >= start && length > 0.0 && length.is_finite())
+        .map(|(&t, &length)| (t.ln(), length.ln()))
+        .collect();
+    if points.len() < 3 {
+        return None;
+    }
+    let count = points.len() as f64;
+    let mean_x = points.iter().map(|p| p.0).sum::<f64>() / count;
+    let mean_y = points.iter().map(|p| p.1).sum::<f64>() / count;
+    let covariance: f64 = points.iter().map(|p| (p.0 - mean_x) * (p.1 - mean_y)).sum();
+    let variance: f64 = points.iter().map(|p| (p.0 - mean_x).powi(2)).sum();
+    if variance <= 0.0 {
+        return None;
+    }
+    let exponent = covariance / variance;
+    Some(((mean_y - exponent * mean_x).exp(), exponent, start))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn growth_fit_recovers_power_law() {
+        let times: Vec<f64> = (1..=1000).map(|t| t as f64).collect();
+        let lengths: Vec<f64> = times.iter().map(|t| 2.0 * t.powf(0.5)).collect();
+        let (prefactor, exponent, start) = growth_fit(&times, &lengths).unwrap();
+        assert!((prefactor - 2.0).abs() < 1e-9, "prefactor {prefactor}");
+        assert!((exponent - 0.5).abs() < 1e-9, "exponent {exponent}");
+        assert!((start - 1000f64.sqrt()).abs() < 1e-9);
+    }
+
+    #[test]
+    fn growth_fit_skips_undefined_lengths() {
+        let times: Vec<f64> = (1..=100).map(|t| t as f64).collect();
+        let mut lengths: Vec<f64> = times.iter().map(|t| 3.0 * t.powf(1.0 / 3.0)).collect();
+        lengths[90] = f64::NAN;
+        let (_, exponent, _) = growth_fit(&times, &lengths).unwrap();
+        assert!((exponent - 1.0 / 3.0).abs() < 1e-9, "exponent {exponent}");
+        assert!(growth_fit(&[1.0, 2.0], &[1.0, 2.0]).is_none());
+    }
+}
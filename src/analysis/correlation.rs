@@ -0,0 +1,157 @@
+use crate::algorithm_processor::Table;
+use crate::analysis::{Complex, Fft2};
+
+/// Correlations below this are noise and left out of the fit.
+const FIT_FLOOR: f64 = 0.02;
+
+/// Orientational correlation function `G(r) = <cos 2(theta_i - theta_j)>` of a periodic 2D
+/// lattice, averaged over pairs in distance bins of one cell up to half the shorter side.
+#[derive(Debug, Clone)]
+pub struct Correlation {
+    /// Mean pair distance of every bin.
+    pub distances: Vec<f64>,
+    pub values: Vec<f64>,
+    /// Fitted `G(r) = A e^(-r / length)`.
+    pub amplitude: f64,
+    pub length: f64,
+}
+
+impl Correlation {
+    /// With `z = e^(2 i theta)`, `cos 2(theta_i - theta_j) = Re(z_i conj(z_j))`, so the sum over all
+    /// pairs at every offset is the inverse transform of `|FFT z|^2`.
+    pub fn new(angles: &[f32], dimensions: (usize, usize)) -> Self {
+        let field = angles.iter().map(|&angle| Complex::unit(2.0 * angle as f64)).collect();
+        Self::autocorrelation(field, dimensions, 1.0)
+    }
+
+    /// `<d phi_i d phi_j> / <d phi^2>` of the fluctuations of a scalar field, e.g. a concentration.
+    pub fn scalar(values: &[f32], dimensions: (usize, usize)) -> Self {
+        let mean = values.iter().map(|&value| value as f64).sum::<f64>() / values.len().max(1) as f64;
+        let field: Vec<Complex> = values.iter().map(|&value| Complex::new(value as f64 - mean, 0.0)).collect();
+        let variance = field.iter().map(|value| value.re * value.re).sum::<f64>() / field.len().max(1) as f64;
+        Self::autocorrelation(field, dimensions, variance.max(f64::MIN_POSITIVE))
+    }
+
+    /// Bins `Re(z_i conj(z_j)) / variance` by pair distance.
+    fn autocorrelation(mut data: Vec<Complex>, (rows, cols): (usize, usize), variance: f64) -> Self {
+        let fft = Fft2::new((rows, cols));
+        fft.process(&mut data, false);
+        for value in data.iter_mut() {
+            *value = Complex::new(value.re * value.re + value.im * value.im, 0.0);
+        }
+        fft.process(&mut data, true);
+
+        let bins = rows.min(cols) / 2 + 1;
+        let (mut sums, mut distances, mut counts) = (vec![0.0; bins], vec![0.0; bins], vec![0usize; bins]);
+        let sites = (rows * cols) as f64;
+        for row in 0..rows {
+            for col in 0..cols {
+                // Offsets past half the lattice are closer through the periodic boundary.
+                let dy = row.min(rows - row) as f64;
+                let dx = col.min(cols - col) as f64;
+                let distance = (dx * dx + dy * dy).sqrt();
+                let bin = distance.round() as usize;
+                if bin < bins {
+                    sums[bin] += data[row * cols + col].re / (sites * variance);
+                    distances[bin] += distance;
+                    counts[bin] += 1;
+                }
+            }
+        }
+        let (distances, values): (Vec<f64>, Vec<f64>) = (0..bins)
+            .filter(|&bin| counts[bin] > 0)
+            .map(|bin| (distances[bin] / counts[bin] as f64, sums[bin] / counts[bin] as f64))
+            .unzip();
+        let (amplitude, length) = fit_exponential(&distances, &values);
+        Self {
+            distances,
+            values,
+            amplitude,
+            length,
+        }
+    }
+
+    /// Distance where the correlation first crosses zero, interpolated between bins, or the
+    /// largest distance when it stays positive.
+    pub fn first_zero(&self) -> f64 {
+        let points: Vec<(f64, f64)> = self.distances.iter().copied().zip(self.values.iter().copied()).collect();
+        points
+            .windows(2)
+            .find(|pair| pair[1].1 <= 0.0)
+            .map(|pair| {
+                let ((r0, g0), (r1, g1)) = (pair[0], pair[1]);
+                r0 + (r1 - r0) * g0 / (g0 - g1)
+            })
+            .unwrap_or_else(|| self.distances.last().copied().unwrap_or(0.0))
+    }
+
+    pub fn table(&self) -> Table {
+        let fit = self
+            .distances
+            .iter()
+            .map(|&distance| self.amplitude * (-distance / self.length.max(f64::MIN_POSITIVE)).exp())
+            .collect();
+        Table::new("correlation")
+            .with_column("distance", self.distances.clone())
+            .with_column("G", self.values.clone())
+            .with_column("G_fit", fit)
+    }
+}
+
+/// Least squares fit of `ln G` against `r` from `r = 0` up to where `G` first drops below
+/// `FIT_FLOOR`. The length is capped at the largest distance, which an ordered lattice reaches.
+fn fit_exponential(distances: &[f64], values: &[f64]) -> (f64, f64) {
+    let largest = distances.last().copied().unwrap_or(0.0);
+    let points: Vec<(f64, f64)> = distances
+        .iter()
+        .zip(values)
+        .take_while(|&(_, &value)| value > FIT_FLOOR)
+        .map(|(&distance, &value)| (distance, value.ln()))
+        .collect();
+    if points.len() < 2 {
+        return (1.0, 0.0);
+    }
+    let count = points.len() as f64;
+    let mean_x = points.iter().map(|p| p.0).sum::<f64>() / count;
+    let mean_y = points.iter().map(|p| p.1).sum::<f64>() / count;
+    let covariance: f64 = points.iter().map(|p| (p.0 - mean_x) * (p.1 - mean_y)).sum();
+    let variance: f64 = points.iter().map(|p| (p.0 - mean_x).powi(2)).sum();
+    let slope = covariance / variance;
+    let amplitude = (mean_y - slope * mean_x).exp();
+    let length = if slope < 0.0 { (-1.0 / slope).min(largest) } else { largest };
+    (amplitude, length)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fit_recovers_exponential_decay() {
+        let distances: Vec<f64> = (0..=20).map(|r| r as f64).collect();
+        let values: Vec<f64> = distances.iter().map(|r| 0.9 * (-r / 4.0).exp()).collect();
+        let (amplitude, length) = fit_exponential(&distances, &values);
+        assert!((amplitude - 0.9).abs() < 1e-9, "amplitude {amplitude}");
+        assert!((length - 4.0).abs() < 1e-9, "length {length}");
+    }
+
+    #[test]
+    fn first_zero_is_interpolated() {
+        let distances: Vec<f64> = (0..=10).map(|r| r as f64).collect();
+        let correlation = Correlation {
+            values: distances.iter().map(|r| 1.0 - r / 4.5).collect(),
+            distances,
+            amplitude: 1.0,
+            length: 1.0,
+        };
+        assert!((correlation.first_zero() - 4.5).abs() < 1e-9);
+    }
+
+    #[test]
+    fn ordered_lattice_stays_correlated() {
+        let correlation = Correlation::new(&[0.3; 16 * 16], (16, 16));
+        assert!(correlation.values.iter().all(|&value| (value - 1.0).abs() < 1e-9));
+        let largest = *correlation.distances.last().unwrap();
+        assert_eq!((correlation.first_zero(), correlation.length), (largest, largest));
+    }
+}
@@ -0,0 +1,288 @@
+use std::f64::consts::PI;
+use std::ops::{Add, Mul, Sub};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Complex {
+    pub re: f64,
+    pub im: f64,
+}
+
+impl Complex {
+    pub const ZERO: Complex = Complex { re: 0.0, im: 0.0 };
+
+    pub fn new(re: f64, im: f64) -> Self {
+        Self { re, im }
+    }
+
+    /// `e^(i phase)`.
+    pub fn unit(phase: f64) -> Self {
+        Self::new(phase.cos(), phase.sin())
+    }
+
+    pub fn conj(self) -> Self {
+        Self::new(self.re, -self.im)
+    }
+
+    pub fn scale(self, factor: f64) -> Self {
+        Self::new(self.re * factor, self.im * factor)
+    }
+
+    /// Multiplication by `i`.
+    pub fn times_i(self) -> Self {
+        Self::new(-self.im, self.re)
+    }
+}
+
+impl Add for Complex {
+    type Output = Complex;
+    fn add(self, other: Complex) -> Complex {
+        Complex::new(self.re + other.re, self.im + other.im)
+    }
+}
+
+impl Sub for Complex {
+    type Output = Complex;
+    fn sub(self, other: Complex) -> Complex {
+        Complex::new(self.re - other.re, self.im - other.im)
+    }
+}
+
+impl Mul for Complex {
+    type Output = Complex;
+    fn mul(self, other: Complex) -> Complex {
+        Complex::new(
+            self.re * other.re - self.im * other.im,
+            self.re * other.im + self.im * other.re,
+        )
+    }
+}
+
+/// Discrete Fourier transform of one length, `X_k = sum_n x_n e^(-2 pi i k n / N)`.
+///
+/// Powers of two use iterative radix-2, other lengths Bluestein's chirp-z on a padded radix-2
+/// transform, so every length costs O(N log N). The inverse is unnormalised.
+pub struct Fft {
+    len: usize,
+    kind: FftKind,
+}
+
+enum FftKind {
+    Radix2 { twiddles: Vec<Complex> },
+    Bluestein {
+        /// `e^(-i pi n^2 / N)`.
+        chirp: Vec<Complex>,
+        /// Transform of the conjugate chirp wrapped onto the padded length.
+        kernel: Vec<Complex>,
+        inner: Box<Fft>,
+    },
+}
+
+impl Fft {
+    pub fn new(len: usize) -> Self {
+        let kind = if len.is_power_of_two() || len == 0 {
+            FftKind::Radix2 {
+                twiddles: (0..len / 2).map(|k| Complex::unit(-2.0 * PI * k as f64 / len as f64)).collect(),
+            }
+        } else {
+            let padded = (2 * len - 1).next_power_of_two();
+            let inner = Fft::new(padded);
+            // n^2 mod 2N keeps the phase accurate for long transforms.
+            let chirp: Vec<Complex> = (0..len)
+                .map(|n| Complex::unit(-PI * ((n * n) % (2 * len)) as f64 / len as f64))
+                .collect();
+            let mut kernel = vec![Complex::ZERO; padded];
+            kernel[0] = chirp[0].conj();
+            for n in 1..len {
+                kernel[n] = chirp[n].conj();
+                kernel[padded - n] = chirp[n].conj();
+            }
+            inner.radix2(&mut kernel, false);
+            FftKind::Bluestein {
+                chirp,
+                kernel,
+                inner: Box::new(inner),
+            }
+        };
+        Self { len, kind }
+    }
+
+    /// Transforms `data` in place; `inverse` uses `e^(+2 pi i k n / N)` without dividing by N.
+    pub fn process(&self, data: &mut [Complex], inverse: bool) {
+        assert_eq!(data.len(), self.len, "transform length mismatch");
+        match &self.kind {
+            FftKind::Radix2 { .. } => self.radix2(data, inverse),
+            FftKind::Bluestein { chirp, kernel, inner } => {
+                // The inverse is the conjugate of the forward transform of the conjugate.
+                let sign = |value: Complex| if inverse { value.conj() } else { value };
+                let mut padded = vec![Complex::ZERO; kernel.len()];
+                for n in 0..self.len {
+                    padded[n] = sign(data[n]) * chirp[n];
+                }
+                inner.radix2(&mut padded, false);
+                for (value, kernel) in padded.iter_mut().zip(kernel) {
+                    *value = *value * *kernel;
+                }
+                inner.radix2(&mut padded, true);
+                let normalisation = 1.0 / kernel.len() as f64;
+                for k in 0..self.len {
+                    data[k] = sign((padded[k] * chirp[k]).scale(normalisation));
+                }
+            }
+        }
+    }
+
+    fn radix2(&self, data: &mut [Complex], inverse: bool) {
+        let FftKind::Radix2 { twiddles } = &self.kind else {
+            unreachable!("radix-2 pass on a Bluestein plan");
+        };
+        let len = data.len();
+        if len < 2 {
+            return;
+        }
+        let bits = len.trailing_zeros();
+        for index in 0..len {
+            let reversed = index.reverse_bits() >> (usize::BITS - bits);
+            if index < reversed {
+                data.swap(index, reversed);
+            }
+        }
+        let mut size = 2;
+        while size <= len {
+            let stride = len / size;
+            for start in (0..len).step_by(size) {
+                for k in 0..size / 2 {
+                    let twiddle = twiddles[k * stride];
+                    let twiddle = if inverse { twiddle.conj() } else { twiddle };
+                    let even = data[start + k];
+                    let odd = data[start + k + size / 2] * twiddle;
+                    data[start + k] = even + odd;
+                    data[start + k + size / 2] = even - odd;
+                }
+            }
+            size *= 2;
+        }
+    }
+}
+
+/// Transforms of a row-major `(rows, cols)` grid, along rows and then columns.
+pub struct Fft2 {
+    dimensions: (usize, usize),
+    rows: Fft,
+    cols: Fft,
+}
+
+impl Fft2 {
+    pub fn new(dimensions: (usize, usize)) -> Self {
+        Self {
+            dimensions,
+            rows: Fft::new(dimensions.0),
+            cols: Fft::new(dimensions.1),
+        }
+    }
+
+    pub fn dimensions(&self) -> (usize, usize) {
+        self.dimensions
+    }
+
+    /// Forward transform; the inverse divides by the number of sites so that it round-trips.
+    pub fn process(&self, data: &mut [Complex], inverse: bool) {
+        let (rows, cols) = self.dimensions;
+        assert_eq!(data.len(), rows * cols, "grid size mismatch");
+        for row in data.chunks_mut(cols) {
+            self.cols.process(row, inverse);
+        }
+        let mut column = vec![Complex::ZERO; rows];
+        for col in 0..cols {
+            for row in 0..rows {
+                column[row] = data[row * cols + col];
+            }
+            self.rows.process(&mut column, inverse);
+            for row in 0..rows {
+                data[row * cols + col] = column[row];
+            }
+        }
+        if inverse {
+            let normalisation = 1.0 / (rows * cols) as f64;
+            for value in data.iter_mut() {
+                *value = value.scale(normalisation);
+            }
+        }
+    }
+
+    pub fn forward_real(&self, values: &[f32]) -> Vec<Complex> {
+        let mut data: Vec<Complex> = values.iter().map(|&value| Complex::new(value as f64, 0.0)).collect();
+        self.process(&mut data, false);
+        data
+    }
+
+    /// Real part of the normalised inverse transform.
+    pub fn inverse_real(&self, spectrum: &[Complex]) -> Vec<f32> {
+        let mut data = spectrum.to_vec();
+        self.process(&mut data, true);
+        data.into_iter().map(|value| value.re as f32).collect()
+    }
+}
+
+/// Angular wavenumber of frequency bin `index` of an `len`-point transform, in `[-pi, pi)`.
+pub fn wavenumber(index: usize, len: usize) -> f64 {
+    let signed = if 2 * index >= len { index as f64 - len as f64 } else { index as f64 };
+    2.0 * PI * signed / len as f64
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn signal(len: usize) -> Vec<Complex> {
+        (0..len)
+            .map(|n| Complex::new((0.7 * n as f64).sin() + 0.1 * n as f64, (1.3 * n as f64).cos()))
+            .collect()
+    }
+
+    fn naive_dft(data: &[Complex], inverse: bool) -> Vec<Complex> {
+        let len = data.len();
+        let sign = if inverse { 1.0 } else { -1.0 };
+        (0..len)
+            .map(|k| {
+                data.iter().enumerate().fold(Complex::ZERO, |sum, (n, &value)| {
+                    sum + value * Complex::unit(sign * 2.0 * PI * ((k * n) % len) as f64 / len as f64)
+                })
+            })
+            .collect()
+    }
+
+    fn largest_difference(a: &[Complex], b: &[Complex]) -> f64 {
+        a.iter()
+            .zip(b)
+            .map(|(a, b)| (a.re - b.re).hypot(a.im - b.im))
+            .fold(0.0, f64::max)
+    }
+
+    #[test]
+    fn matches_naive_dft() {
+        // Powers of two take the radix-2 path, the others Bluestein.
+        for len in [1, 2, 8, 64, 3, 12, 45, 100] {
+            for inverse in [false, true] {
+                let mut data = signal(len);
+                let expected = naive_dft(&data, inverse);
+                Fft::new(len).process(&mut data, inverse);
+                let error = largest_difference(&data, &expected);
+                assert!(error < 1e-9 * len as f64, "length {len}, inverse {inverse}: error {error}");
+            }
+        }
+    }
+
+    #[test]
+    fn round_trips() {
+        for dimensions in [(16, 32), (12, 20), (7, 64)] {
+            let original = signal(dimensions.0 * dimensions.1);
+            let fft = Fft2::new(dimensions);
+            let mut data = original.clone();
+            fft.process(&mut data, false);
+            fft.process(&mut data, true);
+            let error = largest_difference(&data, &original);
+            assert!(error < 1e-9, "{dimensions:?}: error {error}");
+        }
+    }
+}
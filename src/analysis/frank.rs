@@ -0,0 +1,168 @@
+use crate::algorithm_processor::{DataType, Dimensions3};
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+/// Frank elastic term drawn in the scalar map layer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ElasticMap {
+    #[default]
+    Off,
+    Splay,
+    Twist,
+    Bend,
+}
+
+pub const ELASTIC_MAPS: [ElasticMap; 4] = [ElasticMap::Off, ElasticMap::Splay, ElasticMap::Twist, ElasticMap::Bend];
+
+impl fmt::Display for ElasticMap {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Off => write!(f, "off"),
+            Self::Splay => write!(f, "splay"),
+            Self::Twist => write!(f, "twist"),
+            Self::Bend => write!(f, "bend"),
+        }
+    }
+}
+
+/// Local splay `(div n)^2`, twist `(n . curl n)^2` and bend `|n x curl n|^2` of a director field.
+///
+/// Derivatives are central differences on a periodic lattice. The neighbours of a site are
+/// flipped onto the hemisphere of its own director first, so `n -> -n` leaves the maps unchanged.
+/// Directors lying in the plane of a 2D lattice have no twist.
+#[derive(Debug, Clone)]
+pub struct FrankMaps {
+    pub splay: Vec<f32>,
+    pub twist: Vec<f32>,
+    pub bend: Vec<f32>,
+    pub dimensions: Dimensions3,
+}
+
+impl FrankMaps {
+    pub fn new(directors: &[[f32; 3]], dimensions: Dimensions3) -> Self {
+        let (rows, cols, layers) = dimensions;
+        let site = |row: usize, col: usize, layer: usize| (layer * rows + row) * cols + col;
+        let sites = directors.len();
+        let (mut splay, mut twist, mut bend) = (vec![0.0; sites], vec![0.0; sites], vec![0.0; sites]);
+
+        for layer in 0..layers {
+            for row in 0..rows {
+                for col in 0..cols {
+                    let index = site(row, col, layer);
+                    let n = directors[index];
+                    let aligned = |other: usize| {
+                        let m = directors[other];
+                        if dot(n, m) < 0.0 { [-m[0], -m[1], -m[2]] } else { m }
+                    };
+                    let difference = |forward: usize, backward: usize| {
+                        let (forward, backward) = (aligned(forward), aligned(backward));
+                        [
+                            0.5 * (forward[0] - backward[0]),
+                            0.5 * (forward[1] - backward[1]),
+                            0.5 * (forward[2] - backward[2]),
+                        ]
+                    };
+                    // gradient[a][b] = d n_b / d x_a with x, y, z along columns, rows and layers.
+                    let gradient = [
+                        difference(site(row, (col + 1) % cols, layer), site(row, (col + cols - 1) % cols, layer)),
+                        difference(site((row + 1) % rows, col, layer), site((row + rows - 1) % rows, col, layer)),
+                        difference(
+                            site(row, col, (layer + 1) % layers),
+                            site(row, col, (layer + layers - 1) % layers),
+                        ),
+                    ];
+                    let divergence = gradient[0][0] + gradient[1][1] + gradient[2][2];
+                    let curl = [
+                        gradient[1][2] - gradient[2][1],
+                        gradient[2][0] - gradient[0][2],
+                        gradient[0][1] - gradient[1][0],
+                    ];
+                    let bend_vector = [
+                        n[1] * curl[2] - n[2] * curl[1],
+                        n[2] * curl[0] - n[0] * curl[2],
+                        n[0] * curl[1] - n[1] * curl[0],
+                    ];
+                    splay[index] = divergence * divergence;
+                    twist[index] = dot(n, curl).powi(2);
+                    bend[index] = dot(bend_vector, bend_vector);
+                }
+            }
+        }
+        Self {
+            splay,
+            twist,
+            bend,
+            dimensions,
+        }
+    }
+
+    /// Maps of the in-plane directors at `angles` of a 2D lattice.
+    pub fn from_angles(angles: &[f32], (rows, cols): (usize, usize)) -> Self {
+        let directors: Vec<[f32; 3]> = angles.iter().map(|angle| [angle.cos(), angle.sin(), 0.0]).collect();
+        Self::new(&directors, (rows, cols, 1))
+    }
+
+    /// Maps of the director field or angle grid in `data`, if it holds one.
+    pub fn from_data(data: &[DataType]) -> Option<Self> {
+        if let Some((directors, dimensions)) = DataType::directors(data) {
+            Some(Self::new(directors, dimensions))
+        } else {
+            DataType::oriented(data).map(|(angles, dimensions)| Self::from_angles(angles, dimensions))
+        }
+    }
+
+    fn is_3d(&self) -> bool {
+        self.dimensions.2 > 1
+    }
+
+    pub fn map(&self, map: ElasticMap) -> Option<&[f32]> {
+        match map {
+            ElasticMap::Off => None,
+            ElasticMap::Splay => Some(&self.splay),
+            ElasticMap::Twist => Some(&self.twist),
+            ElasticMap::Bend => Some(&self.bend),
+        }
+    }
+
+    /// Lattice sums of the terms as observables, twist only on 3D lattices.
+    pub fn totals(&self) -> Vec<(String, f64)> {
+        let total = |values: &[f32]| values.iter().map(|&value| value as f64).sum::<f64>();
+        let mut totals = vec![("splay_total".to_owned(), total(&self.splay))];
+        if self.is_3d() {
+            totals.push(("twist_total".to_owned(), total(&self.twist)));
+        }
+        totals.push(("bend_total".to_owned(), total(&self.bend)));
+        totals
+    }
+
+    /// Writes one `x,y[,z],splay[,twist],bend` row per site.
+    pub fn write_csv(&self, path: &Path) -> io::Result<()> {
+        let (rows, cols, _) = self.dimensions;
+        let mut writer = BufWriter::new(File::create(path)?);
+        if self.is_3d() {
+            writeln!(writer, "x,y,z,splay,twist,bend")?;
+        } else {
+            writeln!(writer, "x,y,splay,bend")?;
+        }
+        for index in 0..self.splay.len() {
+            let (x, y, z) = (index % cols, (index / cols) % rows, index / (rows * cols));
+            if self.is_3d() {
+                writeln!(
+                    writer,
+                    "{x},{y},{z},{},{},{}",
+                    self.splay[index], self.twist[index], self.bend[index]
+                )?;
+            } else {
+                writeln!(writer, "{x},{y},{},{}", self.splay[index], self.bend[index])?;
+            }
+        }
+        writer.flush()
+    }
+}
+
+fn dot(a: [f32; 3], b: [f32; 3]) -> f32 {
+    a[0] * b[0] + a[1] * b[1] + a[2] * b[2]
+}
+
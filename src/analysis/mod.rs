@@ -0,0 +1,17 @@
+pub mod analyses;
+pub mod coarsening;
+pub mod correlation;
+pub mod fft;
+pub mod frank;
+pub mod spectrum;
+pub mod statistics;
+pub mod tracking;
+
+pub use analyses::*;
+pub use coarsening::*;
+pub use correlation::*;
+pub use fft::*;
+pub use frank::*;
+pub use spectrum::*;
+pub use statistics::*;
+pub use tracking::*;
@@ -0,0 +1,159 @@
+use crate::algorithm_processor::{DataType, Table};
+use crate::analysis::{Fft2, wavenumber};
+use std::fmt;
+
+/// Field whose power spectrum is shown and averaged into S(k).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SpectrumField {
+    #[default]
+    Off,
+    Cos2Theta,
+    Sin2Theta,
+    /// Local Lebwohl-Lasher bond energy, `-1/2 sum_j P2(cos(theta_i - theta_j))`.
+    Energy,
+    /// The scalar field of the algorithm, e.g. a concentration.
+    Scalar,
+}
+
+pub const SPECTRUM_FIELDS: [SpectrumField; 5] = [
+    SpectrumField::Off,
+    SpectrumField::Cos2Theta,
+    SpectrumField::Sin2Theta,
+    SpectrumField::Energy,
+    SpectrumField::Scalar,
+];
+
+impl fmt::Display for SpectrumField {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Off => write!(f, "off"),
+            Self::Cos2Theta => write!(f, "cos2theta"),
+            Self::Sin2Theta => write!(f, "sin2theta"),
+            Self::Energy => write!(f, "energy"),
+            Self::Scalar => write!(f, "scalar"),
+        }
+    }
+}
+
+impl SpectrumField {
+    pub fn parse(text: &str) -> Result<Self, String> {
+        SPECTRUM_FIELDS
+            .into_iter()
+            .find(|field| field.to_string() == text.trim())
+            .ok_or_else(|| {
+                let names: Vec<String> = SPECTRUM_FIELDS.iter().map(|field| field.to_string()).collect();
+                format!("Unknown spectrum field '{text}', expected one of {}", names.join(", "))
+            })
+    }
+
+    /// The field on a 2D lattice, from the director angles or the scalar grid.
+    pub fn values(
+        &self,
+        angles: Option<(&[f32], (usize, usize))>,
+        scalars: Option<(&[f32], (usize, usize))>,
+    ) -> Option<(Vec<f32>, (usize, usize))> {
+        match self {
+            Self::Off => None,
+            Self::Cos2Theta => angles.map(|(angles, dims)| (angles.iter().map(|a| (2.0 * a).cos()).collect(), dims)),
+            Self::Sin2Theta => angles.map(|(angles, dims)| (angles.iter().map(|a| (2.0 * a).sin()).collect(), dims)),
+            Self::Energy => angles.map(|(angles, dims)| (bond_energy(angles, dims), dims)),
+            Self::Scalar => scalars.map(|(values, dims)| (values.to_vec(), dims)),
+        }
+    }
+
+    pub fn values_of(&self, data: &[DataType]) -> Option<(Vec<f32>, (usize, usize))> {
+        let angles = DataType::oriented(data).map(|(angles, dims)| (angles.as_slice(), dims));
+        let scalars = DataType::scalar(data).map(|(values, dims)| (values.as_slice(), dims));
+        self.values(angles, scalars)
+    }
+}
+
+/// Local bond energy of every site with its four periodic neighbours.
+fn bond_energy(angles: &[f32], (rows, cols): (usize, usize)) -> Vec<f32> {
+    let p2 = |a: f32, b: f32| {
+        let cos = (a - b).cos();
+        1.5 * cos * cos - 0.5
+    };
+    (0..angles.len())
+        .map(|index| {
+            let (row, col) = (index / cols, index % cols);
+            let neighbours = [
+                row * cols + (col + 1) % cols,
+                row * cols + (col + cols - 1) % cols,
+                (row + 1) % rows * cols + col,
+                (row + rows - 1) % rows * cols + col,
+            ];
+            -0.5 * neighbours.iter().map(|&other| p2(angles[index], angles[other])).sum::<f32>()
+        })
+        .collect()
+}
+
+/// Power spectrum `|FFT(f - <f>)|^2 / N` of a periodic 2D field, with k = 0 moved to the centre.
+#[derive(Debug, Clone)]
+pub struct PowerSpectrum {
+    pub power: Vec<f64>,
+    pub dimensions: (usize, usize),
+}
+
+/// Decades below the strongest mode shown by the log image.
+const SPECTRUM_DECADES: f32 = 6.0;
+
+impl PowerSpectrum {
+    pub fn new(values: &[f32], dimensions: (usize, usize)) -> Self {
+        let (rows, cols) = dimensions;
+        let mean = values.iter().map(|&value| value as f64).sum::<f64>() / values.len().max(1) as f64;
+        let fluctuations: Vec<f32> = values.iter().map(|&value| (value as f64 - mean) as f32).collect();
+        let spectrum = Fft2::new(dimensions).forward_real(&fluctuations);
+        let sites = (rows * cols) as f64;
+        let mut power = vec![0.0; rows * cols];
+        for row in 0..rows {
+            for col in 0..cols {
+                let value = spectrum[row * cols + col];
+                let shifted = (row + rows / 2) % rows * cols + (col + cols / 2) % cols;
+                power[shifted] = (value.re * value.re + value.im * value.im) / sites;
+            }
+        }
+        Self { power, dimensions }
+    }
+
+    /// `log10` of the power with the range the colormap spans, `SPECTRUM_DECADES` down from the
+    /// strongest mode.
+    pub fn log_image(&self) -> (Vec<f32>, (f32, f32)) {
+        let image: Vec<f32> = self.power.iter().map(|&power| power.max(1e-30).log10() as f32).collect();
+        let max = image.iter().fold(f32::NEG_INFINITY, |max, &value| max.max(value));
+        (image, (max - SPECTRUM_DECADES, max))
+    }
+
+    /// S(k) averaged over rings one `2 pi / L` wide, without k = 0, up to half the shorter side.
+    pub fn radial(&self) -> (Vec<f64>, Vec<f64>) {
+        let (rows, cols) = self.dimensions;
+        let step = wavenumber(1, rows.min(cols));
+        let bins = rows.min(cols) / 2 + 1;
+        let (mut sums, mut wavenumbers, mut counts) = (vec![0.0; bins], vec![0.0; bins], vec![0usize; bins]);
+        for row in 0..rows {
+            for col in 0..cols {
+                let (ky, kx) = (wavenumber(row, rows), wavenumber(col, cols));
+                let k = (kx * kx + ky * ky).sqrt();
+                let bin = (k / step).round() as usize;
+                if bin == 0 || bin >= bins {
+                    continue;
+                }
+                let shifted = (row + rows / 2) % rows * cols + (col + cols / 2) % cols;
+                sums[bin] += self.power[shifted];
+                wavenumbers[bin] += k;
+                counts[bin] += 1;
+            }
+        }
+        (1..bins)
+            .filter(|&bin| counts[bin] > 0)
+            .map(|bin| (wavenumbers[bin] / counts[bin] as f64, sums[bin] / counts[bin] as f64))
+            .unzip()
+    }
+
+    pub fn table(&self) -> Table {
+        let (wavenumbers, structure_factor) = self.radial();
+        Table::new("structure_factor")
+            .with_column("k", wavenumbers)
+            .with_column("S", structure_factor)
+    }
+}
@@ -0,0 +1,284 @@
+use crate::algorithm_processor::*;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+/// Window constant of Sokal's automatic windowing for the autocorrelation time.
+const WINDOW_FACTOR: f64 = 5.0;
+
+/// Equilibrium statistics of one observable time series.
+#[derive(Debug, Clone)]
+pub struct SeriesStatistics {
+    /// Number of leading samples discarded as burn-in.
+    pub burn_in: usize,
+    pub mean: f64,
+    pub variance: f64,
+    pub standard_error: f64,
+    /// Integrated autocorrelation time in units of samples.
+    pub autocorrelation_time: f64,
+    pub block_size: usize,
+}
+
+impl SeriesStatistics {
+    pub fn new(series: &[f64]) -> Option<Self> {
+        if series.is_empty() {
+            return None;
+        }
+        let burn_in = mser_burn_in(series);
+        let equilibrated = &series[burn_in..];
+        let autocorrelation_time = integrated_autocorrelation_time(equilibrated);
+        let block_size = ((2.0 * autocorrelation_time).ceil() as usize).max(1);
+        let (mean, standard_error) = block_average(equilibrated, block_size);
+
+        Some(Self {
+            burn_in,
+            mean,
+            variance: variance(equilibrated),
+            standard_error,
+            autocorrelation_time,
+            block_size,
+        })
+    }
+}
+
+/// Marginal standard error rule: the truncation point `d` minimising
+/// `sum_{i>=d} (x_i - mean_d)^2 / (n - d)^2`, searched over the first half of the series.
+pub fn mser_burn_in(series: &[f64]) -> usize {
+    let n = series.len();
+    if n < 4 {
+        return 0;
+    }
+
+    let (mut sum, mut sum_squares) = (0.0, 0.0);
+    let mut best = (f64::INFINITY, 0);
+    for d in (0..n).rev() {
+        sum += series[d];
+        sum_squares += series[d] * series[d];
+        if d > n / 2 {
+            continue;
+        }
+        let remaining = (n - d) as f64;
+        let statistic = (sum_squares - sum * sum / remaining).max(0.0) / (remaining * remaining);
+        if statistic <= best.0 {
+            best = (statistic, d);
+        }
+    }
+
+    best.1
+}
+
+pub fn mean(series: &[f64]) -> f64 {
+    series.iter().sum::<f64>() / series.len().max(1) as f64
+}
+
+pub fn variance(series: &[f64]) -> f64 {
+    if series.len() < 2 {
+        return 0.0;
+    }
+    let mean = mean(series);
+    series.iter().map(|x| (x - mean).powi(2)).sum::<f64>() / (series.len() - 1) as f64
+}
+
+/// `tau = 1/2 + sum_t rho(t)`, truncated at the first window `M >= 5 tau(M)`.
+pub fn integrated_autocorrelation_time(series: &[f64]) -> f64 {
+    let n = series.len();
+    if n < 2 {
+        return 0.5;
+    }
+
+    let mean = mean(series);
+    let autocovariance = |lag: usize| {
+        series[..n - lag]
+            .iter()
+            .zip(&series[lag..])
+            .map(|(a, b)| (a - mean) * (b - mean))
+            .sum::<f64>()
+            / (n - lag) as f64
+    };
+
+    let c0 = autocovariance(0);
+    if c0 <= f64::EPSILON {
+        return 0.5;
+    }
+
+    let mut tau = 0.5;
+    for lag in 1..n / 2 {
+        tau += autocovariance(lag) / c0;
+        if lag as f64 >= WINDOW_FACTOR * tau {
+            break;
+        }
+    }
+    tau.max(0.5)
+}
+
+/// Mean and its standard error from the means of consecutive blocks of `block_size` samples.
+pub fn block_average(series: &[f64], block_size: usize) -> (f64, f64) {
+    let mean = mean(series);
+    let block_means = block_means(series, block_size);
+    if block_means.len() < 2 {
+        return (mean, f64::NAN);
+    }
+    (mean, (variance(&block_means) / block_means.len() as f64).sqrt())
+}
+
+fn block_means(series: &[f64], block_size: usize) -> Vec<f64> {
+    series.chunks_exact(block_size.max(1)).map(mean).collect()
+}
+
+/// Jackknife estimate and error of `estimator` over blocks of `block_size` samples.
+pub fn jackknife(series: &[f64], block_size: usize, estimator: impl Fn(&[f64]) -> f64) -> (f64, f64) {
+    let block_size = block_size.max(1);
+    let blocks = series.len() / block_size;
+    let full = estimator(series);
+    if blocks < 2 {
+        return (full, f64::NAN);
+    }
+
+    let estimates: Vec<f64> = (0..blocks)
+        .map(|block| {
+            let mut reduced = Vec::with_capacity(series.len() - block_size);
+            reduced.extend_from_slice(&series[..block * block_size]);
+            reduced.extend_from_slice(&series[(block + 1) * block_size..blocks * block_size]);
+            estimator(&reduced)
+        })
+        .collect();
+    let average = mean(&estimates);
+    let spread = estimates.iter().map(|e| (e - average).powi(2)).sum::<f64>();
+    (full, ((blocks - 1) as f64 / blocks as f64 * spread).sqrt())
+}
+
+/// Time-averaged results of a whole run.
+#[derive(Debug, Clone, Default)]
+pub struct RunStatistics {
+    pub samples: usize,
+    pub observables: Vec<(String, SeriesStatistics)>,
+    /// `(name, value, error)` for quantities derived from fluctuations.
+    pub derived: Vec<(String, f64, f64)>,
+}
+
+impl RunStatistics {
+    /// `temperature` and the number of lattice `sites` are needed for the specific heat
+    /// `N var(e) / T^2` and the susceptibility `N var(S) / T`, with `e` and `S` per site.
+    pub fn new(samples: &[Observables], temperature: Option<f32>, sites: usize) -> Self {
+        let names: Vec<String> = samples
+            .first()
+            .map(|sample| sample.values.iter().map(|(name, _)| name.clone()).collect())
+            .unwrap_or_default();
+
+        let mut statistics = Self {
+            samples: samples.len(),
+            ..Default::default()
+        };
+        for name in names {
+            let values = series(samples, &name);
+            let Some(result) = SeriesStatistics::new(&values) else {
+                continue;
+            };
+
+            if let Some(temperature) = temperature.map(|t| t as f64) {
+                let sites = sites as f64;
+                let equilibrated = &values[result.burn_in..];
+                let derived = match name.as_str() {
+                    "energy" => Some(("specific_heat", sites / (temperature * temperature))),
+                    "order_parameter" => Some(("susceptibility", sites / temperature)),
+                    _ => None,
+                };
+                if let Some((derived_name, factor)) = derived {
+                    let (value, error) =
+                        jackknife(equilibrated, result.block_size, |x| factor * variance(x));
+                    statistics.derived.push((derived_name.to_owned(), value, error));
+                }
+            }
+
+            statistics.observables.push((name, result));
+        }
+
+        statistics
+    }
+
+    /// `(name, mean, error)` of every observable followed by the derived quantities.
+    pub fn quantities(&self) -> Vec<(String, f64, f64)> {
+        self.observables
+            .iter()
+            .map(|(name, s)| (name.clone(), s.mean, s.standard_error))
+            .chain(self.derived.iter().cloned())
+            .collect()
+    }
+
+    pub fn write_csv(&self, path: &Path) -> io::Result<()> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        writeln!(writer, "quantity,mean,error,variance,burn_in,autocorrelation_time,block_size")?;
+        for (name, s) in &self.observables {
+            writeln!(
+                writer,
+                "{name},{},{},{},{},{},{}",
+                s.mean, s.standard_error, s.variance, s.burn_in, s.autocorrelation_time, s.block_size
+            )?;
+        }
+        for (name, value, error) in &self.derived {
+            writeln!(writer, "{name},{value},{error},,,,")?;
+        }
+        writer.flush()
+    }
+
+    pub fn format(&self) -> String {
+        let mut text = format!("{} samples\n", self.samples);
+        for (name, s) in &self.observables {
+            text.push_str(&format!(
+                "{name}: {:.6} ± {:.6} (burn-in {}, tau {:.1})\n",
+                s.mean, s.standard_error, s.burn_in, s.autocorrelation_time
+            ));
+        }
+        for (name, value, error) in &self.derived {
+            text.push_str(&format!("{name}: {value:.6} ± {error:.6}\n"));
+        }
+        text
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+
+    fn uniform(count: usize, seed: u64) -> Vec<f64> {
+        let mut rng = StdRng::seed_from_u64(seed);
+        (0..count).map(|_| rng.random_range(0.0..1.0)).collect()
+    }
+
+    #[test]
+    fn autocorrelation_time_of_ar1() {
+        // x_t = phi x_(t-1) + noise has rho(t) = phi^t, so tau = 1/2 + phi / (1 - phi).
+        let phi = 0.8;
+        let mut x = 0.0;
+        let series: Vec<f64> = uniform(200_000, 1)
+            .into_iter()
+            .map(|noise| {
+                x = phi * x + noise - 0.5;
+                x
+            })
+            .collect();
+        let expected = 0.5 + phi / (1.0 - phi);
+        let tau = integrated_autocorrelation_time(&series);
+        assert!((tau - expected).abs() < 0.05 * expected, "tau {tau} against {expected}");
+        assert!((integrated_autocorrelation_time(&uniform(100_000, 2)) - 0.5).abs() < 0.05);
+    }
+
+    #[test]
+    fn block_average_and_jackknife_of_independent_samples() {
+        let series = uniform(40_000, 3);
+        let expected_error = (1.0 / 12.0 / series.len() as f64).sqrt();
+        for block_size in [1, 10, 100] {
+            let (mean, error) = block_average(&series, block_size);
+            assert!((mean - 0.5).abs() < 4.0 * expected_error, "mean {mean}");
+            assert!((error / expected_error - 1.0).abs() < 0.25, "block {block_size}: error {error}");
+
+            let (estimate, error) = jackknife(&series, block_size, super::mean);
+            assert_eq!(estimate, mean);
+            assert!((error / expected_error - 1.0).abs() < 0.25, "jackknife {block_size}: error {error}");
+        }
+        let (estimate, error) = jackknife(&series, 100, variance);
+        assert!((estimate - 1.0 / 12.0).abs() < 4.0 * error, "variance {estimate} ± {error}");
+    }
+}
@@ -0,0 +1,385 @@
+use crate::algorithm_processor::{Boundaries, DataType, Defect, find_defects};
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+/// Furthest a defect is followed between two frames, in cells.
+const MAX_JUMP: f32 = 3.0;
+/// Furthest apart two defects of opposite charge are paired when they appear or vanish together.
+const PAIR_DISTANCE: f32 = 2.0 * MAX_JUMP;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum EventKind {
+    Creation,
+    Annihilation,
+}
+
+/// Two tracks of opposite charge that started or ended together.
+#[derive(Debug, Clone, Copy)]
+pub struct DefectEvent {
+    pub iteration: usize,
+    pub kind: EventKind,
+    pub tracks: (usize, usize),
+    /// Midpoint of the pair.
+    pub position: (f32, f32),
+}
+
+/// One defect followed across frames; its id is its index in `DefectTracker::tracks`.
+#[derive(Debug, Clone)]
+pub struct DefectTrack {
+    pub charge: f32,
+    pub points: Vec<(usize, (f32, f32))>,
+    /// First iteration it was missing on, `None` while it lives.
+    pub ended: Option<usize>,
+    /// The track it was created with and the one it annihilated with.
+    pub created_with: Option<usize>,
+    pub annihilated_with: Option<usize>,
+}
+
+impl DefectTrack {
+    fn last(&self) -> (f32, f32) {
+        self.points.last().map_or((0.0, 0.0), |point| point.1)
+    }
+}
+
+/// Links the defects of successive frames into trajectories.
+///
+/// Every living track moves to the closest new defect of its charge within `MAX_JUMP` cells,
+/// closest pairs first. Tracks left without one end; ending tracks of opposite charge within
+/// `PAIR_DISTANCE` of each other are recorded as an annihilation, and new tracks as a creation
+/// in the same way. Other ends are defects leaving through an edge or lost to noise. Distances
+/// are taken to the nearest image across periodic axes, so defects crossing them stay linked.
+#[derive(Debug, Clone, Default)]
+pub struct DefectTracker {
+    pub tracks: Vec<DefectTrack>,
+    pub events: Vec<DefectEvent>,
+    alive: Vec<usize>,
+    /// Set after the first frame, whose defects were not created.
+    started: bool,
+    /// Whether the x and y axes wrap around.
+    periodic: (bool, bool),
+    /// `(rows, cols)` of the latest frame.
+    dimensions: (usize, usize),
+}
+
+impl DefectTracker {
+    /// A tracker for lattices with the given `boundaries`.
+    pub fn new(boundaries: &Boundaries) -> Self {
+        Self {
+            periodic: boundaries.periodic(),
+            ..Self::default()
+        }
+    }
+
+    /// Follows later frames across the periodic axes of `boundaries`.
+    pub fn set_boundaries(&mut self, boundaries: &Boundaries) {
+        self.periodic = boundaries.periodic();
+    }
+
+    /// Follows the defects of the angle grid in `data`, if it holds one.
+    pub fn record(&mut self, iteration: usize, data: &[DataType]) {
+        if let Some((angles, dimensions)) = DataType::oriented(data) {
+            self.update(iteration, &find_defects(angles, dimensions), dimensions);
+        }
+    }
+
+    /// Links the `defects` of a `(rows, cols)` frame to the living tracks.
+    pub fn update(&mut self, iteration: usize, defects: &[Defect], dimensions: (usize, usize)) {
+        self.dimensions = dimensions;
+        let mut candidates = Vec::new();
+        for (slot, &track) in self.alive.iter().enumerate() {
+            for (index, defect) in defects.iter().enumerate() {
+                let distance = self.distance(self.tracks[track].last(), defect.position);
+                if self.tracks[track].charge == defect.charge && distance <= MAX_JUMP {
+                    candidates.push((distance, slot, index));
+                }
+            }
+        }
+        candidates.sort_by(|a, b| a.0.total_cmp(&b.0));
+        let mut continued = vec![false; self.alive.len()];
+        let mut taken = vec![false; defects.len()];
+        for (_, slot, index) in candidates {
+            if !continued[slot] && !taken[index] {
+                continued[slot] = true;
+                taken[index] = true;
+                self.tracks[self.alive[slot]].points.push((iteration, defects[index].position));
+            }
+        }
+
+        let ended: Vec<usize> = self
+            .alive
+            .iter()
+            .zip(&continued)
+            .filter(|&(_, &continued)| !continued)
+            .map(|(&track, _)| track)
+            .collect();
+        for &track in &ended {
+            self.tracks[track].ended = Some(iteration);
+        }
+        for (first, second) in self.pair(&ended) {
+            self.tracks[first].annihilated_with = Some(second);
+            self.tracks[second].annihilated_with = Some(first);
+            self.push_event(iteration, EventKind::Annihilation, first, second);
+        }
+        self.alive.retain(|&track| self.tracks[track].ended.is_none());
+
+        let created: Vec<usize> = defects
+            .iter()
+            .zip(&taken)
+            .filter(|&(_, &taken)| !taken)
+            .map(|(defect, _)| {
+                self.tracks.push(DefectTrack {
+                    charge: defect.charge,
+                    points: vec![(iteration, defect.position)],
+                    ended: None,
+                    created_with: None,
+                    annihilated_with: None,
+                });
+                self.tracks.len() - 1
+            })
+            .collect();
+        if self.started {
+            for (first, second) in self.pair(&created) {
+                self.tracks[first].created_with = Some(second);
+                self.tracks[second].created_with = Some(first);
+                self.push_event(iteration, EventKind::Creation, first, second);
+            }
+        }
+        self.alive.extend(created);
+        self.started = true;
+    }
+
+    /// Closest pairs of opposite charge among `tracks`, judged by their last positions.
+    fn pair(&self, tracks: &[usize]) -> Vec<(usize, usize)> {
+        let mut candidates = Vec::new();
+        for (i, &first) in tracks.iter().enumerate() {
+            for &second in &tracks[i + 1..] {
+                let (a, b) = (&self.tracks[first], &self.tracks[second]);
+                let distance = self.distance(a.last(), b.last());
+                if a.charge + b.charge == 0.0 && distance <= PAIR_DISTANCE {
+                    candidates.push((distance, first, second));
+                }
+            }
+        }
+        candidates.sort_by(|a, b| a.0.total_cmp(&b.0));
+        let mut paired: Vec<usize> = Vec::new();
+        let mut pairs = Vec::new();
+        for (_, first, second) in candidates {
+            if !paired.contains(&first) && !paired.contains(&second) {
+                paired.extend([first, second]);
+                pairs.push((first, second));
+            }
+        }
+        pairs
+    }
+
+    /// Distance between two positions, to the nearest image along periodic axes.
+    fn distance(&self, a: (f32, f32), b: (f32, f32)) -> f32 {
+        let (rows, cols) = self.dimensions;
+        let separation = |delta: f32, periodic: bool, period: usize| {
+            let delta = delta.abs();
+            if periodic { delta.min(period as f32 - delta) } else { delta }
+        };
+        separation(a.0 - b.0, self.periodic.0, cols).hypot(separation(a.1 - b.1, self.periodic.1, rows))
+    }
+
+    fn push_event(&mut self, iteration: usize, kind: EventKind, first: usize, second: usize) {
+        let (a, b) = (self.tracks[first].last(), self.tracks[second].last());
+        self.events.push(DefectEvent {
+            iteration,
+            kind,
+            tracks: (first, second),
+            position: (0.5 * (a.0 + b.0), 0.5 * (a.1 + b.1)),
+        });
+    }
+
+    pub fn observables(&self) -> Vec<(String, f64)> {
+        let annihilations = self.events.iter().filter(|e| e.kind == EventKind::Annihilation).count();
+        vec![
+            ("tracked_defects".to_owned(), self.alive.len() as f64),
+            ("annihilations".to_owned(), annihilations as f64),
+        ]
+    }
+
+    /// Trails of the last `length` iterations before `iteration` rasterised onto the lattice
+    /// cells, signed by charge and fading from 1 at the head to 0. Points further apart than
+    /// `MAX_JUMP`, e.g. on either side of a periodic edge, are not joined.
+    pub fn trails(&self, iteration: usize, length: usize, (rows, cols): (usize, usize)) -> Vec<f32> {
+        let mut cells = vec![0.0f32; rows * cols];
+        let cell = |(x, y): (f32, f32)| {
+            (
+                (x.floor() as i64).clamp(0, cols as i64 - 1),
+                (y.floor() as i64).clamp(0, rows as i64 - 1),
+            )
+        };
+        let live = |track: &&DefectTrack| track.ended.is_none_or(|ended| ended + length >= iteration);
+        for track in self.tracks.iter().filter(live) {
+            let first = track.points.partition_point(|point| point.0 + length < iteration);
+            let recent = &track.points[first..];
+            let mut previous: Option<(i64, i64)> = None;
+            for &(at, position) in recent {
+                let fade = 1.0 - (iteration.saturating_sub(at) as f32 / length.max(1) as f32).min(1.0);
+                let value = track.charge.signum() * fade.max(0.05);
+                let (x1, y1) = cell(position);
+                let (mut x, mut y) = previous
+                    .filter(|&(x, y)| (((x - x1).pow(2) + (y - y1).pow(2)) as f32).sqrt() <= MAX_JUMP)
+                    .unwrap_or((x1, y1));
+                // Bresenham from the previous point, both ends included.
+                let (dx, dy) = ((x1 - x).abs(), -(y1 - y).abs());
+                let (step_x, step_y) = ((x1 - x).signum(), (y1 - y).signum());
+                let mut error = dx + dy;
+                loop {
+                    let index = y as usize * cols + x as usize;
+                    if value.abs() > cells[index].abs() {
+                        cells[index] = value;
+                    }
+                    if x == x1 && y == y1 {
+                        break;
+                    }
+                    let doubled = 2 * error;
+                    if doubled >= dy {
+                        error += dy;
+                        x += step_x;
+                    }
+                    if doubled <= dx {
+                        error += dx;
+                        y += step_y;
+                    }
+                }
+                previous = Some((x1, y1));
+            }
+        }
+        cells
+    }
+
+    /// Writes `defect_tracks.csv` with one `id,charge,iteration,x,y` row per point,
+    /// `defect_events.csv` with the creations and annihilations, and `defect_tracks.json` with
+    /// both.
+    pub fn save(&self, directory: &Path) -> io::Result<()> {
+        let mut writer = BufWriter::new(File::create(directory.join("defect_tracks.csv"))?);
+        writeln!(writer, "id,charge,iteration,x,y")?;
+        for (id, track) in self.tracks.iter().enumerate() {
+            for (iteration, (x, y)) in &track.points {
+                writeln!(writer, "{id},{},{iteration},{x},{y}", track.charge)?;
+            }
+        }
+        writer.flush()?;
+
+        let mut writer = BufWriter::new(File::create(directory.join("defect_events.csv"))?);
+        writeln!(writer, "iteration,event,first,second,x,y,first_lifetime,second_lifetime")?;
+        for event in &self.events {
+            let lifetime = |track: usize| match event.kind {
+                EventKind::Creation => String::new(),
+                EventKind::Annihilation => (event.iteration - self.tracks[track].points[0].0).to_string(),
+            };
+            writeln!(
+                writer,
+                "{},{},{},{},{},{},{},{}",
+                event.iteration,
+                event_name(event.kind),
+                event.tracks.0,
+                event.tracks.1,
+                event.position.0,
+                event.position.1,
+                lifetime(event.tracks.0),
+                lifetime(event.tracks.1)
+            )?;
+        }
+        writer.flush()?;
+
+        let mut writer = BufWriter::new(File::create(directory.join("defect_tracks.json"))?);
+        writeln!(writer, "{{\n  \"tracks\": [")?;
+        for (id, track) in self.tracks.iter().enumerate() {
+            let points: Vec<String> = track
+                .points
+                .iter()
+                .map(|(iteration, (x, y))| format!("[{iteration}, {x}, {y}]"))
+                .collect();
+            let optional = |value: Option<usize>| value.map_or("null".to_owned(), |value| value.to_string());
+            let separator = if id + 1 < self.tracks.len() { "," } else { "" };
+            writeln!(
+                writer,
+                "    {{\"id\": {id}, \"charge\": {}, \"born\": {}, \"ended\": {}, \"created_with\": {}, \
+                 \"annihilated_with\": {}, \"points\": [{}]}}{separator}",
+                track.charge,
+                track.points[0].0,
+                optional(track.ended),
+                optional(track.created_with),
+                optional(track.annihilated_with),
+                points.join(", ")
+            )?;
+        }
+        writeln!(writer, "  ],\n  \"events\": [")?;
+        for (index, event) in self.events.iter().enumerate() {
+            let separator = if index + 1 < self.events.len() { "," } else { "" };
+            writeln!(
+                writer,
+                "    {{\"iteration\": {}, \"event\": \"{}\", \"tracks\": [{}, {}], \"position\": [{}, {}]}}{separator}",
+                event.iteration,
+                event_name(event.kind),
+                event.tracks.0,
+                event.tracks.1,
+                event.position.0,
+                event.position.1
+            )?;
+        }
+        writeln!(writer, "  ]\n}}")?;
+        writer.flush()
+    }
+}
+
+fn event_name(kind: EventKind) -> &'static str {
+    match kind {
+        EventKind::Creation => "creation",
+        EventKind::Annihilation => "annihilation",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::algorithm_processor::Boundary;
+
+    const DIMENSIONS: (usize, usize) = (20, 20);
+
+    fn defect(x: f32, y: f32, charge: f32) -> Defect {
+        Defect {
+            position: (x, y),
+            charge,
+        }
+    }
+
+    #[test]
+    fn links_pairs_and_crosses_periodic_edges() {
+        let mut tracker = DefectTracker::new(&Boundaries::default());
+        tracker.update(0, &[defect(5.0, 5.0, 0.5), defect(9.0, 5.0, -0.5), defect(18.5, 10.0, 0.5)], DIMENSIONS);
+        tracker.update(1, &[defect(6.0, 5.0, 0.5), defect(8.0, 5.0, -0.5), defect(0.5, 10.0, 0.5)], DIMENSIONS);
+        assert_eq!(tracker.tracks.len(), 3);
+        assert!(tracker.tracks.iter().all(|track| track.points.len() == 2));
+        assert_eq!(tracker.tracks[2].points[1], (1, (0.5, 10.0)));
+
+        tracker.update(2, &[defect(1.5, 10.0, 0.5)], DIMENSIONS);
+        assert_eq!(tracker.tracks.len(), 3);
+        assert_eq!((tracker.tracks[0].ended, tracker.tracks[1].ended), (Some(2), Some(2)));
+        assert_eq!(tracker.tracks[0].annihilated_with, Some(1));
+        assert_eq!(tracker.events.len(), 1);
+        let event = tracker.events[0];
+        assert_eq!((event.kind, event.tracks, event.position), (EventKind::Annihilation, (0, 1), (7.0, 5.0)));
+
+        tracker.update(3, &[defect(1.5, 10.0, 0.5), defect(10.0, 15.0, 0.5), defect(11.0, 15.0, -0.5)], DIMENSIONS);
+        assert_eq!(tracker.tracks.len(), 5);
+        assert_eq!(tracker.tracks[3].created_with, Some(4));
+        assert_eq!(tracker.events[1].kind, EventKind::Creation);
+    }
+
+    #[test]
+    fn free_edges_end_tracks() {
+        let mut boundaries = Boundaries::default();
+        (boundaries.left, boundaries.right) = (Boundary::Free, Boundary::Free);
+        let mut tracker = DefectTracker::new(&boundaries);
+        tracker.update(0, &[defect(18.5, 10.0, 0.5)], DIMENSIONS);
+        tracker.update(1, &[defect(0.5, 10.0, 0.5)], DIMENSIONS);
+        assert_eq!(tracker.tracks.len(), 2);
+        assert_eq!(tracker.tracks[0].ended, Some(1));
+    }
+}
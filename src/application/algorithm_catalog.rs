@@ -1,26 +1,151 @@
 use crate::algorithm_processor::algorithm::Algorithm;
 use crate::algorithm_processor::algorithm::*;
+use crate::algorithms::*;
+use crate::plugins::{PLUGIN_DIRECTORY, load_plugins};
+use std::path::Path;
+use std::sync::Arc;
 
+#[derive(Clone)]
 pub struct AlgorithmCatalog {
     pub algorithms: Vec<Algorithm>,
+    /// One line per plugin library that was loaded.
+    pub plugins_loaded: Vec<String>,
+    /// Why plugin libraries were skipped.
+    pub plugin_errors: Vec<String>,
 }
 
 impl AlgorithmCatalog {
     pub fn new() -> Self {
         Self {
-            algorithms: Vec::new(),
+            algorithms: vec![
+                Algorithm::new("Random rotation", Arc::new(|| Box::new(RandomRotation::default()))),
+                Algorithm::new("Lebwohl-Lasher", Arc::new(|| Box::new(LebwohlLasher::new()))),
+                Algorithm::new(
+                    "Landau-de Gennes (explicit)",
+                    Arc::new(|| Box::new(LandauDeGennes::new(TimeStepping::Explicit))),
+                ),
+                Algorithm::new(
+                    "Landau-de Gennes (semi-implicit)",
+                    Arc::new(|| Box::new(LandauDeGennes::new(TimeStepping::SemiImplicit))),
+                ),
+                Algorithm::new(
+                    "Ising (Metropolis)",
+                    Arc::new(|| Box::new(Ising::new(SpinUpdate::Metropolis))),
+                ),
+                Algorithm::new("Ising (Wolff)", Arc::new(|| Box::new(Ising::new(SpinUpdate::Wolff)))),
+                Algorithm::new("XY (Metropolis)", Arc::new(|| Box::new(XY::new(RotorUpdate::Metropolis)))),
+                Algorithm::new(
+                    "XY (over-relaxation)",
+                    Arc::new(|| Box::new(XY::new(RotorUpdate::OverRelaxation))),
+                ),
+                Algorithm::new(
+                    "Langevin rotors (Euler-Maruyama)",
+                    Arc::new(|| Box::new(LangevinRotors::new(Integrator::EulerMaruyama))),
+                ),
+                Algorithm::new(
+                    "Langevin rotors (Heun)",
+                    Arc::new(|| Box::new(LangevinRotors::new(Integrator::Heun))),
+                ),
+                Algorithm::new(
+                    "Lebwohl-Lasher (parallel tempering)",
+                    Arc::new(|| {
+                        Box::new(ParallelTempering::new(Arc::new(|| Box::new(LebwohlLasher::new())), (0.5, 1.5)))
+                    }),
+                ),
+                Algorithm::new(
+                    "Ising (parallel tempering)",
+                    Arc::new(|| {
+                        Box::new(ParallelTempering::new(
+                            Arc::new(|| Box::new(Ising::new(SpinUpdate::Metropolis))),
+                            (1.5, 3.5),
+                        ))
+                    }),
+                ),
+                Algorithm::new(
+                    "XY (parallel tempering)",
+                    Arc::new(|| {
+                        Box::new(ParallelTempering::new(
+                            Arc::new(|| Box::new(XY::new(RotorUpdate::Metropolis))),
+                            (0.5, 1.5),
+                        ))
+                    }),
+                ),
+                Algorithm::new(
+                    "Ising (Wang-Landau)",
+                    Arc::new(|| Box::new(WangLandau::new(WangLandauModel::Ising))),
+                ),
+                Algorithm::new("XY (Wang-Landau)", Arc::new(|| Box::new(WangLandau::new(WangLandauModel::XY)))),
+                Algorithm::new(
+                    "Lebwohl-Lasher (Wang-Landau)",
+                    Arc::new(|| Box::new(WangLandau::new(WangLandauModel::LebwohlLasher))),
+                ),
+                Algorithm::new("Active nematic", Arc::new(|| Box::new(ActiveNematic::new()))),
+                Algorithm::new("Gay-Berne MD", Arc::new(|| Box::new(GayBerne::new(3.0, 5.0)))),
+                Algorithm::new("Lennard-Jones MD", Arc::new(|| Box::new(GayBerne::new(1.0, 1.0)))),
+                Algorithm::new("Gray-Scott", Arc::new(|| Box::new(GrayScott::new()))),
+                Algorithm::new(
+                    "Heat equation (explicit)",
+                    Arc::new(|| Box::new(HeatEquation::new(HeatScheme::Explicit))),
+                ),
+                Algorithm::new(
+                    "Heat equation (Crank-Nicolson)",
+                    Arc::new(|| Box::new(HeatEquation::new(HeatScheme::CrankNicolson))),
+                ),
+            ],
+            plugins_loaded: Vec::new(),
+            plugin_errors: Vec::new(),
         }
     }
+
+    /// The built-in algorithms followed by those of the plugins in `PLUGIN_DIRECTORY`.
+    pub fn with_plugins() -> Self {
+        let mut catalog = Self::new();
+        catalog.add_plugins(Path::new(PLUGIN_DIRECTORY));
+        catalog
+    }
+
+    /// Appends the algorithms of the plugin libraries in `directory`, skipping taken names.
+    pub fn add_plugins(&mut self, directory: &Path) {
+        for plugin in load_plugins(directory) {
+            match plugin {
+                Ok((plugin, _)) if self.find(&plugin.name).is_some() => self
+                    .plugin_errors
+                    .push(format!("Skipping plugin {}: an algorithm with that name exists", plugin.name)),
+                Ok((plugin, description)) => {
+                    self.algorithms.push(plugin);
+                    self.plugins_loaded.push(description);
+                }
+                Err(error) => self.plugin_errors.push(error),
+            }
+        }
+    }
+
+    /// Every algorithm with the parameters it starts with.
+    pub fn defaults(&self) -> Vec<(String, Vec<Parameter>)> {
+        self.algorithms
+            .iter()
+            .map(|algorithm| (algorithm.name.clone(), algorithm.instantiate().parameters()))
+            .collect()
+    }
+
+    pub fn find(&self, name: &str) -> Option<&Algorithm> {
+        self.algorithms.iter().find(|a| a.name == name)
+    }
 }
 
-struct RandomRotation {}
+/// Spins every arrow at a speed proportional to its initial angle.
+#[derive(Default)]
+struct RandomRotation {
+    initial: Option<Vec<f32>>,
+}
 
 impl AlgorithmComputation for RandomRotation {
-    fn compute(&mut self, iteration: usize, data: &Vec<DataType>) -> Vec<DataType> {
-        let (data, latice_dimentions) = match data[0] {
-            DataType::OrientedGrid(ref data, latice_dimentions) => (data, latice_dimentions)
+    fn compute(&mut self, iteration: usize, data: &[DataType]) -> Vec<DataType> {
+        let Some((data, latice_dimentions)) = DataType::oriented(data) else {
+            return Vec::new();
         };
-        vec![DataType::OrientedGrid(data
+        let initial = self.initial.get_or_insert_with(|| data.clone());
+        vec![DataType::OrientedGrid(initial
                                         .iter()
                                         .map(|e| e * (iteration as f32) * std::f32::consts::PI / 180.0)
                                         .collect(), latice_dimentions)]
@@ -2,7 +2,6 @@ use crate::algorithm_processor::*;
 use crate::application::wininit_wrapper::WininitWrapper;
 use crate::application::{CustomEventProxy, SharedContext, AlgorithmCatalog};
 use crate::gui::controls::Controls;
-use crate::rendering::ImageWriter;
 use crate::rendering::renderers::BackgroundRenderer;
 use crate::rendering::wgpu_wrapper::WGPUWrapper;
 use crate::rendering::*;
@@ -14,7 +13,6 @@ pub struct Components {
     pub algorithm_processor: AlgorithmProcessor,
     pub background_renderer: BackgroundRenderer,
     pub state: program::State<Controls>,
-    pub image_writer: ImageWriter,
     pub event_proxy: CustomEventProxy,
     pub algorithm_catalog: AlgorithmCatalog,
     pub debug: Debug,
@@ -31,10 +29,13 @@ impl Components {
         let (data_handle, algorithm_processor) = AlgorithmProcessor::new(shared_context.clone());
         let background_renderer =
             BackgroundRenderer::new(&wgpu, &win.viewport, data_handle, shared_context.clone());
+        let algorithm_catalog = AlgorithmCatalog::with_plugins();
         let mut debug = Debug::new();
         let state = program::State::new(
             Controls::new(
                 background_renderer.get_texture_handle(),
+                algorithm_catalog.defaults(),
+                algorithm_catalog.plugin_errors.clone(),
                 event_proxy.clone(),
             ),
             win.viewport.logical_size(),
@@ -42,10 +43,6 @@ impl Components {
             &mut debug,
         );
 
-        let image_writer = ImageWriter::new(&wgpu, 5);
-
-        let algorithm_catalog = AlgorithmCatalog::new();
-
         Self {
             win,
             wgpu,
@@ -53,7 +50,6 @@ impl Components {
             algorithm_processor,
             background_renderer,
             state,
-            image_writer,
             event_proxy,
             algorithm_catalog,
             debug,
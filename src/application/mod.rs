@@ -4,9 +4,18 @@ mod wininit_wrapper;
 mod algorithm_catalog;
 
 use self::components::Components;
+use crate::analysis::{Analyses, ElasticMap, SpectrumField};
+use crate::batch::{self, BatchConfig};
 use crate::rendering::*;
 pub use shared_context::*;
 pub use algorithm_catalog::AlgorithmCatalog;
+use crate::algorithm_processor::{
+    InitialState, LIVE_SAMPLE_INTERVAL, LatticeConditions, Observables, Overlays, Parameter, Protocol, RunConfig,
+    RunRecord, Table,
+};
+use std::path::PathBuf;
+use std::thread;
+use std::time::{SystemTime, UNIX_EPOCH};
 use winit::event_loop::EventLoopProxy;
 
 use crate::gui::controls::Message;
@@ -15,8 +24,51 @@ use winit::event::WindowEvent;
 #[derive(Debug)]
 pub enum CustomEvent {
     RequestRedraw,
-    StartStop(bool, usize),
+    StartStop(bool, RunSettings),
     UpdateSharedData,
+    RunBatch(BatchConfig),
+    BatchStatus(String, bool),
+    UpdateConditions(LatticeConditions),
+    UpdateView(SliceView),
+    UpdateColormap(Colormap),
+    UpdateParticleStyle(ParticleStyle),
+    UpdateVectorOverlay(VectorOverlay),
+    UpdateLayers(LayerStack),
+    /// Director angle to draw pitch lines at, `None` to hide them.
+    UpdatePitchLines(Option<f32>),
+    UpdateElasticMap(ElasticMap),
+    /// Shows or hides the trails of the defects.
+    UpdateTrails(bool),
+    /// Field whose power spectrum replaces the lattice on the display, and whose S(k) is plotted.
+    UpdateSpectrum(SpectrumField),
+    /// The latest observables of the running algorithm, for the status line.
+    UpdateStatus(Observables),
+    /// Curves of the running algorithm for the plots.
+    UpdateTables(Vec<Table>),
+    /// What the running algorithm works around in its current settings, see
+    /// `AlgorithmComputation::warnings`.
+    UpdateWarnings(Vec<String>),
+    /// Changes a parameter of the running algorithm, e.g. the replica shown by parallel tempering.
+    SetParameter(String, f32),
+    /// A click on the display at a fractional position, with a radius in cells.
+    Perturb((f32, f32), f32),
+}
+
+/// What the controls ask the interactive worker to run.
+#[derive(Debug, Clone)]
+pub struct RunSettings {
+    pub algorithm: String,
+    pub lattice_size: usize,
+    /// Layers along z, 1 for a 2D lattice.
+    pub lattice_layers: usize,
+    pub initial_state: InitialState,
+    pub conditions: LatticeConditions,
+    pub protocols: Vec<Protocol>,
+    /// Values from the parameter panel, the rest keep their defaults.
+    pub parameters: Vec<Parameter>,
+    pub analyses: Analyses,
+    pub overlays: Overlays,
+    pub output_directory: Option<PathBuf>,
 }
 
 pub type CustomEventProxy = EventLoopProxy<CustomEvent>;
@@ -41,8 +93,8 @@ impl Simula {
 
         components.wgpu.renderer.present(
             &mut components.wgpu.engine,
-            &mut components.wgpu.device,
-            &mut components.wgpu.queue,
+            &components.wgpu.device,
+            &components.wgpu.queue,
             &mut encoder,
             None,
             frame.texture.format(),
@@ -67,6 +119,29 @@ impl Simula {
             ));
     }
 
+    /// Aggregates the observables of a stopped run, saves them next to the other outputs
+    /// and shows the result in the controls.
+    fn finish_run(components: &mut Components, record: RunRecord, output_directory: Option<PathBuf>) {
+        let statistics = match output_directory {
+            Some(output_directory) => {
+                let seconds = SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .map_or(0, |d| d.as_secs());
+                let directory = output_directory.join(format!("run_{seconds}"));
+                match record.save(&directory) {
+                    Ok(statistics) => format!("Saved to {}\n{}", directory.display(), statistics.format()),
+                    Err(error) => format!("Unable to save run: {error}\n{}", record.statistics().format()),
+                }
+            }
+            None => record.statistics().format(),
+        };
+
+        components
+            .state
+            .queue_message(Message::RunStatistics(statistics));
+        components.win.window.request_redraw();
+    }
+
     fn handle_redraw_event(components: &mut Components) {
         if components.win.resized {
             let size = components.win.window.inner_size();
@@ -194,20 +269,47 @@ impl winit::application::ApplicationHandler<CustomEvent> for Simula {
                 components.background_renderer.render(&mut components.wgpu);
                 components.win.window.request_redraw();
             }
-            CustomEvent::StartStop(value, dimention) => {
+            CustomEvent::StartStop(value, settings) => {
                 if value {
-                    components.algorithm_processor.shutdown();
+                    if let Some(record) = components.algorithm_processor.shutdown() {
+                        Simula::finish_run(components, record, settings.output_directory);
+                    }
                 } else {
+                    let Some(algorithm) = components.algorithm_catalog.find(&settings.algorithm).cloned()
+                    else {
+                        return;
+                    };
+                    let dimention = settings.lattice_size;
+                    let config = RunConfig {
+                        algorithm: algorithm.name.clone(),
+                        lattice_dimension: (dimention, dimention),
+                        layers: settings.lattice_layers,
+                        seed: rand::random(),
+                        initial_state: settings.initial_state,
+                        conditions: settings.conditions,
+                        protocols: settings.protocols,
+                        parameters: settings.parameters,
+                        analyses: settings.analyses,
+                        sample_interval: LIVE_SAMPLE_INTERVAL,
+                        ..Default::default()
+                    };
+                    if let Err(error) = config.check(&algorithm) {
+                        components.state.queue_message(Message::RunStatus(error));
+                        components.win.window.request_redraw();
+                        return;
+                    }
                     {
-                        let mut ctx = components.shared_context.lock();
-                        ctx.general_params.write().unwrap().lattice_dimension = (dimention, dimention);
+                        let ctx = components.shared_context.lock();
+                        let mut params = ctx.general_params.write().unwrap();
+                        params.lattice_dimension = (dimention, dimention);
+                        params.lattice_layers = settings.lattice_layers;
                     }
                     components
                         .background_renderer
-                        .resize_latice(&mut components.wgpu, (dimention, dimention));
+                        .resize_latice(&components.wgpu, (dimention, dimention));
                     components
                         .algorithm_processor
-                        .start(components.shared_context.clone());
+                        .start(components.shared_context.clone(), algorithm, config, settings.overlays);
                 }
             }
             CustomEvent::UpdateSharedData => {
@@ -215,6 +317,113 @@ impl winit::application::ApplicationHandler<CustomEvent> for Simula {
                     .state
                     .queue_message(Message::UpdateSharedData(components.shared_context.clone()));
             }
+            CustomEvent::RunBatch(config) => {
+                let catalog = components.algorithm_catalog.clone();
+                let event_proxy = components.event_proxy.clone();
+                thread::spawn(move || {
+                    let warnings: String = batch::batch_warnings(&config, &catalog)
+                        .iter()
+                        .map(|warning| format!("Warning: {warning}\n"))
+                        .collect();
+                    let result = batch::run_batch(&config, &catalog, |finished, total| {
+                        let _ = event_proxy.send_event(CustomEvent::BatchStatus(
+                            format!("{warnings}{finished}/{total} jobs finished"),
+                            false,
+                        ));
+                    });
+                    let status = match result {
+                        Ok(report) => {
+                            let failures: String = report
+                                .failures
+                                .iter()
+                                .map(|failure| format!("Job failed: {failure}\n"))
+                                .collect();
+                            format!("{warnings}{failures}{}", batch::format_summary(&report.summary))
+                        }
+                        Err(error) => format!("{warnings}Batch failed: {error}"),
+                    };
+                    let _ = event_proxy.send_event(CustomEvent::BatchStatus(status, true));
+                });
+            }
+            CustomEvent::BatchStatus(status, finished) => {
+                components
+                    .state
+                    .queue_message(Message::BatchStatus(status, finished));
+                components.win.window.request_redraw();
+            }
+            CustomEvent::UpdateView(view) => {
+                components.background_renderer.set_view(&components.wgpu, view);
+                components.background_renderer.render(&mut components.wgpu);
+                components.win.window.request_redraw();
+            }
+            CustomEvent::UpdateColormap(colormap) => {
+                components.background_renderer.set_colormap(&components.wgpu, colormap);
+                components.background_renderer.render(&mut components.wgpu);
+                components.win.window.request_redraw();
+            }
+            CustomEvent::UpdateParticleStyle(style) => {
+                components.background_renderer.set_particle_style(&components.wgpu, style);
+                components.background_renderer.render(&mut components.wgpu);
+                components.win.window.request_redraw();
+            }
+            CustomEvent::UpdateVectorOverlay(overlay) => {
+                components.background_renderer.set_vector_overlay(&components.wgpu, overlay);
+                components.background_renderer.render(&mut components.wgpu);
+                components.win.window.request_redraw();
+            }
+            CustomEvent::UpdateLayers(layers) => {
+                components.background_renderer.set_layers(layers);
+                components.background_renderer.render(&mut components.wgpu);
+                components.win.window.request_redraw();
+            }
+            CustomEvent::UpdateStatus(sample) => {
+                components.state.queue_message(Message::StatusUpdated(sample));
+            }
+            CustomEvent::UpdateTables(tables) => {
+                components.state.queue_message(Message::TablesUpdated(tables));
+                components.win.window.request_redraw();
+            }
+            CustomEvent::UpdateWarnings(warnings) => {
+                components.state.queue_message(Message::WarningsUpdated(warnings));
+                components.win.window.request_redraw();
+            }
+            CustomEvent::UpdatePitchLines(reference) => {
+                components.background_renderer.set_pitch_lines(reference);
+                components.background_renderer.render(&mut components.wgpu);
+                components.win.window.request_redraw();
+            }
+            CustomEvent::UpdateElasticMap(map) => {
+                components.algorithm_processor.set_elastic_map(map);
+            }
+            CustomEvent::UpdateTrails(enabled) => {
+                components.algorithm_processor.set_trails(enabled);
+                components.background_renderer.set_trails(enabled);
+                components.background_renderer.render(&mut components.wgpu);
+                components.win.window.request_redraw();
+            }
+            CustomEvent::UpdateSpectrum(field) => {
+                components.algorithm_processor.set_spectrum(field);
+                components.background_renderer.set_spectrum(field);
+                components.background_renderer.render(&mut components.wgpu);
+                components.win.window.request_redraw();
+            }
+            CustomEvent::SetParameter(name, value) => {
+                components.algorithm_processor.set_parameter(name, value);
+            }
+            CustomEvent::Perturb(position, radius) => {
+                components.algorithm_processor.perturb(position, radius);
+            }
+            CustomEvent::UpdateConditions(conditions) => {
+                components
+                    .background_renderer
+                    .set_mask(&components.wgpu, conditions.mask.clone());
+                components
+                    .background_renderer
+                    .set_sources(&components.wgpu, conditions.sources.as_ref());
+                components.algorithm_processor.update_conditions(conditions);
+                components.background_renderer.render(&mut components.wgpu);
+                components.win.window.request_redraw();
+            }
         }
     }
 }
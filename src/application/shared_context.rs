@@ -4,6 +4,8 @@ use std::sync::RwLock;
 #[derive(Debug)]
 pub struct GeneralParams {
     pub lattice_dimension: (usize, usize),
+    /// Layers along z, 1 for a 2D lattice.
+    pub lattice_layers: usize,
     pub algorithm_started: bool,
 }
 
@@ -19,7 +21,7 @@ pub(super) mod internal {
     impl SharedContext {}
 }
 
-use std::sync::{Arc, Mutex};
+use std::sync::Arc;
 
 #[derive(Clone, Debug)]
 pub struct SharedContext(Arc<internal::SharedContext>);
@@ -30,6 +32,7 @@ impl SharedContext {
             event_proxy,
             general_params: RwLock::new(GeneralParams {
                 lattice_dimension: dimensions,
+                lattice_layers: 1,
                 algorithm_started: false,
             }),
 
@@ -16,7 +16,7 @@ pub struct WininitWrapper {
 impl WininitWrapper {
     pub fn new(event_loop: &winit::event_loop::ActiveEventLoop) -> Self {
         let window = Self::create_window(event_loop);
-        let viewport = Self::get_viewport(&*window);
+        let viewport = Self::get_viewport(&window);
         let clipboard = Clipboard::connect(window.clone());
         event_loop.set_control_flow(ControlFlow::Wait);
         Self {
@@ -35,13 +35,11 @@ impl WininitWrapper {
         let mut window_attributes = winit::window::WindowAttributes::default();
         window_attributes.title = "Simula".to_owned();
         window_attributes.maximized = true;
-        let window = Arc::new(
+        Arc::new(
             event_loop
                 .create_window(window_attributes)
                 .expect("Create window"),
-        );
-
-        window
+        )
     }
 
     fn get_viewport(window: &winit::window::Window) -> Viewport {
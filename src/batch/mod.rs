@@ -0,0 +1,193 @@
+mod runner;
+mod sweep;
+
+use crate::algorithm_processor::{Boundary, InitialState, Mask, Protocol, RunConfig, Schedule};
+use std::path::{Path, PathBuf};
+use std::thread;
+
+pub use runner::*;
+pub use sweep::*;
+
+const SWEEPABLE_FIELD_KEYS: [&str; 2] = ["field.angle", "field.magnitude"];
+
+/// A base run configuration expanded over one or more sweep axes.
+///
+/// The text form is one `key = value` per line, `#` starts a comment:
+///
+/// ```text
+/// algorithm = Lebwohl-Lasher
+/// lattice = 64
+/// iterations = 4000
+/// sample_interval = 10
+/// output = runs/freedericksz
+/// initial_state = aligned 0
+/// boundary.y = fixed 0
+/// field.waveform = step 500
+/// protocol.temperature = exponential 2.0 0.1 1000
+/// analysis.correlation = 1
+/// sweep.field.magnitude = lin 0 1 20
+/// sweep.seed = list 1 2 3
+/// ```
+///
+/// Run settings come first, then the lattice conditions, protocols and analyses, and the sweep axes
+/// last. Unrecognised keys are passed to the algorithm as parameters, e.g. `temperature = 0.5`.
+#[derive(Debug, Clone)]
+pub struct BatchConfig {
+    pub base: RunConfig,
+    pub axes: Vec<SweepAxis>,
+    pub output: PathBuf,
+    pub threads: usize,
+}
+
+#[derive(Debug, Clone)]
+pub struct Job {
+    pub index: usize,
+    pub point: ParameterPoint,
+    pub config: RunConfig,
+}
+
+impl Job {
+    pub fn directory_name(&self) -> String {
+        let mut name = format!("{:04}", self.index);
+        for (parameter, value) in &self.point {
+            name.push_str(&format!("_{parameter}-{value}"));
+        }
+        name
+    }
+}
+
+impl BatchConfig {
+    pub fn new(base: RunConfig, output: PathBuf) -> Self {
+        Self {
+            base,
+            axes: Vec::new(),
+            output,
+            threads: thread::available_parallelism().map_or(1, |n| n.get()),
+        }
+    }
+
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let text = std::fs::read_to_string(path)
+            .map_err(|e| format!("Unable to read {}: {e}", path.display()))?;
+        Self::parse(&text)
+    }
+
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut config = Self::new(RunConfig::default(), PathBuf::from("."));
+
+        for (number, line) in text.lines().enumerate() {
+            let line = line.split('#').next().unwrap_or_default().trim();
+            if line.is_empty() {
+                continue;
+            }
+            let (key, value) = line
+                .split_once('=')
+                .map(|(k, v)| (k.trim(), v.trim()))
+                .ok_or_else(|| format!("Line {}: expected 'key = value'", number + 1))?;
+            config
+                .apply(key, value)
+                .map_err(|e| format!("Line {}: {e}", number + 1))?;
+        }
+
+        if config.base.algorithm.is_empty() {
+            return Err("No algorithm selected".to_owned());
+        }
+        Ok(config)
+    }
+
+    pub fn apply(&mut self, key: &str, value: &str) -> Result<(), String> {
+        let invalid = || format!("Invalid value '{value}' for '{key}'");
+        match key {
+            "algorithm" => self.base.algorithm = value.to_owned(),
+            "lattice" => {
+                let (rows, cols, layers) = parse_lattice(value).ok_or_else(invalid)?;
+                self.base.lattice_dimension = (rows, cols);
+                self.base.layers = layers;
+            }
+            "iterations" => self.base.iterations = value.parse().map_err(|_| invalid())?,
+            "sample_interval" => self.base.sample_interval = value.parse().map_err(|_| invalid())?,
+            "seed" => self.base.seed = value.parse().map_err(|_| invalid())?,
+            "initial_state" => self.base.initial_state = InitialState::parse(value)?,
+            "mask" => self.base.conditions.mask = Some(Mask::load(Path::new(value))?),
+            "threads" => self.threads = value.parse::<usize>().map_err(|_| invalid())?.max(1),
+            "output" => self.output = PathBuf::from(value),
+            _ => {
+                if let Some(name) = key.strip_prefix("sweep.") {
+                    if name.starts_with("field.") && !SWEEPABLE_FIELD_KEYS.contains(&name) {
+                        return Err(format!("Only {} can be swept", SWEEPABLE_FIELD_KEYS.join(" and ")));
+                    }
+                    self.axes.push(SweepAxis::parse(name, value)?);
+                } else if let Some(parameter) = key.strip_prefix("protocol.") {
+                    let schedule = Schedule::parse(value)?;
+                    let protocols = &mut self.base.protocols;
+                    protocols.retain(|protocol| protocol.parameter != parameter);
+                    protocols.push(Protocol {
+                        parameter: parameter.to_owned(),
+                        schedule,
+                    });
+                } else if let Some(name) = key.strip_prefix("analysis.") {
+                    self.base.analyses.apply(name, value)?;
+                } else if let Some(setting) = key.strip_prefix("field.") {
+                    self.base.conditions.field.apply(setting, value)?;
+                } else if let Some(edges) = key
+                    .strip_prefix("boundary")
+                    .filter(|edges| edges.is_empty() || edges.starts_with('.'))
+                {
+                    let boundary = Boundary::parse(value)?;
+                    let boundaries = &mut self.base.conditions.boundaries;
+                    let edges: &[&str] = match edges {
+                        "" => &["left", "right", "bottom", "top", "front", "back"],
+                        ".x" => &["left", "right"],
+                        ".y" => &["bottom", "top"],
+                        ".z" => &["front", "back"],
+                        edge => &[edge.trim_start_matches('.')],
+                    };
+                    for edge in edges {
+                        *boundaries.edge_mut(edge).ok_or_else(invalid)? = boundary;
+                    }
+                } else {
+                    self.base
+                        .set_parameter(key, value.parse().map_err(|_| invalid())?);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    pub fn jobs(&self) -> Vec<Job> {
+        parameter_points(&self.axes)
+            .into_iter()
+            .enumerate()
+            .map(|(index, point)| {
+                let mut config = self.base.clone();
+                for (name, value) in &point {
+                    match name.as_str() {
+                        "seed" => config.seed = *value as u64,
+                        "field.angle" => config.conditions.field.angle = *value as f32,
+                        "field.magnitude" => config.conditions.field.magnitude = *value as f32,
+                        _ => config.set_parameter(name, *value as f32),
+                    }
+                }
+                Job {
+                    index,
+                    point,
+                    config,
+                }
+            })
+            .collect()
+    }
+}
+
+/// `N`, `RxC` or `RxCxL` for rows, columns and layers.
+fn parse_lattice(value: &str) -> Option<(usize, usize, usize)> {
+    let sizes = value
+        .split('x')
+        .map(|size| size.trim().parse::<usize>().ok().filter(|&size| size > 0))
+        .collect::<Option<Vec<usize>>>()?;
+    match *sizes.as_slice() {
+        [size] => Some((size, size, 1)),
+        [rows, cols] => Some((rows, cols, 1)),
+        [rows, cols, layers] => Some((rows, cols, layers)),
+        _ => None,
+    }
+}
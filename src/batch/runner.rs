@@ -0,0 +1,256 @@
+use super::*;
+use crate::algorithm_processor::*;
+use crate::analysis::{mean, variance};
+use crate::application::AlgorithmCatalog;
+use crossbeam::channel::unbounded;
+use std::fs::{self, File};
+use std::io::{BufWriter, Write};
+
+/// Equilibrium averages of one job.
+#[derive(Debug, Clone)]
+pub struct JobResult {
+    pub job: Job,
+    /// `(name, mean, standard error)` from the run's time series.
+    pub observables: Vec<(String, f64, f64)>,
+}
+
+/// Observables at one parameter point, averaged over all seeds run there.
+#[derive(Debug, Clone)]
+pub struct SummaryRow {
+    pub point: ParameterPoint,
+    pub runs: usize,
+    /// `(name, mean, standard error)`, see `error_kind` for what the error measures.
+    pub observables: Vec<(String, f64, f64)>,
+}
+
+impl SummaryRow {
+    /// `within_run` for a single run, whose error comes from its own time series, and
+    /// `between_seeds` for the standard error of the mean over several seeds.
+    pub fn error_kind(&self) -> &'static str {
+        if self.runs > 1 { "between_seeds" } else { "within_run" }
+    }
+}
+
+/// Outcome of a batch: the summary of the jobs that finished and the errors of those that failed.
+#[derive(Debug, Clone)]
+pub struct BatchReport {
+    pub summary: Vec<SummaryRow>,
+    pub failures: Vec<String>,
+}
+
+/// Runs every job of `config` in parallel and writes `summary.csv` into the output directory.
+/// A failed job does not stop the others; its error goes to `failed_jobs.txt` and the report.
+///
+/// `progress` is called with `(finished, total)` after each job completes.
+pub fn run_batch(
+    config: &BatchConfig,
+    catalog: &AlgorithmCatalog,
+    progress: impl Fn(usize, usize) + Sync,
+) -> Result<BatchReport, String> {
+    let algorithm = catalog
+        .find(&config.base.algorithm)
+        .ok_or_else(|| format!("Unknown algorithm '{}'", config.base.algorithm))?;
+    config.base.check(algorithm)?;
+    fs::create_dir_all(&config.output)
+        .map_err(|e| format!("Unable to create {}: {e}", config.output.display()))?;
+
+    let jobs = config.jobs();
+    let total = jobs.len();
+    let (job_sender, job_receiver) = unbounded::<Job>();
+    let (result_sender, result_receiver) = unbounded::<Result<JobResult, String>>();
+    for job in jobs {
+        job_sender.send(job).expect("Job queue is already closed");
+    }
+    drop(job_sender);
+
+    let mut results = Vec::with_capacity(total);
+    thread::scope(|scope| {
+        for _ in 0..config.threads.min(total).max(1) {
+            let job_receiver = job_receiver.clone();
+            let result_sender = result_sender.clone();
+            scope.spawn(move || {
+                for job in job_receiver.iter() {
+                    let result = run_job(algorithm, &config.output, job);
+                    result_sender.send(result).expect("Result channel is already closed");
+                }
+            });
+        }
+        drop(result_sender);
+
+        for result in result_receiver.iter() {
+            results.push(result);
+            progress(results.len(), total);
+        }
+    });
+
+    let (mut finished, mut failures) = (Vec::new(), Vec::new());
+    for result in results {
+        match result {
+            Ok(result) => finished.push(result),
+            Err(error) => failures.push(error),
+        }
+    }
+    finished.sort_by_key(|result| result.job.index);
+    failures.sort();
+
+    let summary = summarize(&finished);
+    write_summary(&config.output.join("summary.csv"), &summary)
+        .map_err(|e| format!("Unable to write summary: {e}"))?;
+    let failed_path = config.output.join("failed_jobs.txt");
+    if failures.is_empty() {
+        // A rerun into the same directory must not keep the failures of an earlier one.
+        let _ = fs::remove_file(&failed_path);
+    } else {
+        fs::write(&failed_path, failures.join("\n") + "\n")
+            .map_err(|e| format!("Unable to write {}: {e}", failed_path.display()))?;
+    }
+    Ok(BatchReport { summary, failures })
+}
+
+/// Distinct warnings of every job of `config`, each with the number of jobs it applies to.
+pub fn batch_warnings(config: &BatchConfig, catalog: &AlgorithmCatalog) -> Vec<String> {
+    let Some(algorithm) = catalog.find(&config.base.algorithm) else {
+        return Vec::new();
+    };
+    let jobs = config.jobs();
+    let mut warnings: Vec<(String, usize)> = Vec::new();
+    for job in &jobs {
+        for warning in job.config.warnings(algorithm) {
+            match warnings.iter_mut().find(|(text, _)| *text == warning) {
+                Some((_, count)) => *count += 1,
+                None => warnings.push((warning, 1)),
+            }
+        }
+    }
+    warnings
+        .into_iter()
+        .map(|(warning, count)| format!("{warning} ({count} of {} jobs)", jobs.len()))
+        .collect()
+}
+
+fn run_job(algorithm: &Algorithm, output: &Path, job: Job) -> Result<JobResult, String> {
+    let directory = output.join(job.directory_name());
+    let statistics = run_headless(algorithm, &job.config)
+        .save(&directory)
+        .map_err(|e| format!("{}: {e}", directory.display()))?;
+
+    Ok(JobResult {
+        observables: statistics.quantities(),
+        job,
+    })
+}
+
+/// Groups results by parameter point, ignoring the seed, and averages across seeds.
+/// A point with a single run keeps that run's own error estimate.
+pub fn summarize(results: &[JobResult]) -> Vec<SummaryRow> {
+    let mut groups: Vec<(ParameterPoint, Vec<&JobResult>)> = Vec::new();
+    for result in results {
+        let point: ParameterPoint = result
+            .job
+            .point
+            .iter()
+            .filter(|(name, _)| name != "seed")
+            .cloned()
+            .collect();
+        match groups.iter_mut().find(|(p, _)| *p == point) {
+            Some((_, members)) => members.push(result),
+            None => groups.push((point, vec![result])),
+        }
+    }
+
+    groups
+        .into_iter()
+        .map(|(point, members)| {
+            let observables = match members.as_slice() {
+                [single] => single.observables.clone(),
+                _ => members[0]
+                    .observables
+                    .iter()
+                    .map(|(name, _, _)| {
+                        let values: Vec<f64> = members
+                            .iter()
+                            .filter_map(|m| {
+                                m.observables.iter().find(|(n, _, _)| n == name).map(|(_, v, _)| *v)
+                            })
+                            .collect();
+                        let error = (variance(&values) / values.len() as f64).sqrt();
+                        (name.clone(), mean(&values), error)
+                    })
+                    .collect(),
+            };
+            SummaryRow {
+                point,
+                runs: members.len(),
+                observables,
+            }
+        })
+        .collect()
+}
+
+fn summary_header(summary: &[SummaryRow]) -> Vec<String> {
+    let Some(row) = summary.first() else {
+        return Vec::new();
+    };
+    let mut header: Vec<String> = row.point.iter().map(|(name, _)| name.clone()).collect();
+    header.push("runs".to_owned());
+    header.push("error_kind".to_owned());
+    for (name, _, _) in &row.observables {
+        header.push(name.clone());
+        header.push(format!("{name}_error"));
+    }
+    header
+}
+
+fn summary_cells(row: &SummaryRow) -> Vec<String> {
+    let mut cells: Vec<String> = row.point.iter().map(|(_, value)| value.to_string()).collect();
+    cells.push(row.runs.to_string());
+    cells.push(row.error_kind().to_owned());
+    for (_, mean, error) in &row.observables {
+        cells.push(mean.to_string());
+        cells.push(error.to_string());
+    }
+    cells
+}
+
+fn write_summary(path: &Path, summary: &[SummaryRow]) -> std::io::Result<()> {
+    let mut writer = BufWriter::new(File::create(path)?);
+    writeln!(writer, "{}", summary_header(summary).join(","))?;
+    for row in summary {
+        writeln!(writer, "{}", summary_cells(row).join(","))?;
+    }
+    writer.flush()
+}
+
+/// Fixed width text rendering of the summary for the terminal and the GUI.
+pub fn format_summary(summary: &[SummaryRow]) -> String {
+    let header = summary_header(summary);
+    let rows: Vec<Vec<String>> = summary
+        .iter()
+        .map(|row| {
+            let mut values: Vec<String> = row.point.iter().map(|(_, v)| format!("{v:.6}")).collect();
+            values.push(row.runs.to_string());
+            values.push(row.error_kind().to_owned());
+            for (_, mean, error) in &row.observables {
+                values.push(format!("{mean:.6}"));
+                values.push(format!("{error:.6}"));
+            }
+            values
+        })
+        .collect();
+    let widths: Vec<usize> = header
+        .iter()
+        .enumerate()
+        .map(|(index, column)| {
+            let widest = rows.iter().filter_map(|values| values.get(index)).map(String::len).max();
+            column.len().max(widest.unwrap_or(0)).max(10) + 2
+        })
+        .collect();
+    let mut text = String::new();
+    for values in std::iter::once(&header).chain(&rows) {
+        for (value, width) in values.iter().zip(&widths) {
+            text.push_str(&format!("{value:>width$}"));
+        }
+        text.push('\n');
+    }
+    text
+}
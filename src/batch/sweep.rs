@@ -0,0 +1,119 @@
+/// One swept parameter and the values it takes.
+#[derive(Debug, Clone)]
+pub struct SweepAxis {
+    pub name: String,
+    pub values: Vec<f64>,
+}
+
+impl SweepAxis {
+    /// Parses `lin <start> <end> <steps>`, `log <start> <end> <steps>` or `list <v1> <v2> ...`.
+    pub fn parse(name: &str, spec: &str) -> Result<Self, String> {
+        let mut tokens = spec.split(|c: char| c.is_whitespace() || c == ',').filter(|t| !t.is_empty());
+        let kind = tokens
+            .next()
+            .ok_or_else(|| format!("Empty sweep specification for '{name}'"))?;
+        let numbers = tokens
+            .map(|t| t.parse::<f64>().map_err(|_| format!("Invalid number '{t}' in sweep '{name}'")))
+            .collect::<Result<Vec<f64>, String>>()?;
+
+        let values = match kind {
+            "lin" | "log" => {
+                let [start, end, steps] = numbers[..] else {
+                    return Err(format!("Sweep '{name}' expects: {kind} <start> <end> <steps>"));
+                };
+                if steps < 1.0 || steps.fract() != 0.0 {
+                    return Err(format!("Sweep '{name}' needs a positive whole number of steps"));
+                }
+                if kind == "log" && (start <= 0.0 || end <= 0.0) {
+                    return Err(format!("Logarithmic sweep '{name}' needs positive bounds"));
+                }
+                let steps = steps as usize;
+                (0..steps)
+                    .map(|i| {
+                        let t = if steps > 1 { i as f64 / (steps - 1) as f64 } else { 0.0 };
+                        if kind == "lin" {
+                            start + (end - start) * t
+                        } else {
+                            start * (end / start).powf(t)
+                        }
+                    })
+                    .collect()
+            }
+            "list" if !numbers.is_empty() => numbers,
+            "list" => return Err(format!("Sweep '{name}' has an empty list")),
+            _ => return Err(format!("Unknown sweep kind '{kind}' for '{name}'")),
+        };
+
+        Ok(Self {
+            name: name.to_owned(),
+            values,
+        })
+    }
+}
+
+/// `(name, value)` of every swept parameter at one point of the sweep.
+pub type ParameterPoint = Vec<(String, f64)>;
+
+/// Cartesian product of all axes.
+pub fn parameter_points(axes: &[SweepAxis]) -> Vec<ParameterPoint> {
+    axes.iter().fold(vec![Vec::new()], |points, axis| {
+        points
+            .iter()
+            .flat_map(|point| {
+                axis.values.iter().map(move |&value| {
+                    let mut point = point.clone();
+                    point.push((axis.name.clone(), value));
+                    point
+                })
+            })
+            .collect()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn values(spec: &str) -> Vec<f64> {
+        SweepAxis::parse("temperature", spec).unwrap().values
+    }
+
+    #[test]
+    fn linear_and_logarithmic_axes_include_their_ends() {
+        assert_eq!(values("lin 0 1 5"), [0.0, 0.25, 0.5, 0.75, 1.0]);
+        let log = values("log 0.01 100 5");
+        for (value, expected) in log.iter().zip([0.01, 0.1, 1.0, 10.0, 100.0]) {
+            assert!((value - expected).abs() < 1e-12 * expected, "{log:?}");
+        }
+        assert_eq!(values("list 3, 1 2"), [3.0, 1.0, 2.0]);
+    }
+
+    #[test]
+    fn single_step_takes_the_start() {
+        assert_eq!(values("lin 2 5 1"), [2.0]);
+        assert_eq!(values("log 2 5 1"), [2.0]);
+    }
+
+    #[test]
+    fn rejects_invalid_axes() {
+        let invalid = ["log 0 1 5", "log -1 1 5", "log 1 0 5", "lin 0 1 0", "lin 0 1 2.5", "lin 0 1", "list", "cubic 0 1 2"];
+        for spec in invalid {
+            assert!(SweepAxis::parse("temperature", spec).is_err(), "{spec} was accepted");
+        }
+    }
+
+    #[test]
+    fn points_cover_the_cartesian_product() {
+        let axes = [
+            SweepAxis::parse("temperature", "lin 0 1 4").unwrap(),
+            SweepAxis::parse("seed", "list 1 2 3").unwrap(),
+            SweepAxis::parse("coupling", "log 1 10 2").unwrap(),
+        ];
+        let points = parameter_points(&axes);
+        assert_eq!(points.len(), 4 * 3 * 2);
+        let first = [("temperature", 0.0), ("seed", 1.0), ("coupling", 1.0)].map(|(name, value)| (name.to_owned(), value));
+        assert_eq!(points[0], first);
+        assert_eq!(points[1][2], ("coupling".to_owned(), 10.0));
+        assert_eq!(parameter_points(&[]), [Vec::new()]);
+    }
+}
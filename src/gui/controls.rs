@@ -1,18 +1,27 @@
 use crate::rendering::*;
-use crate::widgets::textured_widget::TexturedWidget;
+use crate::widgets::textured_widget::{DisplayPress, TexturedWidget};
 use iced::*;
 use iced_wgpu::Renderer;
-use iced_widget::{column, container, row, shader, text, text_input};
+use iced_widget::{checkbox, column, container, image, row, scrollable, shader, slider, text, text_input};
 use iced_winit::core::{Color, Element, Theme};
 use iced_winit::runtime::{Program, Task};
 
 use std::path::Path;
 use widget::{button, pick_list};
 
+use crate::algorithm_processor::{
+    BOUNDARY_KINDS, Boundary, ExternalField, FIELD_PROFILE_KINDS, FieldProfile, INITIAL_STATE_KINDS,
+    InitialState, LatticeConditions, Mask, Observables, Overlays, Parameter, Protocol, RunConfig, SourceMap, Table, WAVEFORM_KINDS, Waveform,
+};
+use crate::analysis::{Analyses, ELASTIC_MAPS, ElasticMap, SPECTRUM_FIELDS, SpectrumField};
 use crate::application::CustomEvent;
 use crate::application::CustomEventProxy;
+use crate::application::RunSettings;
 use crate::application::SharedContext;
+use crate::batch::BatchConfig;
+use crate::gui::plot::Plot;
 use rfd::FileDialog;
+use std::path::PathBuf;
 
 type ContainerType<'a> = container::Container<'a, Message, Theme, Renderer>;
 
@@ -23,6 +32,13 @@ const INVALID_INPUT_COLOR: Color = Color {
     a: 1.0,
 };
 
+const WARNING_COLOR: Color = Color {
+    r: 1.0,
+    b: 0.2,
+    g: 0.75,
+    a: 1.0,
+};
+
 const START_BUTTON: Color = Color {
     r: 0.0,
     b: 0.0,
@@ -45,15 +61,86 @@ const DISABLED_BUTTON: Color = Color {
 };
 
 const DEFAULT_LATTICE_SIZE: usize = 200;
+const DEFAULT_SWEEP: &str = "temperature = lin 0.1 2.0 40; seed = list 1 2 3";
+const DEFAULT_BATCH_ITERATIONS: usize = 2000;
+const EDGES: [&str; 6] = ["left", "right", "bottom", "top", "front", "back"];
+/// "seed" hands clicks to the algorithm instead of editing the mask, e.g. to seed Gray-Scott spots,
+/// and "source" and "sink" paint heat sources.
+const MASK_TOOLS: [&str; 6] = ["off", "paint", "erase", "seed", "source", "sink"];
+const DEFAULT_BRUSH_RADIUS: f32 = 5.0;
+const DEFAULT_SOURCE_RATE: f32 = 1.0;
+/// Parameters that switch a term on or off, shown as checkboxes.
+const TOGGLE_PARAMETERS: [&str; 4] = ["cholesteric", "smectic", "polar", "inverse_time"];
+/// Name of the table the observables history is plotted from.
+const HISTORY_TABLE: &str = "observables";
+/// Most rows the history keeps; beyond it every other row is dropped and the spacing doubles.
+const HISTORY_ROWS: usize = 1000;
+/// Parameters shown side by side in the parameter panel.
+const PARAMETER_COLUMNS: usize = 2;
 
 pub struct Controls {
     texture: TexturedWidget,
     available_algorithms: Vec<String>,
+    /// Default parameters of every algorithm.
+    algorithm_parameters: Vec<(String, Vec<Parameter>)>,
+    /// Plugin libraries that could not be added to the algorithm list, and why.
+    plugin_errors: Vec<String>,
     selected_algorithm: Option<String>,
+    /// Name and typed value of every parameter of the selected algorithm.
+    parameter_inputs: Vec<(String, String)>,
     output_path: String,
     button_state: bool,
     dimentions: Option<usize>,
     dimentions_raw: String,
+    layers: Option<usize>,
+    layers_raw: String,
+    view: SliceView,
+    colormap: Colormap,
+    particle_style: ParticleStyle,
+    particle_radius_raw: String,
+    vector_overlay: VectorOverlay,
+    pitch_lines: bool,
+    trails: bool,
+    elastic_map: ElasticMap,
+    /// Measurements added to the observables of the next run.
+    analyses: Analyses,
+    layer_stack: LayerStack,
+    initial_state_kind: &'static str,
+    initial_state_arguments: String,
+    initial_state: std::result::Result<InitialState, String>,
+    protocols_raw: String,
+    replica_raw: String,
+    protocols: std::result::Result<Vec<Protocol>, String>,
+    sweep_axes: String,
+    batch_iterations_raw: String,
+    batch_running: bool,
+    batch_status: String,
+    run_statistics: String,
+    /// Iteration and, for dynamics with a real time, the simulated time of the running worker.
+    run_status: String,
+    run_warnings: Vec<String>,
+    tables: Vec<Table>,
+    /// Every observable of the running worker against the iteration, plotted like a table.
+    history: Table,
+    /// Iterations between rows of `history`.
+    history_stride: usize,
+    /// `table: column` of the plotted curve.
+    plot_series: Option<String>,
+    plot: Option<Plot>,
+    boundary_kinds: [&'static str; 6],
+    boundary_arguments: [String; 6],
+    boundary_error: Option<String>,
+    conditions: LatticeConditions,
+    mask_tool: &'static str,
+    brush_radius_raw: String,
+    source_rate_raw: String,
+    mask_error: Option<String>,
+    field_angle_raw: String,
+    field_magnitude_raw: String,
+    waveform_kind: &'static str,
+    waveform_arguments: String,
+    field_profile_kind: &'static str,
+    field_profile_arguments: String,
     custom_event_proxy: CustomEventProxy,
 }
 
@@ -63,26 +150,478 @@ pub enum Message {
     PickDirectory,
     ManualDirectoryEntry(String),
     DimentionsChanged(String),
+    LayersChanged(String),
+    SlicePlaneChanged(SlicePlane),
+    SliceIndexChanged(u32),
+    DirectorDisplayChanged(DirectorDisplay),
+    ColormapChanged(Colormap),
+    ParticleGlyphChanged(ParticleGlyph),
+    ParticleRadiusChanged(String),
+    VectorsToggled(bool),
+    VectorSpacingChanged(u32),
+    LicToggled(bool),
+    PitchLinesToggled(bool),
+    TrailsToggled(bool),
+    TrackingAnalysisToggled(bool),
+    CoarseningAnalysisToggled(bool),
+    ElasticMapChanged(ElasticMap),
+    SpectrumFieldChanged(SpectrumField),
+    ElasticAnalysisToggled(bool),
+    CorrelationAnalysisToggled(bool),
+    ParameterChanged(usize, String),
+    ParameterToggled(usize, bool),
+    LayerVisibilityChanged(LayerKind, bool),
+    LayerOpacityChanged(LayerKind, f32),
+    LayerBlendChanged(LayerKind, BlendMode),
+    InitialStateKindChanged(&'static str),
+    InitialStateArgumentsChanged(String),
+    ProtocolsChanged(String),
+    ReplicaChanged(String),
+    PickInitialStateFile,
     StartStop(bool),
     UpdateSharedData(SharedContext),
+    SweepAxesChanged(String),
+    BatchIterationsChanged(String),
+    RunBatch,
+    BatchStatus(String, bool),
+    RunStatus(String),
+    WarningsUpdated(Vec<String>),
+    RunStatistics(String),
+    StatusUpdated(Observables),
+    TablesUpdated(Vec<Table>),
+    PlotSeriesChanged(String),
+    BoundaryKindChanged(usize, &'static str),
+    BoundaryArgumentsChanged(usize, String),
+    MaskToolChanged(&'static str),
+    BrushRadiusChanged(String),
+    SourceRateChanged(String),
+    ClearSources,
+    LoadMask,
+    SaveMask,
+    ClearMask,
+    DisplayPressed(DisplayPress),
+    FieldAngleChanged(String),
+    FieldMagnitudeChanged(String),
+    WaveformKindChanged(&'static str),
+    WaveformArgumentsChanged(String),
+    FieldProfileKindChanged(&'static str),
+    FieldProfileArgumentsChanged(String),
+}
+
+impl From<DisplayPress> for Message {
+    fn from(press: DisplayPress) -> Self {
+        Message::DisplayPressed(press)
+    }
 }
 
 impl Controls {
-    pub fn new(texture: TextureHandle, custom_event_proxy: CustomEventProxy) -> Controls {
-        let options = vec!["None".to_owned(), "Simple".to_owned()];
+    pub fn new(
+        texture: TextureHandle,
+        algorithm_parameters: Vec<(String, Vec<Parameter>)>,
+        plugin_errors: Vec<String>,
+        custom_event_proxy: CustomEventProxy,
+    ) -> Controls {
+        let options: Vec<String> = algorithm_parameters.iter().map(|(name, _)| name.clone()).collect();
         let selection = options.first().cloned();
-        Controls {
+        let mut controls = Controls {
             texture: TexturedWidget::new(texture),
             available_algorithms: options,
+            algorithm_parameters,
+            plugin_errors,
             selected_algorithm: selection,
+            parameter_inputs: Vec::new(),
             output_path: "".to_owned(),
             button_state: false,
             dimentions_raw: DEFAULT_LATTICE_SIZE.to_string(),
             dimentions: Some(DEFAULT_LATTICE_SIZE),
+            layers: Some(1),
+            layers_raw: "1".to_owned(),
+            view: SliceView::default(),
+            colormap: Colormap::default(),
+            particle_style: ParticleStyle::default(),
+            particle_radius_raw: ParticleStyle::default().radius.to_string(),
+            vector_overlay: VectorOverlay::default(),
+            pitch_lines: false,
+            trails: false,
+            elastic_map: ElasticMap::Off,
+            analyses: Analyses::default(),
+            layer_stack: LayerStack::default(),
+            initial_state_kind: INITIAL_STATE_KINDS[0],
+            initial_state_arguments: "".to_owned(),
+            initial_state: Ok(InitialState::default()),
+            protocols_raw: String::new(),
+            replica_raw: "0".to_owned(),
+            protocols: Ok(Vec::new()),
+            sweep_axes: DEFAULT_SWEEP.to_owned(),
+            batch_iterations_raw: DEFAULT_BATCH_ITERATIONS.to_string(),
+            batch_running: false,
+            batch_status: "".to_owned(),
+            run_statistics: "".to_owned(),
+            run_status: "".to_owned(),
+            run_warnings: Vec::new(),
+            tables: Vec::new(),
+            history: Table::new(HISTORY_TABLE),
+            history_stride: 1,
+            plot_series: None,
+            plot: None,
+            boundary_kinds: [BOUNDARY_KINDS[0]; 6],
+            boundary_arguments: Default::default(),
+            boundary_error: None,
+            conditions: LatticeConditions::default(),
+            mask_tool: MASK_TOOLS[0],
+            brush_radius_raw: DEFAULT_BRUSH_RADIUS.to_string(),
+            source_rate_raw: DEFAULT_SOURCE_RATE.to_string(),
+            mask_error: None,
+            field_angle_raw: "0".to_owned(),
+            field_magnitude_raw: "0".to_owned(),
+            waveform_kind: WAVEFORM_KINDS[0],
+            waveform_arguments: "".to_owned(),
+            field_profile_kind: FIELD_PROFILE_KINDS[0],
+            field_profile_arguments: "".to_owned(),
             custom_event_proxy,
+        };
+        controls.reset_parameters();
+        controls
+    }
+
+    /// Fills the parameter panel with the defaults of the selected algorithm.
+    fn reset_parameters(&mut self) {
+        self.parameter_inputs = self
+            .algorithm_parameters
+            .iter()
+            .find(|(name, _)| Some(name) == self.selected_algorithm.as_ref())
+            .map(|(_, parameters)| {
+                parameters
+                    .iter()
+                    .map(|parameter| (parameter.name.clone(), parameter.value.to_string()))
+                    .collect()
+            })
+            .unwrap_or_default();
+    }
+
+    /// The parameters typed in, leaving out those that are not numbers.
+    fn parameters(&self) -> Vec<Parameter> {
+        self.parameter_inputs
+            .iter()
+            .filter_map(|(name, raw)| raw.parse::<f32>().ok().map(|value| Parameter::new(name, value)))
+            .collect()
+    }
+
+    fn parameter(&self, name: &str) -> Option<f32> {
+        self.parameters().into_iter().find(|p| p.name == name).map(|p| p.value)
+    }
+
+    /// Applies a parameter to the running algorithm.
+    fn send_parameter(&self, index: usize) {
+        let Some((name, raw)) = self.parameter_inputs.get(index) else {
+            return;
+        };
+        if let Ok(value) = raw.parse::<f32>() {
+            let _ = self
+                .custom_event_proxy
+                .send_event(CustomEvent::SetParameter(name.clone(), value));
+        }
+        if name == "helix_angle" {
+            self.send_pitch_lines();
         }
     }
 
+    /// Pitch lines follow the helix axis of the parameter panel, or x without one.
+    fn send_pitch_lines(&self) {
+        let reference = self.pitch_lines.then(|| self.parameter("helix_angle").unwrap_or(0.0));
+        let _ = self
+            .custom_event_proxy
+            .send_event(CustomEvent::UpdatePitchLines(reference));
+    }
+
+    fn parameter_controls(&self) -> widget::Column<'_, Message, Theme, Renderer> {
+        let entries: Vec<Element<'_, Message, Theme, Renderer>> = self
+            .parameter_inputs
+            .iter()
+            .enumerate()
+            .map(|(index, (name, raw))| {
+                if TOGGLE_PARAMETERS.contains(&name.as_str()) {
+                    let enabled = raw.parse::<f32>().is_ok_and(|value| value > 0.0);
+                    checkbox(name.as_str(), enabled)
+                        .on_toggle(move |enabled| Message::ParameterToggled(index, enabled))
+                        .style(Self::white_checkbox)
+                        .width(Fill)
+                        .into()
+                } else {
+                    row![
+                        text(name.as_str()).color(Color::WHITE).size(12).width(Fill),
+                        text_input(name.as_str(), raw)
+                            .on_input(move |raw| Message::ParameterChanged(index, raw))
+                            .style(move |theme, status| {
+                                Self::valid_when(raw.parse::<f32>().is_ok(), theme, status)
+                            })
+                            .width(Fill),
+                    ]
+                    .spacing(5)
+                    .width(Fill)
+                    .into()
+                }
+            })
+            .collect();
+        let mut rows = vec![text("Parameters").color(Color::WHITE).into()];
+        let mut entries = entries.into_iter().peekable();
+        while entries.peek().is_some() {
+            let row = widget::Row::with_children(entries.by_ref().take(PARAMETER_COLUMNS)).spacing(10);
+            rows.push(row.into());
+        }
+        widget::Column::with_children(rows).spacing(5)
+    }
+
+    /// Every curve of the tables as `table: column`.
+    fn plotted_tables(&self) -> impl Iterator<Item = &Table> {
+        self.tables.iter().chain(Some(&self.history).filter(|history| !history.columns.is_empty()))
+    }
+
+    fn plot_series_names(&self) -> Vec<String> {
+        self.plotted_tables()
+            .flat_map(|table| {
+                table
+                    .columns
+                    .iter()
+                    .skip(1)
+                    .filter(|(column, _)| {
+                        column
+                            .strip_suffix("_fit")
+                            .is_none_or(|fitted| table.column(fitted).is_none())
+                    })
+                    .map(move |(column, _)| format!("{}: {column}", table.name))
+            })
+            .collect()
+    }
+
+    /// Rasterises the selected curve again, falling back to the first one.
+    fn update_plot(&mut self) {
+        let names = self.plot_series_names();
+        if !self.plot_series.as_ref().is_some_and(|series| names.contains(series)) {
+            self.plot_series = names.first().cloned();
+        }
+        self.plot = self.plot_series.as_ref().and_then(|series| {
+            let (table, column) = series.split_once(": ")?;
+            let table = self.plotted_tables().find(|t| t.name == table)?;
+            let overlay = table.column(&format!("{column}_fit"));
+            Plot::new(&table.columns.first()?.1, table.column(column)?, overlay, table.log_axes)
+        });
+    }
+
+    /// Appends `sample` to the observables history, starting the columns with the first sample.
+    /// Only every `history_stride`-th iteration is kept, so the history and its plot stay bounded.
+    fn record_sample(&mut self, sample: &Observables) {
+        if !sample.iteration.is_multiple_of(self.history_stride) {
+            return;
+        }
+        if self.history.columns.is_empty() {
+            self.history = sample
+                .values
+                .iter()
+                .fold(Table::new(HISTORY_TABLE).with_column("iteration", Vec::new()), |table, (name, _)| {
+                    table.with_column(name, Vec::new())
+                });
+        }
+        for (name, values) in self.history.columns.iter_mut() {
+            let value = if name == "iteration" { Some(sample.iteration as f64) } else { sample.get(name) };
+            values.push(value.unwrap_or(f64::NAN));
+        }
+        if self.history.columns.first().is_some_and(|(_, values)| values.len() > HISTORY_ROWS) {
+            self.history_stride *= 2;
+            let stride = self.history_stride as f64;
+            let kept: Vec<bool> = self.history.columns[0].1.iter().map(|&iteration| iteration % stride == 0.0).collect();
+            for (_, values) in self.history.columns.iter_mut() {
+                let mut kept = kept.iter();
+                values.retain(|_| *kept.next().unwrap_or(&false));
+            }
+        }
+        if self
+            .plot_series
+            .as_ref()
+            .is_some_and(|series| series.starts_with(&format!("{HISTORY_TABLE}: ")))
+        {
+            self.update_plot();
+        }
+    }
+
+    fn parse_initial_state(&mut self) {
+        self.initial_state =
+            InitialState::parse(&format!("{} {}", self.initial_state_kind, self.initial_state_arguments));
+    }
+
+    /// Rebuilds the boundaries from the edge inputs; invalid edges keep their last value.
+    fn parse_boundaries(&mut self) {
+        self.boundary_error = None;
+        for (edge, (kind, arguments)) in EDGES
+            .iter()
+            .zip(self.boundary_kinds.iter().zip(&self.boundary_arguments))
+        {
+            match Boundary::parse(&format!("{kind} {arguments}")) {
+                Ok(boundary) => {
+                    if let Some(slot) = self.conditions.boundaries.edge_mut(edge) {
+                        *slot = boundary;
+                    }
+                }
+                Err(error) => self.boundary_error = Some(format!("{edge}: {error}")),
+            }
+        }
+        self.send_conditions();
+    }
+
+    fn parse_field(&self) -> std::result::Result<ExternalField, String> {
+        let number = |raw: &str, name: &str| {
+            raw.parse::<f32>()
+                .map_err(|_| format!("Invalid field {name} '{raw}'"))
+        };
+        Ok(ExternalField {
+            angle: number(&self.field_angle_raw, "angle")?,
+            magnitude: number(&self.field_magnitude_raw, "magnitude")?,
+            waveform: Waveform::parse(&format!("{} {}", self.waveform_kind, self.waveform_arguments))?,
+            profile: FieldProfile::parse(&format!(
+                "{} {}",
+                self.field_profile_kind, self.field_profile_arguments
+            ))?,
+        })
+    }
+
+    /// Applies the field inputs live once they are all valid.
+    fn update_field(&mut self) {
+        if let Ok(field) = self.parse_field() {
+            self.conditions.field = field;
+            self.send_conditions();
+        }
+    }
+
+    fn send_particle_style(&self) {
+        let _ = self
+            .custom_event_proxy
+            .send_event(CustomEvent::UpdateParticleStyle(self.particle_style));
+    }
+
+    fn send_vector_overlay(&self) {
+        let _ = self
+            .custom_event_proxy
+            .send_event(CustomEvent::UpdateVectorOverlay(self.vector_overlay));
+    }
+
+    fn send_layers(&self) {
+        let _ = self
+            .custom_event_proxy
+            .send_event(CustomEvent::UpdateLayers(self.layer_stack));
+    }
+
+    fn send_view(&self) {
+        let _ = self
+            .custom_event_proxy
+            .send_event(CustomEvent::UpdateView(self.view));
+    }
+
+    fn send_conditions(&self) {
+        let _ = self
+            .custom_event_proxy
+            .send_event(CustomEvent::UpdateConditions(self.conditions.clone()));
+    }
+
+    /// Seeds a perturbation of the brush radius in the running algorithm.
+    fn seed_perturbation(&self, press: DisplayPress) {
+        if let (mouse::Button::Left, Ok(radius)) = (press.button, self.brush_radius_raw.parse::<f32>()) {
+            let _ = self
+                .custom_event_proxy
+                .send_event(CustomEvent::Perturb(press.position, radius));
+        }
+    }
+
+    /// Left button paints sources or sinks of the source rate, right button clears them.
+    fn paint_sources(&mut self, press: DisplayPress) {
+        let (Some(dimentions), Ok(radius), Ok(rate)) = (
+            self.dimentions,
+            self.brush_radius_raw.parse::<f32>(),
+            self.source_rate_raw.parse::<f32>(),
+        ) else {
+            return;
+        };
+        let rate = match (press.button, self.mask_tool) {
+            (mouse::Button::Left, "source") => rate,
+            (mouse::Button::Left, _) => -rate,
+            (mouse::Button::Right, _) => 0.0,
+            _ => return,
+        };
+
+        let dimensions = (dimentions, dimentions);
+        let sources = match self.conditions.sources.take() {
+            Some(sources) => sources.resampled(dimensions),
+            None => SourceMap::new(dimensions),
+        };
+        self.conditions.sources.insert(sources).paint(press.position, radius, rate);
+        self.send_conditions();
+    }
+
+    /// Left button applies the mask tool, right button always erases.
+    fn paint_mask(&mut self, press: DisplayPress) {
+        let frozen = match (press.button, self.mask_tool) {
+            (_, "off") => return,
+            (mouse::Button::Left, "paint") => true,
+            (mouse::Button::Left | mouse::Button::Right, _) => false,
+            _ => return,
+        };
+        let (Some(dimentions), Ok(radius)) = (self.dimentions, self.brush_radius_raw.parse::<f32>())
+        else {
+            return;
+        };
+
+        let dimensions = (dimentions, dimentions);
+        let mask = match self.conditions.mask.take() {
+            Some(mask) => mask.resampled(dimensions),
+            None => Mask::new(dimensions),
+        };
+        let mask = self.conditions.mask.insert(mask);
+        mask.paint(press.position, radius, frozen);
+        self.send_conditions();
+    }
+
+    /// Builds a batch from the current selection, lattice size and output directory.
+    /// Sweep axes are written as `name = spec` pairs separated by `;`.
+    fn batch_config(&self) -> std::result::Result<BatchConfig, String> {
+        let algorithm = self
+            .selected_algorithm
+            .clone()
+            .ok_or_else(|| "No algorithm selected".to_owned())?;
+        let dimentions = self.dimentions.ok_or_else(|| "Invalid lattice size".to_owned())?;
+        let layers = self.layers.ok_or_else(|| "Invalid number of layers".to_owned())?;
+        if !Path::new(&self.output_path).is_dir() {
+            return Err("Pick an output directory first".to_owned());
+        }
+        let iterations = self
+            .batch_iterations_raw
+            .parse::<usize>()
+            .map_err(|_| "Invalid number of iterations".to_owned())?;
+
+        let initial_state = self.initial_state.clone()?;
+
+        let base = RunConfig {
+            algorithm,
+            lattice_dimension: (dimentions, dimentions),
+            layers,
+            initial_state,
+            conditions: self.conditions.clone(),
+            protocols: self.protocols.clone()?,
+            parameters: self.parameters(),
+            analyses: self.analyses.clone(),
+            iterations,
+            sample_interval: (iterations / 1000).max(1),
+            ..Default::default()
+        };
+        let mut config = BatchConfig::new(base, PathBuf::from(&self.output_path));
+        for axis in self.sweep_axes.split(';').filter(|a| !a.trim().is_empty()) {
+            let (name, spec) = axis
+                .split_once('=')
+                .ok_or_else(|| format!("Expected 'name = spec' in '{}'", axis.trim()))?;
+            config.apply(&format!("sweep.{}", name.trim()), spec.trim())?;
+        }
+
+        Ok(config)
+    }
+
     fn valid_path_style(&self, theme: &Theme, status: text_input::Status) -> text_input::Style {
         let mut style = text_input::default(theme, status);
         if !Path::new(&self.output_path).is_dir() {
@@ -92,16 +631,102 @@ impl Controls {
         style
     }
 
+    fn valid_batch_iterations(
+        &self,
+        theme: &Theme,
+        status: text_input::Status,
+    ) -> text_input::Style {
+        let mut style = text_input::default(theme, status);
+        if self.batch_iterations_raw.parse::<usize>().is_err() {
+            style.value = INVALID_INPUT_COLOR;
+        }
+
+        style
+    }
+
+    fn valid_initial_state(&self, theme: &Theme, status: text_input::Status) -> text_input::Style {
+        let mut style = text_input::default(theme, status);
+        if self.initial_state.is_err() {
+            style.value = INVALID_INPUT_COLOR;
+        }
+
+        style
+    }
+
+    fn valid_boundary(
+        &self,
+        edge: usize,
+        theme: &Theme,
+        status: text_input::Status,
+    ) -> text_input::Style {
+        let mut style = text_input::default(theme, status);
+        let boundary = format!("{} {}", self.boundary_kinds[edge], self.boundary_arguments[edge]);
+        if Boundary::parse(&boundary).is_err() {
+            style.value = INVALID_INPUT_COLOR;
+        }
+
+        style
+    }
+
+    fn valid_brush_radius(&self, theme: &Theme, status: text_input::Status) -> text_input::Style {
+        let mut style = text_input::default(theme, status);
+        if self.brush_radius_raw.parse::<f32>().is_err() {
+            style.value = INVALID_INPUT_COLOR;
+        }
+
+        style
+    }
+
+    fn valid_when(valid: bool, theme: &Theme, status: text_input::Status) -> text_input::Style {
+        let mut style = text_input::default(theme, status);
+        if !valid {
+            style.value = INVALID_INPUT_COLOR;
+        }
+
+        style
+    }
+
+    /// Checkbox labels are drawn in white like the other labels on the dark panel.
+    fn white_checkbox(theme: &Theme, status: checkbox::Status) -> checkbox::Style {
+        checkbox::Style {
+            text_color: Some(Color::WHITE),
+            ..checkbox::primary(theme, status)
+        }
+    }
+
+    /// One row per layer, bottom first: visibility, opacity and blend mode.
+    fn layer_controls(&self) -> widget::Column<'_, Message, Theme, Renderer> {
+        widget::Column::with_children(LAYER_KINDS.iter().map(|&kind| {
+            let settings = self.layer_stack.get(kind);
+            row![
+                checkbox(kind.to_string(), settings.visible)
+                    .on_toggle(move |visible| Message::LayerVisibilityChanged(kind, visible))
+                    .style(Self::white_checkbox)
+                    .width(120),
+                slider(0.0..=1.0, settings.opacity, move |opacity| {
+                    Message::LayerOpacityChanged(kind, opacity)
+                })
+                .step(0.05),
+                pick_list(BLEND_MODES.as_slice(), Some(settings.blend), move |blend| {
+                    Message::LayerBlendChanged(kind, blend)
+                }),
+            ]
+            .spacing(5)
+            .into()
+        }))
+        .spacing(5)
+    }
+
     fn valid_dimentions(&self, theme: &Theme, status: text_input::Status) -> text_input::Style {
         let mut style = text_input::default(theme, status);
-        if let Err(_) = self.dimentions_raw.parse::<usize>() {
+        if self.dimentions_raw.parse::<usize>().is_err() {
             style.value = INVALID_INPUT_COLOR;
         }
 
         style
     }
 
-    fn start_stop_button(&self) -> button::Button<Message, Theme, Renderer> {
+    fn start_stop_button(&self) -> button::Button<'_, Message, Theme, Renderer> {
         if self.button_state {
             button("Stop")
                 .on_press(Message::StartStop(self.button_state))
@@ -110,14 +735,14 @@ impl Controls {
                     ..Default::default()
                 })
         } else {
-            match self.dimentions {
-                Some(_) => button("Start")
+            match (self.dimentions, self.layers, &self.initial_state, &self.protocols) {
+                (Some(_), Some(_), Ok(_), Ok(_)) => button("Start")
                     .on_press(Message::StartStop(self.button_state))
                     .style(|_, _| button::Style {
                         background: Some(Background::from(START_BUTTON)),
                         ..Default::default()
                     }),
-                None => button("Start").style(|_, _| button::Style {
+                _ => button("Start").style(|_, _| button::Style {
                     background: Some(Background::from(DISABLED_BUTTON)),
                     ..Default::default()
                 }),
@@ -125,15 +750,22 @@ impl Controls {
         }
     }
 
-    fn static_interface(&self) -> ContainerType {
+    fn static_interface(&self) -> ContainerType<'_> {
+        let mut algorithms = column![
+            text("Select algorithm").color(Color::WHITE),
+            pick_list(
+                self.available_algorithms.as_slice(),
+                self.selected_algorithm.clone(),
+                |input| { Message::InputChanged(input) }
+            ),
+        ]
+        .spacing(5);
+        for error in &self.plugin_errors {
+            algorithms = algorithms.push(text(format!("Warning: {error}")).color(WARNING_COLOR).size(12));
+        }
         container(
             column![
-                text("Select algorithm").color(Color::WHITE),
-                pick_list(
-                    self.available_algorithms.as_slice(),
-                    self.selected_algorithm.clone(),
-                    |input| { Message::InputChanged(input) }
-                ),
+                algorithms,
                 row![
                     button("Pick output directory").on_press(Message::PickDirectory),
                     self.start_stop_button()
@@ -154,13 +786,347 @@ impl Controls {
             .height(FillPortion(3))
     }
 
-    fn dynamic_interface(&self) -> ContainerType {
-        let dimentions = column![
-            text_input(self.dimentions_raw.as_str(), &self.dimentions_raw)
-                .on_input(Message::DimentionsChanged)
-                .style(|theme, status| self.valid_dimentions(theme, status))
-        ];
-        container(column![dimentions])
+    fn dynamic_interface(&self) -> ContainerType<'_> {
+        let mut dimentions = column![
+            row![
+                text_input(self.dimentions_raw.as_str(), &self.dimentions_raw)
+                    .on_input(Message::DimentionsChanged)
+                    .style(|theme, status| self.valid_dimentions(theme, status)),
+                text("Layers").color(Color::WHITE),
+                text_input("Layers along z", &self.layers_raw)
+                    .on_input(Message::LayersChanged)
+                    .style(|theme, status| Self::valid_when(self.layers.is_some(), theme, status)),
+            ]
+            .spacing(5),
+            row![
+                text("Colormap").color(Color::WHITE),
+                pick_list(COLORMAPS.as_slice(), Some(self.colormap), Message::ColormapChanged),
+                text("Particles").color(Color::WHITE),
+                pick_list(
+                    PARTICLE_GLYPHS.as_slice(),
+                    Some(self.particle_style.glyph),
+                    Message::ParticleGlyphChanged
+                ),
+                text_input("Radius", &self.particle_radius_raw)
+                    .on_input(Message::ParticleRadiusChanged)
+                    .style(|theme, status| {
+                        let valid = self.particle_radius_raw.parse::<f32>().is_ok_and(|radius| radius > 0.0);
+                        Self::valid_when(valid, theme, status)
+                    }),
+            ]
+            .spacing(5),
+            row![
+                checkbox("Arrows", self.vector_overlay.enabled)
+                    .on_toggle(Message::VectorsToggled)
+                    .style(Self::white_checkbox),
+                text(format!("every {} cells", self.vector_overlay.spacing)).color(Color::WHITE),
+                slider(1..=16, self.vector_overlay.spacing as u32, Message::VectorSpacingChanged),
+                checkbox("LIC", self.vector_overlay.lic)
+                    .on_toggle(Message::LicToggled)
+                    .style(Self::white_checkbox),
+                checkbox("Pitch lines", self.pitch_lines)
+                    .on_toggle(Message::PitchLinesToggled)
+                    .style(Self::white_checkbox),
+                checkbox("Trails", self.trails)
+                    .on_toggle_maybe(self.analyses.tracking.then_some(Message::TrailsToggled))
+                    .style(Self::white_checkbox),
+            ]
+            .spacing(5),
+            row![
+                text("Elastic map").color(Color::WHITE),
+                pick_list(ELASTIC_MAPS.as_slice(), Some(self.elastic_map), Message::ElasticMapChanged),
+                text("Spectrum").color(Color::WHITE),
+                pick_list(
+                    SPECTRUM_FIELDS.as_slice(),
+                    Some(self.analyses.spectrum),
+                    Message::SpectrumFieldChanged
+                ),
+            ]
+            .spacing(5),
+            self.layer_controls(),
+        ]
+        .spacing(5);
+        if let (Some(size), Some(layers)) = (self.dimentions, self.layers)
+            && layers > 1
+        {
+            let extent = SliceView::extent(self.view.plane, (size, size, layers));
+            let index = self.view.index.min(extent.saturating_sub(1));
+            dimentions = dimentions.push(
+                row![
+                    pick_list(SLICE_PLANES.as_slice(), Some(self.view.plane), Message::SlicePlaneChanged),
+                    pick_list(
+                        DIRECTOR_DISPLAYS.as_slice(),
+                        Some(self.view.display),
+                        Message::DirectorDisplayChanged
+                    ),
+                    text(format!("Slice {index}")).color(Color::WHITE),
+                ]
+                .spacing(5),
+            );
+            dimentions = dimentions.push(slider(
+                0..=extent.saturating_sub(1) as u32,
+                index as u32,
+                Message::SliceIndexChanged,
+            ));
+        }
+        if self
+            .selected_algorithm
+            .as_ref()
+            .is_some_and(|algorithm| algorithm.contains("parallel tempering"))
+        {
+            dimentions = dimentions.push(
+                row![
+                    text("Replica").color(Color::WHITE),
+                    text_input("Ladder position, or all", &self.replica_raw)
+                        .on_input(Message::ReplicaChanged)
+                        .style(|theme, status| {
+                            Self::valid_when(parse_replica(&self.replica_raw).is_some(), theme, status)
+                        }),
+                ]
+                .spacing(5),
+            );
+        }
+        let initial_state_arguments = text_input(
+            initial_state_usage(self.initial_state_kind),
+            &self.initial_state_arguments,
+        )
+        .on_input(Message::InitialStateArgumentsChanged)
+        .style(|theme, status| self.valid_initial_state(theme, status));
+        let mut initial_state = column![
+            text("Initial state").color(Color::WHITE),
+            pick_list(
+                INITIAL_STATE_KINDS.as_slice(),
+                Some(self.initial_state_kind),
+                Message::InitialStateKindChanged
+            ),
+        ]
+        .spacing(5);
+        initial_state = if self.initial_state_kind == "file" {
+            initial_state.push(
+                row![
+                    button("Pick file").on_press(Message::PickInitialStateFile),
+                    initial_state_arguments
+                ]
+                .spacing(5),
+            )
+        } else {
+            initial_state.push(initial_state_arguments)
+        };
+        if let Err(error) = &self.initial_state {
+            initial_state = initial_state.push(text(error).color(INVALID_INPUT_COLOR).size(12));
+        }
+
+        let mut boundaries = column![text("Boundaries").color(Color::WHITE)].spacing(5);
+        // Front and back only exist on layered lattices.
+        let edges = if self.layers.is_some_and(|layers| layers > 1) { 6 } else { 4 };
+        for (index, edge) in EDGES.iter().enumerate().take(edges) {
+            boundaries = boundaries.push(
+                row![
+                    text(*edge).color(Color::WHITE).width(50),
+                    pick_list(
+                        BOUNDARY_KINDS.as_slice(),
+                        Some(self.boundary_kinds[index]),
+                        move |kind| Message::BoundaryKindChanged(index, kind)
+                    ),
+                    text_input(boundary_usage(self.boundary_kinds[index]), &self.boundary_arguments[index])
+                        .on_input(move |arguments| Message::BoundaryArgumentsChanged(index, arguments))
+                        .style(move |theme, status| self.valid_boundary(index, theme, status)),
+                ]
+                .spacing(5),
+            );
+        }
+        if let Some(error) = &self.boundary_error {
+            boundaries = boundaries.push(text(error).color(INVALID_INPUT_COLOR).size(12));
+        }
+
+        let frozen_cells = self
+            .conditions
+            .mask
+            .as_ref()
+            .map_or(0, |mask| mask.frozen.iter().filter(|&&frozen| frozen).count());
+        let mut mask = column![
+            text(format!("Mask ({frozen_cells} frozen cells)")).color(Color::WHITE),
+            row![
+                pick_list(MASK_TOOLS.as_slice(), Some(self.mask_tool), Message::MaskToolChanged),
+                text_input("Brush radius", &self.brush_radius_raw)
+                    .on_input(Message::BrushRadiusChanged)
+                    .style(|theme, status| self.valid_brush_radius(theme, status)),
+                text_input("Source rate", &self.source_rate_raw)
+                    .on_input(Message::SourceRateChanged)
+                    .style(|theme, status| {
+                        Self::valid_when(self.source_rate_raw.parse::<f32>().is_ok(), theme, status)
+                    }),
+            ]
+            .spacing(5),
+            row![
+                button("Load mask").on_press(Message::LoadMask),
+                button("Save mask").on_press(Message::SaveMask),
+                button("Clear mask").on_press(Message::ClearMask),
+                button("Clear sources").on_press(Message::ClearSources),
+            ]
+            .spacing(5),
+        ]
+        .spacing(5);
+        if let Some(error) = &self.mask_error {
+            mask = mask.push(text(error).color(INVALID_INPUT_COLOR).size(12));
+        }
+
+        let waveform = format!("{} {}", self.waveform_kind, self.waveform_arguments);
+        let profile = format!("{} {}", self.field_profile_kind, self.field_profile_arguments);
+        let mut field = column![
+            text("External field").color(Color::WHITE),
+            row![
+                text_input("Angle", &self.field_angle_raw)
+                    .on_input(Message::FieldAngleChanged)
+                    .style(|theme, status| {
+                        Self::valid_when(self.field_angle_raw.parse::<f32>().is_ok(), theme, status)
+                    }),
+                text_input("Magnitude", &self.field_magnitude_raw)
+                    .on_input(Message::FieldMagnitudeChanged)
+                    .style(|theme, status| {
+                        Self::valid_when(self.field_magnitude_raw.parse::<f32>().is_ok(), theme, status)
+                    }),
+            ]
+            .spacing(5),
+            row![
+                pick_list(WAVEFORM_KINDS.as_slice(), Some(self.waveform_kind), Message::WaveformKindChanged),
+                text_input(waveform_usage(self.waveform_kind), &self.waveform_arguments)
+                    .on_input(Message::WaveformArgumentsChanged)
+                    .style(move |theme, status| {
+                        Self::valid_when(Waveform::parse(&waveform).is_ok(), theme, status)
+                    }),
+            ]
+            .spacing(5),
+            row![
+                pick_list(
+                    FIELD_PROFILE_KINDS.as_slice(),
+                    Some(self.field_profile_kind),
+                    Message::FieldProfileKindChanged
+                ),
+                text_input(field_profile_usage(self.field_profile_kind), &self.field_profile_arguments)
+                    .on_input(Message::FieldProfileArgumentsChanged)
+                    .style(move |theme, status| {
+                        Self::valid_when(FieldProfile::parse(&profile).is_ok(), theme, status)
+                    }),
+            ]
+            .spacing(5),
+        ]
+        .spacing(5);
+        if let Err(error) = self.parse_field() {
+            field = field.push(text(error).color(INVALID_INPUT_COLOR).size(12));
+        }
+
+        let mut protocols = column![
+            text("Protocols").color(Color::WHITE),
+            text_input(
+                "name = ramp|exponential|quench|table|sine|square|triangle ...; ...",
+                &self.protocols_raw
+            )
+            .on_input(Message::ProtocolsChanged)
+            .style(|theme, status| Self::valid_when(self.protocols.is_ok(), theme, status)),
+        ]
+        .spacing(5);
+        if let Err(error) = &self.protocols {
+            protocols = protocols.push(text(error).color(INVALID_INPUT_COLOR).size(12));
+        }
+
+        let run_batch = if self.batch_running {
+            button("Run batch")
+        } else {
+            button("Run batch").on_press(Message::RunBatch)
+        };
+        let batch = column![
+            text("Batch sweep").color(Color::WHITE),
+            text_input("name = lin|log|list ...; ...", &self.sweep_axes)
+                .on_input(Message::SweepAxesChanged),
+            row![
+                text_input("Iterations per run", &self.batch_iterations_raw)
+                    .on_input(Message::BatchIterationsChanged)
+                    .style(|theme, status| self.valid_batch_iterations(theme, status)),
+                run_batch
+            ]
+            .spacing(5),
+            scrollable(text(&self.batch_status).font(Font::MONOSPACE).size(12)).height(Fill),
+        ]
+        .spacing(5);
+        let analysis = column![
+            text("Analysis").color(Color::WHITE),
+            checkbox("Elastic energy totals", self.analyses.elastic)
+                .on_toggle(Message::ElasticAnalysisToggled)
+                .style(Self::white_checkbox),
+            checkbox("Correlation function", self.analyses.correlation)
+                .on_toggle(Message::CorrelationAnalysisToggled)
+                .style(Self::white_checkbox),
+            checkbox("Defect tracking", self.analyses.tracking)
+                .on_toggle(Message::TrackingAnalysisToggled)
+                .style(Self::white_checkbox),
+            checkbox("Coarsening lengths", self.analyses.coarsening)
+                .on_toggle(Message::CoarseningAnalysisToggled)
+                .style(Self::white_checkbox),
+        ]
+        .spacing(5);
+        let mut statistics = column![
+            text("Run statistics").color(Color::WHITE),
+            text(&self.run_status).font(Font::MONOSPACE).size(12),
+        ]
+        .spacing(5);
+        for warning in &self.run_warnings {
+            statistics = statistics.push(text(format!("Warning: {warning}")).color(WARNING_COLOR).size(12));
+        }
+        let statistics = statistics.push(text(&self.run_statistics).font(Font::MONOSPACE).size(12));
+        let mut plots = column![].spacing(5);
+        if self.plotted_tables().next().is_some() {
+            plots = plots.push(
+                row![
+                    text("Plots").color(Color::WHITE),
+                    pick_list(
+                        self.plot_series_names(),
+                        self.plot_series.clone(),
+                        Message::PlotSeriesChanged
+                    ),
+                ]
+                .spacing(5),
+            );
+        }
+        if let Some(plot) = &self.plot {
+            let x_name = self
+                .plot_series
+                .as_ref()
+                .and_then(|series| self.plotted_tables().find(|t| series.starts_with(&format!("{}: ", t.name))))
+                .and_then(|table| table.columns.first())
+                .map_or("x", |(name, _)| name.as_str());
+            let log = if plot.log_axes { "log10 " } else { "" };
+            let mut caption = format!(
+                "{log}{x_name} {:.4} .. {:.4}\n{log}y {:.4} .. {:.4}",
+                plot.x_range.0, plot.x_range.1, plot.y_range.0, plot.y_range.1
+            );
+            if let (true, Some(slope)) = (plot.log_axes, plot.overlay_slope) {
+                caption.push_str(&format!("\nfitted exponent {slope:.4}"));
+            }
+            plots = plots.push(image(plot.handle.clone()).width(Fill));
+            plots = plots.push(
+                text(caption)
+                .font(Font::MONOSPACE)
+                .size(12),
+            );
+        }
+        container(
+            column![
+                dimentions,
+                self.parameter_controls(),
+                initial_state,
+                boundaries,
+                mask,
+                field,
+                protocols,
+                analysis,
+                statistics,
+                plots,
+                batch
+            ]
+            .spacing(10),
+        )
+            .padding(5)
             .style(|_| container::Style {
                 border: border::rounded(10).color(Color::WHITE).width(2),
                 ..Default::default()
@@ -170,6 +1136,83 @@ impl Controls {
     }
 }
 
+/// `displayed_replica` for a ladder position, or -1 for `all`.
+fn parse_replica(text: &str) -> Option<f32> {
+    match text.trim() {
+        "all" => Some(-1.0),
+        position => position.parse::<usize>().ok().map(|position| position as f32),
+    }
+}
+
+fn initial_state_usage(kind: &str) -> &'static str {
+    match kind {
+        "aligned" => "<angle>",
+        "twisted" => "<start angle> <end angle>",
+        "splayed" => "<spread>",
+        "defect_pair" => "<charge> <x1> <y1> <x2> <y2>",
+        "bilayer" => "<top angle> <bottom angle>",
+        "file" => "Path to a .png or .npy file",
+        _ => "",
+    }
+}
+
+fn boundary_usage(kind: &str) -> &'static str {
+    match kind {
+        "fixed" => "<value>",
+        "weak" => "<value> <strength>",
+        _ => "",
+    }
+}
+
+fn boundary_example(kind: &str) -> &'static str {
+    match kind {
+        "fixed" => "0",
+        "weak" => "0 0.5",
+        _ => "",
+    }
+}
+
+fn waveform_usage(kind: &str) -> &'static str {
+    match kind {
+        "step" => "<start iteration>",
+        "sine" | "square" => "<period in iterations>",
+        _ => "",
+    }
+}
+
+fn waveform_example(kind: &str) -> &'static str {
+    match kind {
+        "step" => "100",
+        "sine" | "square" => "200",
+        _ => "",
+    }
+}
+
+fn field_profile_usage(kind: &str) -> &'static str {
+    match kind {
+        "spot" => "<x> <y> <radius>",
+        _ => "",
+    }
+}
+
+fn field_profile_example(kind: &str) -> &'static str {
+    match kind {
+        "spot" => "0.5 0.5 0.2",
+        _ => "",
+    }
+}
+
+fn initial_state_example(kind: &str) -> &'static str {
+    match kind {
+        "aligned" => "0",
+        "twisted" => "0 3.1416",
+        "splayed" => "1.5708",
+        "defect_pair" => "0.5 0.3 0.5 0.7 0.5",
+        "bilayer" => "0 1.5708",
+        _ => "",
+    }
+}
+
 impl Program for Controls {
     type Theme = Theme;
     type Message = Message;
@@ -179,6 +1222,7 @@ impl Program for Controls {
         match message {
             Message::InputChanged(input) => {
                 self.selected_algorithm = Some(input);
+                self.reset_parameters();
             }
             Message::PickDirectory => {
                 if let Some(path) = FileDialog::new().pick_folder() {
@@ -195,23 +1239,282 @@ impl Program for Controls {
                     Err(_) => self.dimentions = None,
                 }
             }
+            Message::LayersChanged(layers) => {
+                self.layers = layers.parse::<usize>().ok().filter(|&layers| layers > 0);
+                self.layers_raw = layers;
+            }
+            Message::SlicePlaneChanged(plane) => {
+                self.view.plane = plane;
+                self.send_view();
+            }
+            Message::SliceIndexChanged(index) => {
+                self.view.index = index as usize;
+                self.send_view();
+            }
+            Message::ColormapChanged(colormap) => {
+                self.colormap = colormap;
+                let _ = self
+                    .custom_event_proxy
+                    .send_event(CustomEvent::UpdateColormap(colormap));
+            }
+            Message::ParticleGlyphChanged(glyph) => {
+                self.particle_style.glyph = glyph;
+                self.send_particle_style();
+            }
+            Message::ParticleRadiusChanged(radius) => {
+                if let Some(radius) = radius.parse::<f32>().ok().filter(|&radius| radius > 0.0) {
+                    self.particle_style.radius = radius;
+                    self.send_particle_style();
+                }
+                self.particle_radius_raw = radius;
+            }
+            Message::VectorsToggled(enabled) => {
+                self.vector_overlay.enabled = enabled;
+                self.send_vector_overlay();
+            }
+            Message::VectorSpacingChanged(spacing) => {
+                self.vector_overlay.spacing = spacing as usize;
+                self.send_vector_overlay();
+            }
+            Message::LicToggled(lic) => {
+                self.vector_overlay.lic = lic;
+                self.send_vector_overlay();
+            }
+            Message::ElasticMapChanged(map) => {
+                self.elastic_map = map;
+                let _ = self
+                    .custom_event_proxy
+                    .send_event(CustomEvent::UpdateElasticMap(map));
+            }
+            Message::SpectrumFieldChanged(field) => {
+                self.analyses.spectrum = field;
+                let _ = self
+                    .custom_event_proxy
+                    .send_event(CustomEvent::UpdateSpectrum(field));
+            }
+            Message::ElasticAnalysisToggled(enabled) => self.analyses.elastic = enabled,
+            Message::CorrelationAnalysisToggled(enabled) => self.analyses.correlation = enabled,
+            Message::TrackingAnalysisToggled(enabled) => self.analyses.tracking = enabled,
+            Message::CoarseningAnalysisToggled(enabled) => self.analyses.coarsening = enabled,
+            Message::TrailsToggled(enabled) => {
+                self.trails = enabled;
+                let _ = self
+                    .custom_event_proxy
+                    .send_event(CustomEvent::UpdateTrails(enabled));
+            }
+            Message::PitchLinesToggled(enabled) => {
+                self.pitch_lines = enabled;
+                self.send_pitch_lines();
+            }
+            Message::ParameterChanged(index, raw) => {
+                if let Some((_, value)) = self.parameter_inputs.get_mut(index) {
+                    *value = raw;
+                    self.send_parameter(index);
+                }
+            }
+            Message::ParameterToggled(index, enabled) => {
+                if let Some((_, value)) = self.parameter_inputs.get_mut(index) {
+                    *value = if enabled { "1" } else { "0" }.to_owned();
+                    self.send_parameter(index);
+                }
+            }
+            Message::LayerVisibilityChanged(kind, visible) => {
+                self.layer_stack.get_mut(kind).visible = visible;
+                self.send_layers();
+            }
+            Message::LayerOpacityChanged(kind, opacity) => {
+                self.layer_stack.get_mut(kind).opacity = opacity;
+                self.send_layers();
+            }
+            Message::LayerBlendChanged(kind, blend) => {
+                self.layer_stack.get_mut(kind).blend = blend;
+                self.send_layers();
+            }
+            Message::DirectorDisplayChanged(display) => {
+                self.view.display = display;
+                self.send_view();
+            }
             Message::StartStop(value) => {
-                if let Some(dimentions) = &self.dimentions {
+                if let (Some(dimentions), Some(algorithm), Ok(initial_state)) =
+                    (self.dimentions, &self.selected_algorithm, &self.initial_state)
+                {
+                    let output_directory = Some(PathBuf::from(&self.output_path)).filter(|p| p.is_dir());
+                    let settings = RunSettings {
+                        algorithm: algorithm.clone(),
+                        lattice_size: dimentions,
+                        lattice_layers: self.layers.unwrap_or(1),
+                        initial_state: initial_state.clone(),
+                        conditions: self.conditions.clone(),
+                        protocols: self.protocols.clone().unwrap_or_default(),
+                        parameters: self.parameters(),
+                        analyses: self.analyses.clone(),
+                        overlays: Overlays {
+                            elastic_map: self.elastic_map,
+                            trails: self.trails,
+                        },
+                        output_directory,
+                    };
+                    if !value {
+                        self.history = Table::new(HISTORY_TABLE);
+                        self.history_stride = 1;
+                        self.run_warnings.clear();
+                    }
                     let _ = self
                         .custom_event_proxy
-                        .send_event(CustomEvent::StartStop(value, dimentions.clone()));
+                        .send_event(CustomEvent::StartStop(value, settings));
+                    // The replica typed in before starting was not sent to any worker yet.
+                    if let (false, Some(replica)) = (value, parse_replica(&self.replica_raw)) {
+                        let _ = self
+                            .custom_event_proxy
+                            .send_event(CustomEvent::SetParameter("displayed_replica".to_owned(), replica));
+                    }
                 }
             }
             Message::UpdateSharedData(ctx) => {
                 let ctx = ctx.lock();
                 self.button_state = ctx.general_params.read().unwrap().algorithm_started;
             }
+            Message::InitialStateKindChanged(kind) => {
+                self.initial_state_kind = kind;
+                self.initial_state_arguments = initial_state_example(kind).to_owned();
+                self.parse_initial_state();
+            }
+            Message::InitialStateArgumentsChanged(arguments) => {
+                self.initial_state_arguments = arguments;
+                self.parse_initial_state();
+            }
+            Message::ReplicaChanged(replica) => {
+                if let Some(value) = parse_replica(&replica) {
+                    let _ = self
+                        .custom_event_proxy
+                        .send_event(CustomEvent::SetParameter("displayed_replica".to_owned(), value));
+                }
+                self.replica_raw = replica;
+            }
+            Message::ProtocolsChanged(protocols) => {
+                self.protocols = Protocol::parse_list(&protocols);
+                self.protocols_raw = protocols;
+            }
+            Message::PickInitialStateFile => {
+                if let Some(path) = FileDialog::new()
+                    .add_filter("Angles", &["png", "npy"])
+                    .pick_file()
+                {
+                    self.initial_state_arguments = path.display().to_string();
+                    self.parse_initial_state();
+                }
+            }
+            Message::SweepAxesChanged(axes) => self.sweep_axes = axes,
+            Message::BatchIterationsChanged(iterations) => self.batch_iterations_raw = iterations,
+            Message::RunBatch => match self.batch_config() {
+                Ok(config) => {
+                    self.batch_running = true;
+                    self.batch_status = format!("Starting {} jobs", config.jobs().len());
+                    let _ = self
+                        .custom_event_proxy
+                        .send_event(CustomEvent::RunBatch(config));
+                }
+                Err(error) => self.batch_status = error,
+            },
+            Message::BatchStatus(status, finished) => {
+                self.batch_status = status;
+                self.batch_running = !finished;
+            }
+            Message::RunStatus(status) => self.run_status = status,
+            Message::WarningsUpdated(warnings) => self.run_warnings = warnings,
+            Message::RunStatistics(statistics) => self.run_statistics = statistics,
+            Message::StatusUpdated(sample) => {
+                self.run_status = match sample.get("time") {
+                    Some(time) => format!("Iteration {}, time {time:.4}", sample.iteration),
+                    None => format!("Iteration {}", sample.iteration),
+                };
+                self.record_sample(&sample);
+            }
+            Message::TablesUpdated(tables) => {
+                self.tables = tables;
+                self.update_plot();
+            }
+            Message::PlotSeriesChanged(series) => {
+                self.plot_series = Some(series);
+                self.update_plot();
+            }
+            Message::BoundaryKindChanged(edge, kind) => {
+                self.boundary_kinds[edge] = kind;
+                self.boundary_arguments[edge] = boundary_example(kind).to_owned();
+                self.parse_boundaries();
+            }
+            Message::BoundaryArgumentsChanged(edge, arguments) => {
+                self.boundary_arguments[edge] = arguments;
+                self.parse_boundaries();
+            }
+            Message::MaskToolChanged(tool) => self.mask_tool = tool,
+            Message::BrushRadiusChanged(radius) => self.brush_radius_raw = radius,
+            Message::SourceRateChanged(rate) => self.source_rate_raw = rate,
+            Message::ClearSources => {
+                self.conditions.sources = None;
+                self.send_conditions();
+            }
+            Message::LoadMask => {
+                if let Some(path) = FileDialog::new().add_filter("Mask", &["png"]).pick_file() {
+                    match Mask::load(&path) {
+                        Ok(mask) => {
+                            self.conditions.mask = Some(mask);
+                            self.mask_error = None;
+                            self.send_conditions();
+                        }
+                        Err(error) => self.mask_error = Some(error),
+                    }
+                }
+            }
+            Message::SaveMask => {
+                if let Some(mask) = &mut self.conditions.mask
+                    && let Some(path) = FileDialog::new().add_filter("Mask", &["png"]).save_file()
+                {
+                    self.mask_error = mask.save(&path).err();
+                }
+            }
+            Message::ClearMask => {
+                self.conditions.mask = None;
+                self.mask_error = None;
+                self.send_conditions();
+            }
+            Message::DisplayPressed(press) if self.mask_tool == "seed" => self.seed_perturbation(press),
+            Message::DisplayPressed(press) if matches!(self.mask_tool, "source" | "sink") => {
+                self.paint_sources(press)
+            }
+            Message::DisplayPressed(press) => self.paint_mask(press),
+            Message::FieldAngleChanged(angle) => {
+                self.field_angle_raw = angle;
+                self.update_field();
+            }
+            Message::FieldMagnitudeChanged(magnitude) => {
+                self.field_magnitude_raw = magnitude;
+                self.update_field();
+            }
+            Message::WaveformKindChanged(kind) => {
+                self.waveform_kind = kind;
+                self.waveform_arguments = waveform_example(kind).to_owned();
+                self.update_field();
+            }
+            Message::WaveformArgumentsChanged(arguments) => {
+                self.waveform_arguments = arguments;
+                self.update_field();
+            }
+            Message::FieldProfileKindChanged(kind) => {
+                self.field_profile_kind = kind;
+                self.field_profile_arguments = field_profile_example(kind).to_owned();
+                self.update_field();
+            }
+            Message::FieldProfileArgumentsChanged(arguments) => {
+                self.field_profile_arguments = arguments;
+                self.update_field();
+            }
         }
 
         Task::none()
     }
 
-    fn view(&self) -> Element<Message, Theme, Renderer> {
+    fn view(&self) -> Element<'_, Message, Theme, Renderer> {
         let interactive_interface =
             container(column![self.static_interface(), self.dynamic_interface()].spacing(10))
                 .width(FillPortion(1));
@@ -1 +1,2 @@
 pub mod controls;
+pub mod plot;
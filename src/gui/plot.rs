@@ -0,0 +1,142 @@
+use iced_widget::image::Handle;
+
+pub const PLOT_WIDTH: u32 = 360;
+pub const PLOT_HEIGHT: u32 = 200;
+/// Pixels between the frame and the edge of the image.
+const MARGIN: i32 = 6;
+const BACKGROUND: [u8; 4] = [20, 20, 24, 255];
+const FRAME: [u8; 4] = [150, 150, 150, 255];
+const GRID: [u8; 4] = [55, 55, 60, 255];
+const LINE: [u8; 4] = [255, 190, 60, 255];
+/// Color of the overlaid curve, e.g. a fit.
+const OVERLAY: [u8; 4] = [90, 200, 255, 255];
+
+/// A curve rasterised for an image widget, with the ranges its axes span. On log axes the
+/// ranges are in decades.
+#[derive(Debug, Clone)]
+pub struct Plot {
+    pub handle: Handle,
+    pub x_range: (f64, f64),
+    pub y_range: (f64, f64),
+    pub log_axes: bool,
+    /// Slope of the overlaid curve between its ends, the exponent of a power law on log axes.
+    pub overlay_slope: Option<f64>,
+}
+
+impl Plot {
+    /// Draws `y` against `x` as a polyline, with `overlay` over it, e.g. a fit, and optionally
+    /// `log10` of both axes. Points that are not finite, or not positive on log axes, are skipped.
+    /// `None` when fewer than two points of `y` remain.
+    pub fn new(x: &[f64], y: &[f64], overlay: Option<&[f64]>, log_axes: bool) -> Option<Plot> {
+        let transform = |value: f64| if log_axes { value.log10() } else { value };
+        let curve = |y: &[f64]| -> Vec<(f64, f64)> {
+            x.iter()
+                .zip(y)
+                .map(|(&x, &y)| (transform(x), transform(y)))
+                .filter(|(x, y)| x.is_finite() && y.is_finite())
+                .collect()
+        };
+        let points = curve(y);
+        if points.len() < 2 {
+            return None;
+        }
+        let overlay = overlay.map(curve).unwrap_or_default();
+        let overlay_slope = match (overlay.first(), overlay.last()) {
+            (Some(first), Some(last)) if last.0 > first.0 => Some((last.1 - first.1) / (last.0 - first.0)),
+            _ => None,
+        };
+        let x_range = range(points.iter().map(|p| p.0));
+        let y_range = range(points.iter().chain(&overlay).map(|p| p.1));
+
+        let mut canvas = Canvas::new(PLOT_WIDTH, PLOT_HEIGHT);
+        let (left, bottom) = (MARGIN, PLOT_HEIGHT as i32 - 1 - MARGIN);
+        let (right, top) = (PLOT_WIDTH as i32 - 1 - MARGIN, MARGIN);
+        for quarter in 1..4 {
+            let x = left + (right - left) * quarter / 4;
+            let y = top + (bottom - top) * quarter / 4;
+            canvas.line((x, top), (x, bottom), GRID);
+            canvas.line((left, y), (right, y), GRID);
+        }
+        canvas.line((left, top), (right, top), FRAME);
+        canvas.line((left, bottom), (right, bottom), FRAME);
+        canvas.line((left, top), (left, bottom), FRAME);
+        canvas.line((right, top), (right, bottom), FRAME);
+
+        let pixel = |(x, y): (f64, f64)| {
+            let u = (x - x_range.0) / (x_range.1 - x_range.0);
+            let v = (y - y_range.0) / (y_range.1 - y_range.0);
+            (
+                left + (u * (right - left) as f64).round() as i32,
+                bottom - (v * (bottom - top) as f64).round() as i32,
+            )
+        };
+        for pair in points.windows(2) {
+            canvas.line(pixel(pair[0]), pixel(pair[1]), LINE);
+        }
+        for pair in overlay.windows(2) {
+            canvas.line(pixel(pair[0]), pixel(pair[1]), OVERLAY);
+        }
+
+        Some(Plot {
+            handle: Handle::from_rgba(PLOT_WIDTH, PLOT_HEIGHT, canvas.pixels),
+            x_range,
+            y_range,
+            log_axes,
+            overlay_slope,
+        })
+    }
+}
+
+/// Smallest and largest value, widened when they coincide.
+fn range(values: impl Iterator<Item = f64>) -> (f64, f64) {
+    let (low, high) = values.fold((f64::INFINITY, f64::NEG_INFINITY), |(low, high), value| {
+        (low.min(value), high.max(value))
+    });
+    if low < high { (low, high) } else { (low - 0.5, low + 0.5) }
+}
+
+/// RGBA pixels, row 0 at the top.
+struct Canvas {
+    width: u32,
+    height: u32,
+    pixels: Vec<u8>,
+}
+
+impl Canvas {
+    fn new(width: u32, height: u32) -> Self {
+        Self {
+            width,
+            height,
+            pixels: BACKGROUND.repeat((width * height) as usize),
+        }
+    }
+
+    fn set(&mut self, x: i32, y: i32, color: [u8; 4]) {
+        if x >= 0 && y >= 0 && (x as u32) < self.width && (y as u32) < self.height {
+            let index = 4 * (y as usize * self.width as usize + x as usize);
+            self.pixels[index..index + 4].copy_from_slice(&color);
+        }
+    }
+
+    /// Bresenham line between two pixels, both ends included.
+    fn line(&mut self, (mut x, mut y): (i32, i32), (x1, y1): (i32, i32), color: [u8; 4]) {
+        let (dx, dy) = ((x1 - x).abs(), -(y1 - y).abs());
+        let (step_x, step_y) = ((x1 - x).signum(), (y1 - y).signum());
+        let mut error = dx + dy;
+        loop {
+            self.set(x, y, color);
+            if x == x1 && y == y1 {
+                break;
+            }
+            let doubled = 2 * error;
+            if doubled >= dy {
+                error += dy;
+                x += step_x;
+            }
+            if doubled <= dx {
+                error += dx;
+                y += step_y;
+            }
+        }
+    }
+}
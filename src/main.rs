@@ -1,19 +1,75 @@
 mod algorithm_processor;
+mod algorithms;
+mod analysis;
 mod application;
+mod batch;
 mod gui;
+mod plugins;
 mod rendering;
 mod widgets;
 
-use crate::application::Simula;
+use crate::application::{AlgorithmCatalog, Simula};
+use crate::batch::BatchConfig;
 use application::CustomEvent;
 use iced_winit::winit;
+use std::path::Path;
 use winit::event_loop::EventLoop;
 
 pub fn main() -> Result<(), winit::error::EventLoopError> {
     tracing_subscriber::fmt::init();
 
+    let args: Vec<String> = std::env::args().collect();
+    if let Some(position) = args.iter().position(|arg| arg == "--batch") {
+        let Some(config_path) = args.get(position + 1) else {
+            eprintln!("Usage: simula --batch <config file>");
+            std::process::exit(2);
+        };
+        run_batch(Path::new(config_path));
+        return Ok(());
+    }
+
     let event_loop: EventLoop<CustomEvent> = EventLoop::with_user_event().build()?;
     let proxy = event_loop.create_proxy();
     let mut app = Simula::new(proxy.clone());
     event_loop.run_app(&mut app)
 }
+
+fn run_batch(config_path: &Path) {
+    let catalog = AlgorithmCatalog::with_plugins();
+    for plugin in &catalog.plugins_loaded {
+        println!("{plugin}");
+    }
+    for error in &catalog.plugin_errors {
+        println!("Warning: {error}");
+    }
+    let result = BatchConfig::load(config_path).and_then(|config| {
+        for warning in batch::batch_warnings(&config, &catalog) {
+            println!("Warning: {warning}");
+        }
+        println!(
+            "Running {} jobs with {} threads into {}",
+            config.jobs().len(),
+            config.threads,
+            config.output.display()
+        );
+        batch::run_batch(&config, &catalog, |finished, total| {
+            println!("{finished}/{total} jobs finished");
+        })
+    });
+
+    match result {
+        Ok(report) => {
+            print!("{}", batch::format_summary(&report.summary));
+            for failure in &report.failures {
+                eprintln!("Job failed: {failure}");
+            }
+            if !report.failures.is_empty() {
+                std::process::exit(1);
+            }
+        }
+        Err(error) => {
+            eprintln!("Batch failed: {error}");
+            std::process::exit(1);
+        }
+    }
+}
@@ -0,0 +1,122 @@
+//! The C ABI between Simula and plugin libraries, mirrored by `plugins/simula_plugin.h`.
+use std::ffi::{c_char, c_void};
+
+/// Bumped whenever the layout of anything below changes.
+pub const PLUGIN_ABI_VERSION: u32 = 1;
+
+/// Symbol every plugin exports, `const SimulaPlugin *simula_plugin(void)`.
+pub const PLUGIN_ENTRY_POINT: &std::ffi::CStr = c"simula_plugin";
+
+/// Most grids a plugin may return from one step.
+pub const MAX_PLUGIN_OUTPUTS: usize = 8;
+
+/// Most observables a plugin may report for one frame.
+pub const MAX_PLUGIN_OBSERVABLES: usize = 32;
+
+/// Director angles of a 2D lattice, one value per site.
+pub const GRID_ORIENTED: u32 = 0;
+/// One scalar per site of a 2D lattice.
+pub const GRID_SCALAR: u32 = 1;
+/// `(x, y)` per site of a 2D lattice.
+pub const GRID_VECTOR: u32 = 2;
+/// Unit `(x, y, z)` directors per site of a 3D lattice.
+pub const GRID_DIRECTOR: u32 = 3;
+
+/// Floats per site of a grid kind, `None` for kinds this version does not know.
+pub fn grid_components(kind: u32) -> Option<usize> {
+    match kind {
+        GRID_ORIENTED | GRID_SCALAR => Some(1),
+        GRID_VECTOR => Some(2),
+        GRID_DIRECTOR => Some(3),
+        _ => None,
+    }
+}
+
+/// A lattice field lent across the boundary. Sites are row-major with row 0 at the bottom,
+/// layer after layer, so `values` holds `rows * cols * layers * components` floats.
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct PluginGrid {
+    pub kind: u32,
+    pub rows: usize,
+    pub cols: usize,
+    /// 1 for 2D grids.
+    pub layers: usize,
+    pub values: *const f32,
+}
+
+impl PluginGrid {
+    pub const EMPTY: PluginGrid = PluginGrid {
+        kind: GRID_ORIENTED,
+        rows: 0,
+        cols: 0,
+        layers: 0,
+        values: std::ptr::null(),
+    };
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct PluginParameter {
+    pub name: *const c_char,
+    pub value: f32,
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct PluginObservable {
+    pub name: *const c_char,
+    pub value: f64,
+}
+
+impl PluginObservable {
+    pub const EMPTY: PluginObservable = PluginObservable {
+        name: std::ptr::null(),
+        value: 0.0,
+    };
+}
+
+pub type PluginState = *mut c_void;
+
+/// What `simula_plugin` returns. It and every string it points to must stay valid while the
+/// library is loaded; Simula never unloads plugins.
+#[repr(C)]
+pub struct PluginDescriptor {
+    pub abi_version: u32,
+    /// Shown in the "Select algorithm" list.
+    pub name: *const c_char,
+    /// `1 << kind` for every grid kind the plugin reads.
+    pub inputs: u32,
+    /// `1 << kind` for every grid kind the plugin writes.
+    pub outputs: u32,
+    /// Tunable parameters with their default values.
+    pub parameters: *const PluginParameter,
+    pub parameter_count: usize,
+    /// A new simulation state, called once per run.
+    pub create: extern "C" fn() -> PluginState,
+    pub destroy: extern "C" fn(PluginState),
+    /// Reads the grids of the previous step and points up to `capacity` entries of `outputs` at
+    /// grids owned by the plugin, valid until the next call on this state. Returns how many.
+    pub compute: extern "C" fn(
+        state: PluginState,
+        iteration: u64,
+        inputs: *const PluginGrid,
+        input_count: usize,
+        outputs: *mut PluginGrid,
+        capacity: usize,
+    ) -> usize,
+    pub set_parameter: Option<extern "C" fn(state: PluginState, name: *const c_char, value: f32)>,
+    pub seed: Option<extern "C" fn(state: PluginState, seed: u64)>,
+    /// Like `compute`, but fills named measurements of the grids it is given.
+    pub observables: Option<
+        extern "C" fn(
+            state: PluginState,
+            grids: *const PluginGrid,
+            grid_count: usize,
+            observables: *mut PluginObservable,
+            capacity: usize,
+        ) -> usize,
+    >,
+}
+
+pub type PluginEntryPoint = extern "C" fn() -> *const PluginDescriptor;
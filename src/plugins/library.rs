@@ -0,0 +1,95 @@
+use std::ffi::{CStr, CString, c_char, c_void};
+use std::path::Path;
+
+/// A dynamic library opened with the platform loader. It is never closed, so pointers into it
+/// stay valid for the rest of the process.
+pub struct Library {
+    handle: *mut c_void,
+}
+
+impl Library {
+    pub fn open(path: &Path) -> Result<Library, String> {
+        let handle = sys::open(path)?;
+        if handle.is_null() {
+            return Err(format!("could not load {}: {}", path.display(), sys::last_error()));
+        }
+        Ok(Library { handle })
+    }
+
+    /// Address of the exported symbol `name`.
+    pub fn symbol(&self, name: &CStr) -> Result<*mut c_void, String> {
+        let address = unsafe { sys::symbol(self.handle, name.as_ptr()) };
+        if address.is_null() {
+            return Err(format!("missing symbol {}: {}", name.to_string_lossy(), sys::last_error()));
+        }
+        Ok(address)
+    }
+}
+
+/// Borrowed C string as Rust text, `None` for null pointers or invalid UTF-8.
+///
+/// # Safety
+///
+/// `text` is null or points to a NUL-terminated string that stays valid and unchanged for `'a`.
+pub unsafe fn c_text<'a>(text: *const c_char) -> Option<&'a str> {
+    if text.is_null() {
+        return None;
+    }
+    // SAFETY: non-null, and the caller guarantees the string outlives 'a.
+    unsafe { CStr::from_ptr(text) }.to_str().ok()
+}
+
+#[cfg(unix)]
+mod sys {
+    use super::*;
+    use std::ffi::c_int;
+    use std::os::unix::ffi::OsStrExt;
+
+    const RTLD_NOW: c_int = 2;
+
+    unsafe extern "C" {
+        fn dlopen(filename: *const c_char, flags: c_int) -> *mut c_void;
+        fn dlsym(handle: *mut c_void, symbol: *const c_char) -> *mut c_void;
+        fn dlerror() -> *const c_char;
+    }
+
+    pub fn open(path: &Path) -> Result<*mut c_void, String> {
+        let path = CString::new(path.as_os_str().as_bytes()).map_err(|e| e.to_string())?;
+        Ok(unsafe { dlopen(path.as_ptr(), RTLD_NOW) })
+    }
+
+    pub unsafe fn symbol(handle: *mut c_void, name: *const c_char) -> *mut c_void {
+        unsafe { dlsym(handle, name) }
+    }
+
+    pub fn last_error() -> String {
+        // SAFETY: dlerror returns null or a message valid until the next dl call, copied here.
+        unsafe { c_text(dlerror()) }.unwrap_or("unknown error").to_owned()
+    }
+}
+
+#[cfg(windows)]
+mod sys {
+    use super::*;
+
+    #[link(name = "kernel32")]
+    unsafe extern "system" {
+        fn LoadLibraryA(filename: *const c_char) -> *mut c_void;
+        fn GetProcAddress(module: *mut c_void, name: *const c_char) -> *mut c_void;
+        fn GetLastError() -> u32;
+    }
+
+    pub fn open(path: &Path) -> Result<*mut c_void, String> {
+        let path = path.to_str().ok_or("plugin path is not valid UTF-8")?;
+        let path = CString::new(path).map_err(|e| e.to_string())?;
+        Ok(unsafe { LoadLibraryA(path.as_ptr()) })
+    }
+
+    pub unsafe fn symbol(handle: *mut c_void, name: *const c_char) -> *mut c_void {
+        unsafe { GetProcAddress(handle, name) }
+    }
+
+    pub fn last_error() -> String {
+        format!("error code {}", unsafe { GetLastError() })
+    }
+}
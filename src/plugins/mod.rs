@@ -0,0 +1,8 @@
+pub mod abi;
+pub mod library;
+pub mod plugin;
+
+pub use plugin::*;
+
+/// Where plugin libraries are looked for, relative to the working directory.
+pub const PLUGIN_DIRECTORY: &str = "plugins";
@@ -0,0 +1,243 @@
+use crate::algorithm_processor::*;
+use crate::plugins::abi::*;
+use crate::plugins::library::*;
+use std::ffi::CString;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+/// Descriptor of a loaded plugin, shared by every run of it.
+#[derive(Clone, Copy)]
+struct PluginHandle(&'static PluginDescriptor);
+
+// The descriptor is immutable and lives as long as its library, which is never closed.
+unsafe impl Send for PluginHandle {}
+unsafe impl Sync for PluginHandle {}
+
+/// One run of a plugin algorithm, owning the state the plugin created for it.
+pub struct PluginAlgorithm {
+    descriptor: &'static PluginDescriptor,
+    state: PluginState,
+    parameters: Vec<Parameter>,
+}
+
+// Plugins are required to let their states move to the worker thread.
+unsafe impl Send for PluginAlgorithm {}
+
+impl PluginAlgorithm {
+    fn new(handle: PluginHandle) -> Self {
+        let descriptor = handle.0;
+        Self {
+            descriptor,
+            state: (descriptor.create)(),
+            parameters: default_parameters(descriptor).unwrap_or_default(),
+        }
+    }
+}
+
+impl Drop for PluginAlgorithm {
+    fn drop(&mut self) {
+        (self.descriptor.destroy)(self.state);
+    }
+}
+
+impl AlgorithmComputation for PluginAlgorithm {
+    fn compute(&mut self, iteration: usize, data: &[DataType]) -> Vec<DataType> {
+        let inputs = lend(data);
+        let mut outputs = [PluginGrid::EMPTY; MAX_PLUGIN_OUTPUTS];
+        let count = (self.descriptor.compute)(
+            self.state,
+            iteration as u64,
+            inputs.as_ptr(),
+            inputs.len(),
+            outputs.as_mut_ptr(),
+            outputs.len(),
+        );
+        outputs[..count.min(outputs.len())].iter().filter_map(take).collect()
+    }
+
+    fn parameters(&self) -> Vec<Parameter> {
+        self.parameters.clone()
+    }
+
+    fn set_parameter(&mut self, name: &str, value: f32) {
+        let (Some(parameter), Some(set_parameter), Ok(c_name)) = (
+            self.parameters.iter_mut().find(|p| p.name == name),
+            self.descriptor.set_parameter,
+            CString::new(name),
+        ) else {
+            return;
+        };
+        parameter.value = value;
+        set_parameter(self.state, c_name.as_ptr(), value);
+    }
+
+    fn seed(&mut self, seed: u64) {
+        if let Some(plugin_seed) = self.descriptor.seed {
+            plugin_seed(self.state, seed);
+        }
+    }
+
+    /// Plugins that read director grids are handed the 3D lattice.
+    fn supports_layers(&self) -> bool {
+        self.descriptor.inputs & (1 << GRID_DIRECTOR) != 0
+    }
+
+    fn observables(&self, data: &[DataType]) -> Vec<(String, f64)> {
+        let Some(plugin_observables) = self.descriptor.observables else {
+            return Vec::new();
+        };
+        let grids = lend(data);
+        let mut observables = [PluginObservable::EMPTY; MAX_PLUGIN_OBSERVABLES];
+        let count = plugin_observables(
+            self.state,
+            grids.as_ptr(),
+            grids.len(),
+            observables.as_mut_ptr(),
+            observables.len(),
+        );
+        observables[..count.min(observables.len())]
+            .iter()
+            // SAFETY: the plugin points names at strings valid until its next call, copied here.
+            .filter_map(|observable| Some((unsafe { c_text(observable.name) }?.to_owned(), observable.value)))
+            .collect()
+    }
+}
+
+/// `count` if that many `T` fit in the `isize::MAX` bytes a slice may span.
+fn slice_len<T>(count: usize) -> Option<usize> {
+    count
+        .checked_mul(std::mem::size_of::<T>())
+        .filter(|&bytes| bytes <= isize::MAX as usize)
+        .map(|_| count)
+}
+
+fn default_parameters(descriptor: &PluginDescriptor) -> Result<Vec<Parameter>, String> {
+    if descriptor.parameters.is_null() {
+        return Ok(Vec::new());
+    }
+    let count = slice_len::<PluginParameter>(descriptor.parameter_count)
+        .ok_or_else(|| format!("{} parameters do not fit in memory", descriptor.parameter_count))?;
+    // SAFETY: the descriptor points at `parameter_count` entries that live as long as the
+    // library, which is never closed, and their size was checked above.
+    let parameters = unsafe { std::slice::from_raw_parts(descriptor.parameters, count) };
+    Ok(parameters
+        .iter()
+        // SAFETY: parameter names live as long as the descriptor.
+        .filter_map(|parameter| Some(Parameter::new(unsafe { c_text(parameter.name) }?, parameter.value)))
+        .collect())
+}
+
+/// Views of the grids in `data` for the plugin; particles have no grid form and are left out.
+fn lend(data: &[DataType]) -> Vec<PluginGrid> {
+    data.iter()
+        .filter_map(|datum| {
+            let (kind, values, (rows, cols, layers)) = match datum {
+                DataType::OrientedGrid(angles, (rows, cols)) => (GRID_ORIENTED, angles.as_slice(), (*rows, *cols, 1)),
+                DataType::ScalarGrid(values, (rows, cols)) => (GRID_SCALAR, values.as_slice(), (*rows, *cols, 1)),
+                DataType::VectorGrid(vectors, (rows, cols)) => (GRID_VECTOR, vectors.as_flattened(), (*rows, *cols, 1)),
+                DataType::DirectorField(directors, dimensions) => (GRID_DIRECTOR, directors.as_flattened(), *dimensions),
+                DataType::Particles(_) => return None,
+            };
+            Some(PluginGrid {
+                kind,
+                rows,
+                cols,
+                layers,
+                values: values.as_ptr(),
+            })
+        })
+        .collect()
+}
+
+/// Copy of a grid the plugin returned, `None` when it is null, of an unknown kind or too large
+/// to address.
+fn take(grid: &PluginGrid) -> Option<DataType> {
+    let components = grid_components(grid.kind)?;
+    if grid.values.is_null() || (grid.layers != 1 && grid.kind != GRID_DIRECTOR) {
+        return None;
+    }
+    let len = grid
+        .rows
+        .checked_mul(grid.cols)?
+        .checked_mul(grid.layers)?
+        .checked_mul(components)
+        .and_then(slice_len::<f32>)?;
+    // SAFETY: the plugin points `values` at `len` floats it owns until its next call on this
+    // state, and they are copied out before that.
+    let values = unsafe { std::slice::from_raw_parts(grid.values, len) };
+    let dimensions = (grid.rows, grid.cols);
+    Some(match grid.kind {
+        GRID_ORIENTED => DataType::OrientedGrid(values.to_vec(), dimensions),
+        GRID_SCALAR => DataType::ScalarGrid(values.to_vec(), dimensions),
+        GRID_VECTOR => DataType::VectorGrid(values.chunks_exact(2).map(|v| [v[0], v[1]]).collect(), dimensions),
+        _ => DataType::DirectorField(
+            values.chunks_exact(3).map(|v| [v[0], v[1], v[2]]).collect(),
+            (grid.rows, grid.cols, grid.layers),
+        ),
+    })
+}
+
+/// Names of the grid kinds set in a `1 << kind` mask.
+fn grid_kinds(mask: u32) -> String {
+    let names: Vec<&str> = [
+        (GRID_ORIENTED, "oriented"),
+        (GRID_SCALAR, "scalar"),
+        (GRID_VECTOR, "vector"),
+        (GRID_DIRECTOR, "director"),
+    ]
+    .iter()
+    .filter(|(kind, _)| mask & (1 << kind) != 0)
+    .map(|(_, name)| *name)
+    .collect();
+    if names.is_empty() { "nothing".to_owned() } else { names.join(", ") }
+}
+
+/// The algorithm of the plugin library at `path` and a line describing it.
+fn load_plugin(path: &Path) -> Result<(Algorithm, String), String> {
+    let library = Library::open(path)?;
+    let entry = library.symbol(PLUGIN_ENTRY_POINT)?;
+    // SAFETY: plugins export `simula_plugin` with the `PluginEntryPoint` signature.
+    let entry: PluginEntryPoint = unsafe { std::mem::transmute(entry) };
+    // SAFETY: the descriptor is null or valid while the library is loaded, and it is never closed.
+    let descriptor = unsafe { entry().as_ref() }.ok_or("simula_plugin returned null")?;
+    if descriptor.abi_version != PLUGIN_ABI_VERSION {
+        return Err(format!(
+            "built for plugin ABI {}, this Simula uses {PLUGIN_ABI_VERSION}",
+            descriptor.abi_version
+        ));
+    }
+    // SAFETY: the name lives as long as the descriptor.
+    let name = unsafe { c_text(descriptor.name) }.ok_or("the plugin name is null or not UTF-8")?;
+    let parameters: Vec<String> = default_parameters(descriptor)?
+        .iter()
+        .map(|p| format!("{} = {}", p.name, p.value))
+        .collect();
+    let description = format!(
+        "Loaded plugin {name} from {}: reads {}, writes {}, parameters [{}]",
+        path.display(),
+        grid_kinds(descriptor.inputs),
+        grid_kinds(descriptor.outputs),
+        parameters.join(", ")
+    );
+    let handle = PluginHandle(descriptor);
+    let algorithm = Algorithm::new(name, Arc::new(move || Box::new(PluginAlgorithm::new(handle))));
+    Ok((algorithm, description))
+}
+
+/// Every plugin library in `directory`, loaded with a line describing it or refused with the
+/// reason; a missing directory holds none.
+pub fn load_plugins(directory: &Path) -> Vec<Result<(Algorithm, String), String>> {
+    let Ok(entries) = fs::read_dir(directory) else {
+        return Vec::new();
+    };
+    let mut paths: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.extension().is_some_and(|extension| extension == std::env::consts::DLL_EXTENSION))
+        .collect();
+    paths.sort();
+    paths
+        .iter()
+        .map(|path| load_plugin(path).map_err(|error| format!("Skipping plugin {}: {error}", path.display())))
+        .collect()
+}
@@ -0,0 +1,261 @@
+use crate::rendering::wgpu_wrapper::WGPUWrapper;
+use crate::rendering::*;
+
+/// Layout of `Uniforms` in `composite.wgsl`.
+#[derive(Debug, Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+#[repr(C)]
+struct CompositeUniforms {
+    opacity: f32,
+    blend: u32,
+    _padding: [u32; 2],
+}
+
+/// Offscreen texture a layer is drawn into, with what samples it during compositing.
+struct LayerTarget {
+    view: TextureView,
+    bind_group: BindGroup,
+    uniform_buffer: Buffer,
+}
+
+/// Draws every layer into its own texture and blends them into the render texture in order.
+pub struct Compositor {
+    /// One per blend mode, in `BLEND_MODES` order.
+    pipelines: Vec<RenderPipeline>,
+    targets: Vec<LayerTarget>,
+    vertex_buffer: Buffer,
+    index_buffer: Buffer,
+}
+
+impl Compositor {
+    pub fn new(webgpu_wrapper: &WGPUWrapper, size: Extent3d) -> Compositor {
+        let device = &webgpu_wrapper.device;
+        let shader = device.create_shader_module(iced_wgpu::wgpu::include_wgsl!("../shader/composite.wgsl"));
+
+        let bind_group_layout = device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+            label: Some("composite bind group layout"),
+            entries: &[
+                BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: ShaderStages::FRAGMENT,
+                    ty: BindingType::Texture {
+                        sample_type: TextureSampleType::Float { filterable: true },
+                        view_dimension: TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+                BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: ShaderStages::FRAGMENT,
+                    ty: BindingType::Sampler(SamplerBindingType::Filtering),
+                    count: None,
+                },
+                BindGroupLayoutEntry {
+                    binding: 2,
+                    visibility: ShaderStages::FRAGMENT,
+                    ty: BindingType::Buffer {
+                        ty: BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+            ],
+        });
+        // Layers have the size of the render texture, so the nearest texel is exact.
+        let sampler = device.create_sampler(&SamplerDescriptor {
+            address_mode_u: AddressMode::ClampToEdge,
+            address_mode_v: AddressMode::ClampToEdge,
+            address_mode_w: AddressMode::ClampToEdge,
+            mag_filter: FilterMode::Nearest,
+            min_filter: FilterMode::Nearest,
+            mipmap_filter: FilterMode::Nearest,
+            ..Default::default()
+        });
+
+        let targets = LAYER_KINDS
+            .iter()
+            .map(|kind| {
+                let texture = device.create_texture(&TextureDescriptor {
+                    label: Some(&format!("{kind} layer")),
+                    size,
+                    mip_level_count: 1,
+                    sample_count: 1,
+                    dimension: TextureDimension::D2,
+                    view_formats: &[TextureFormat::Bgra8UnormSrgb],
+                    format: TextureFormat::Bgra8UnormSrgb,
+                    usage: TextureUsages::RENDER_ATTACHMENT | TextureUsages::TEXTURE_BINDING,
+                });
+                let view = texture.create_view(&TextureViewDescriptor::default());
+                let uniform_buffer = device.create_buffer(&BufferDescriptor {
+                    label: Some("Composite Uniform Buffer"),
+                    size: std::mem::size_of::<CompositeUniforms>() as BufferAddress,
+                    usage: BufferUsages::UNIFORM | BufferUsages::COPY_DST,
+                    mapped_at_creation: false,
+                });
+                let bind_group = device.create_bind_group(&BindGroupDescriptor {
+                    layout: &bind_group_layout,
+                    entries: &[
+                        BindGroupEntry {
+                            binding: 0,
+                            resource: BindingResource::TextureView(&view),
+                        },
+                        BindGroupEntry {
+                            binding: 1,
+                            resource: BindingResource::Sampler(&sampler),
+                        },
+                        BindGroupEntry {
+                            binding: 2,
+                            resource: uniform_buffer.as_entire_binding(),
+                        },
+                    ],
+                    label: Some("composite_bind_group"),
+                });
+                LayerTarget {
+                    view,
+                    bind_group,
+                    uniform_buffer,
+                }
+            })
+            .collect();
+
+        let pipeline_layout = device.create_pipeline_layout(&PipelineLayoutDescriptor {
+            label: Some("Composite layers"),
+            push_constant_ranges: &[],
+            bind_group_layouts: &[&bind_group_layout],
+        });
+        let pipelines = BLEND_MODES
+            .iter()
+            .map(|&mode| {
+                device.create_render_pipeline(&RenderPipelineDescriptor {
+                    label: None,
+                    layout: Some(&pipeline_layout),
+                    vertex: VertexState {
+                        module: &shader,
+                        entry_point: "vs_main",
+                        buffers: &[Vertex::desc()],
+                    },
+                    fragment: Some(FragmentState {
+                        module: &shader,
+                        entry_point: "fs_main",
+                        targets: &[Some(ColorTargetState {
+                            format: TextureFormat::Bgra8UnormSrgb,
+                            blend: Some(blend_state(mode)),
+                            write_mask: ColorWrites::ALL,
+                        })],
+                    }),
+                    primitive: PrimitiveState {
+                        topology: PrimitiveTopology::TriangleList,
+                        front_face: FrontFace::Ccw,
+                        ..Default::default()
+                    },
+                    depth_stencil: None,
+                    multisample: MultisampleState::default(),
+                    multiview: None,
+                })
+            })
+            .collect();
+
+        let vertex_buffer = device.create_buffer_init(&util::BufferInitDescriptor {
+            label: Some("Composite Vertex Buffer"),
+            contents: bytemuck::cast_slice(VERTICES),
+            usage: BufferUsages::VERTEX,
+        });
+        let index_buffer = device.create_buffer_init(&util::BufferInitDescriptor {
+            label: Some("Composite Index Buffer"),
+            contents: bytemuck::cast_slice(INDICES),
+            usage: BufferUsages::INDEX,
+        });
+
+        Compositor {
+            pipelines,
+            targets,
+            vertex_buffer,
+            index_buffer,
+        }
+    }
+
+    /// Starts drawing `kind` into its own texture, cleared to transparent.
+    pub fn begin_layer<'a>(&'a self, encoder: &'a mut CommandEncoder, kind: LayerKind) -> RenderPass<'a> {
+        encoder.begin_render_pass(&RenderPassDescriptor {
+            label: Some("Layer Pass"),
+            color_attachments: &[Some(RenderPassColorAttachment {
+                view: &self.targets[kind.index()].view,
+                resolve_target: None,
+                ops: Operations {
+                    load: LoadOp::Clear(iced_wgpu::wgpu::Color::TRANSPARENT),
+                    store: StoreOp::Store,
+                },
+            })],
+            depth_stencil_attachment: None,
+            occlusion_query_set: None,
+            timestamp_writes: None,
+        })
+    }
+
+    /// Blends `layers`, bottom first, into `target` cleared to black.
+    pub fn composite(
+        &self,
+        queue: &Queue,
+        encoder: &mut CommandEncoder,
+        target: &TextureView,
+        layers: &[(LayerKind, LayerSettings)],
+    ) {
+        for (kind, settings) in layers {
+            let uniforms = CompositeUniforms {
+                opacity: settings.opacity,
+                blend: BLEND_MODES.iter().position(|&mode| mode == settings.blend).unwrap_or_default() as u32,
+                _padding: [0; 2],
+            };
+            queue.write_buffer(&self.targets[kind.index()].uniform_buffer, 0, bytemuck::bytes_of(&uniforms));
+        }
+
+        let mut render_pass = encoder.begin_render_pass(&RenderPassDescriptor {
+            label: Some("Composite Pass"),
+            color_attachments: &[Some(RenderPassColorAttachment {
+                view: target,
+                resolve_target: None,
+                ops: Operations::default(),
+            })],
+            depth_stencil_attachment: None,
+            occlusion_query_set: None,
+            timestamp_writes: None,
+        });
+        render_pass.set_vertex_buffer(0, self.vertex_buffer.slice(..));
+        render_pass.set_index_buffer(self.index_buffer.slice(..), IndexFormat::Uint16);
+        for (kind, settings) in layers {
+            let mode = BLEND_MODES.iter().position(|&mode| mode == settings.blend).unwrap_or_default();
+            render_pass.set_pipeline(&self.pipelines[mode]);
+            render_pass.set_bind_group(0, &self.targets[kind.index()].bind_group, &[]);
+            render_pass.draw_indexed(0..INDICES.len() as u32, 0, 0..1);
+        }
+    }
+}
+
+/// Blend state completing `composite.wgsl` for premultiplied layers.
+fn blend_state(mode: BlendMode) -> BlendState {
+    let component = |src_factor, dst_factor| BlendComponent {
+        src_factor,
+        dst_factor,
+        operation: BlendOperation::Add,
+    };
+    let over = component(BlendFactor::One, BlendFactor::OneMinusSrcAlpha);
+    match mode {
+        BlendMode::Normal => BlendState {
+            color: over,
+            alpha: over,
+        },
+        BlendMode::Add => BlendState {
+            color: component(BlendFactor::One, BlendFactor::One),
+            alpha: over,
+        },
+        BlendMode::Multiply => BlendState {
+            color: component(BlendFactor::Dst, BlendFactor::Zero),
+            alpha: component(BlendFactor::Zero, BlendFactor::One),
+        },
+        BlendMode::Screen => BlendState {
+            color: component(BlendFactor::One, BlendFactor::OneMinusSrc),
+            alpha: over,
+        },
+    }
+}
@@ -0,0 +1,101 @@
+use std::fmt;
+
+/// The render layers of the display, bottom to top.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LayerKind {
+    /// Colormapped scalar field, e.g. Ising spins, temperature or the nematic order.
+    ScalarMap,
+    /// Director glyphs of lattices, or particles.
+    Glyphs,
+    /// Markers on topological defects of the director field.
+    Defects,
+    /// Vorticity, arrows and line integral convolution of flows and gradients.
+    Vectors,
+    /// Painted heat sources and sinks.
+    Annotations,
+}
+
+pub const LAYER_KINDS: [LayerKind; 5] = [
+    LayerKind::ScalarMap,
+    LayerKind::Glyphs,
+    LayerKind::Defects,
+    LayerKind::Vectors,
+    LayerKind::Annotations,
+];
+
+impl LayerKind {
+    pub fn index(self) -> usize {
+        LAYER_KINDS.iter().position(|&kind| kind == self).unwrap_or_default()
+    }
+}
+
+impl fmt::Display for LayerKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::ScalarMap => write!(f, "scalar map"),
+            Self::Glyphs => write!(f, "glyphs"),
+            Self::Defects => write!(f, "defects"),
+            Self::Vectors => write!(f, "vectors"),
+            Self::Annotations => write!(f, "annotations"),
+        }
+    }
+}
+
+/// How a layer is combined with the layers under it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum BlendMode {
+    /// Covers what is below where the layer is opaque.
+    #[default]
+    Normal,
+    Add,
+    /// Darkens, so white parts of the layer let the layers below show through.
+    Multiply,
+    /// Lightens, so black parts of the layer let the layers below show through.
+    Screen,
+}
+
+pub const BLEND_MODES: [BlendMode; 4] = [BlendMode::Normal, BlendMode::Add, BlendMode::Multiply, BlendMode::Screen];
+
+impl fmt::Display for BlendMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Normal => write!(f, "normal"),
+            Self::Add => write!(f, "add"),
+            Self::Multiply => write!(f, "multiply"),
+            Self::Screen => write!(f, "screen"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LayerSettings {
+    pub visible: bool,
+    pub opacity: f32,
+    pub blend: BlendMode,
+}
+
+impl Default for LayerSettings {
+    fn default() -> Self {
+        Self {
+            visible: true,
+            opacity: 1.0,
+            blend: BlendMode::Normal,
+        }
+    }
+}
+
+/// Settings of every layer, indexed like `LAYER_KINDS`.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct LayerStack {
+    pub layers: [LayerSettings; LAYER_KINDS.len()],
+}
+
+impl LayerStack {
+    pub fn get(&self, kind: LayerKind) -> LayerSettings {
+        self.layers[kind.index()]
+    }
+
+    pub fn get_mut(&mut self, kind: LayerKind) -> &mut LayerSettings {
+        &mut self.layers[kind.index()]
+    }
+}
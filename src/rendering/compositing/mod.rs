@@ -0,0 +1,5 @@
+pub mod compositor;
+pub mod layers;
+
+pub use compositor::*;
+pub use layers::*;
@@ -0,0 +1,3 @@
+pub mod scene;
+
+pub use scene::*;
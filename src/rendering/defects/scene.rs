@@ -0,0 +1,186 @@
+use crate::algorithm_processor::*;
+use crate::rendering::wgpu_wrapper::WGPUWrapper;
+use crate::rendering::*;
+
+const CORNERS: [[f32; 2]; 6] = [[-1.0, -1.0], [1.0, -1.0], [-1.0, 1.0], [-1.0, 1.0], [1.0, -1.0], [1.0, 1.0]];
+const CORNER_ATTRIBS: [VertexAttribute; 1] = iced_wgpu::wgpu::vertex_attr_array![0 => Float32x2];
+
+/// Lattices drawn this many cells across or fewer get markers of one cell radius.
+const CELLS_PER_MARKER: f32 = 80.0;
+
+/// Layout of `Uniforms` in `defects.wgsl`.
+#[derive(Debug, Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+#[repr(C)]
+struct DefectUniforms {
+    dimentions: [f32; 2],
+    radius: f32,
+    _padding: u32,
+}
+
+/// Per-instance data of one marker.
+#[derive(Debug, Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+#[repr(C)]
+struct DefectInstance {
+    position: [f32; 2],
+    charge: f32,
+}
+
+impl DefectInstance {
+    const ATTRIBS: [VertexAttribute; 2] = iced_wgpu::wgpu::vertex_attr_array![
+        1 => Float32x2,
+        2 => Float32,
+    ];
+
+    fn desc<'a>() -> VertexBufferLayout<'a> {
+        VertexBufferLayout {
+            array_stride: std::mem::size_of::<DefectInstance>() as BufferAddress,
+            step_mode: VertexStepMode::Instance,
+            attributes: &Self::ATTRIBS,
+        }
+    }
+}
+
+/// Draws defects of a director field as disks, red for positive and blue for negative charge.
+pub struct DefectScene {
+    pipeline: RenderPipeline,
+    bind_group: BindGroup,
+    corner_buffer: Buffer,
+    instance_buffer: Buffer,
+    uniform_buffer: Buffer,
+    capacity: usize,
+    count: usize,
+}
+
+impl DefectScene {
+    pub fn new(webgpu_wrapper: &WGPUWrapper) -> DefectScene {
+        let device = &webgpu_wrapper.device;
+        let shader = device.create_shader_module(iced_wgpu::wgpu::include_wgsl!("../shader/defects.wgsl"));
+
+        let uniform_buffer = device.create_buffer(&BufferDescriptor {
+            label: Some("Defect Uniform Buffer"),
+            size: std::mem::size_of::<DefectUniforms>() as BufferAddress,
+            usage: BufferUsages::UNIFORM | BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let capacity = 1;
+        let instance_buffer = Self::instance_buffer(device, capacity);
+        let corner_buffer = device.create_buffer_init(&util::BufferInitDescriptor {
+            label: Some("Defect Corner Buffer"),
+            contents: bytemuck::cast_slice(&CORNERS),
+            usage: BufferUsages::VERTEX,
+        });
+
+        let bind_group_layout = device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+            label: Some("defect bind group layout"),
+            entries: &[BindGroupLayoutEntry {
+                binding: 0,
+                visibility: ShaderStages::VERTEX,
+                ty: BindingType::Buffer {
+                    ty: BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            }],
+        });
+        let bind_group = device.create_bind_group(&BindGroupDescriptor {
+            layout: &bind_group_layout,
+            entries: &[BindGroupEntry {
+                binding: 0,
+                resource: uniform_buffer.as_entire_binding(),
+            }],
+            label: Some("defect_bind_group"),
+        });
+
+        let pipeline_layout = device.create_pipeline_layout(&PipelineLayoutDescriptor {
+            label: Some("Render defects"),
+            push_constant_ranges: &[],
+            bind_group_layouts: &[&bind_group_layout],
+        });
+        let corner_layout = VertexBufferLayout {
+            array_stride: std::mem::size_of::<[f32; 2]>() as BufferAddress,
+            step_mode: VertexStepMode::Vertex,
+            attributes: &CORNER_ATTRIBS,
+        };
+        let pipeline = device.create_render_pipeline(&RenderPipelineDescriptor {
+            label: None,
+            layout: Some(&pipeline_layout),
+            vertex: VertexState {
+                module: &shader,
+                entry_point: "vs_main",
+                buffers: &[corner_layout, DefectInstance::desc()],
+            },
+            fragment: Some(FragmentState {
+                module: &shader,
+                entry_point: "fs_main",
+                targets: &[Some(ColorTargetState {
+                    format: TextureFormat::Bgra8UnormSrgb,
+                    blend: Some(BlendState::REPLACE),
+                    write_mask: ColorWrites::ALL,
+                })],
+            }),
+            primitive: PrimitiveState {
+                topology: PrimitiveTopology::TriangleList,
+                front_face: FrontFace::Ccw,
+                ..Default::default()
+            },
+            depth_stencil: None,
+            multisample: MultisampleState::default(),
+            multiview: None,
+        });
+
+        DefectScene {
+            pipeline,
+            bind_group,
+            corner_buffer,
+            instance_buffer,
+            uniform_buffer,
+            capacity,
+            count: 0,
+        }
+    }
+
+    fn instance_buffer(device: &Device, capacity: usize) -> Buffer {
+        device.create_buffer(&BufferDescriptor {
+            label: Some("Defect Instance Buffer"),
+            size: (capacity * std::mem::size_of::<DefectInstance>()) as BufferAddress,
+            usage: BufferUsages::VERTEX | BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        })
+    }
+
+    pub fn update(&mut self, wgpu: &WGPUWrapper, defects: &[Defect], dimensions: (usize, usize)) {
+        let instances: Vec<DefectInstance> = defects
+            .iter()
+            .map(|defect| DefectInstance {
+                position: [defect.position.0, defect.position.1],
+                charge: defect.charge,
+            })
+            .collect();
+        if instances.len() > self.capacity {
+            self.capacity = instances.len().next_power_of_two();
+            self.instance_buffer = Self::instance_buffer(&wgpu.device, self.capacity);
+        }
+        self.count = instances.len();
+        wgpu.queue
+            .write_buffer(&self.instance_buffer, 0, bytemuck::cast_slice(&instances));
+        let uniforms = DefectUniforms {
+            dimentions: [dimensions.0 as f32, dimensions.1 as f32],
+            radius: (dimensions.0.max(dimensions.1) as f32 / CELLS_PER_MARKER).max(1.0),
+            _padding: 0,
+        };
+        wgpu.queue
+            .write_buffer(&self.uniform_buffer, 0, bytemuck::bytes_of(&uniforms));
+    }
+
+    pub fn draw<'a>(&'a self, render_pass: &mut RenderPass<'a>) {
+        if self.count == 0 {
+            return;
+        }
+        render_pass.set_pipeline(&self.pipeline);
+        render_pass.set_bind_group(0, &self.bind_group, &[]);
+        render_pass.set_vertex_buffer(0, self.corner_buffer.slice(..));
+        render_pass.set_vertex_buffer(1, self.instance_buffer.slice(..));
+        render_pass.draw(0..CORNERS.len() as u32, 0..self.count as u32);
+    }
+}
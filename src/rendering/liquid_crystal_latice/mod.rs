@@ -1,4 +1,6 @@
 pub mod scene;
 mod scene_generation;
+pub mod slice;
 
 pub use scene::*;
+pub use slice::*;
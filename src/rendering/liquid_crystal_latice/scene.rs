@@ -13,6 +13,8 @@ pub struct Scene {
     index_buffer: Buffer,
     _uniform_buffer: Buffer,
     storage_buffer: Buffer,
+    mask_buffer: Buffer,
+    style_buffer: Buffer,
     _angle_data: Data,
     dimentions: (usize, usize),
 }
@@ -20,7 +22,7 @@ pub struct Scene {
 impl Scene {
     pub fn new(webgpu_wrapper: &WGPUWrapper, dimentions: Dimentions) -> Scene {
         let _angle_data = vec![0.0; dimentions.0 * dimentions.1];
-        let (pipeline, bind_group, _uniform_buffer, storage_buffer) = build_pipeline(
+        let (pipeline, bind_group, _uniform_buffer, storage_buffer, mask_buffer, style_buffer) = build_pipeline(
             &webgpu_wrapper.device,
             &webgpu_wrapper.queue,
             TextureFormat::Bgra8UnormSrgb,
@@ -52,6 +54,8 @@ impl Scene {
             index_buffer,
             _uniform_buffer,
             storage_buffer,
+            mask_buffer,
+            style_buffer,
             _angle_data,
             dimentions,
         }
@@ -73,6 +77,30 @@ impl Scene {
     pub fn update(&self, queue: &Queue, angle: Data) {
         queue.write_buffer(&self.storage_buffer, 0, bytemuck::cast_slice(&angle));
     }
+
+    pub fn dimentions(&self) -> Dimentions {
+        self.dimentions
+    }
+
+    /// Glyph lengths and tilts, one per cell.
+    pub fn update_styles(&self, queue: &Queue, styles: &[GlyphStyle]) {
+        queue.write_buffer(&self.style_buffer, 0, bytemuck::cast_slice(styles));
+    }
+
+    /// Highlights frozen cells; `None` clears the highlight.
+    pub fn update_mask(&self, queue: &Queue, mask: Option<&Mask>) {
+        let cells = self.dimentions.0 * self.dimentions.1;
+        let frozen: Vec<u32> = match mask {
+            Some(mask) => mask
+                .resampled(self.dimentions)
+                .frozen
+                .iter()
+                .map(|&frozen| frozen as u32)
+                .collect(),
+            None => vec![0; cells],
+        };
+        queue.write_buffer(&self.mask_buffer, 0, bytemuck::cast_slice(&frozen));
+    }
 }
 
 fn build_pipeline(
@@ -81,7 +109,7 @@ fn build_pipeline(
     texture_format: TextureFormat,
     dimentions: &Dimentions,
     angle_data: &Vec<f32>,
-) -> (RenderPipeline, BindGroup, Buffer, Buffer) {
+) -> (RenderPipeline, BindGroup, Buffer, Buffer, Buffer, Buffer) {
     let shader = device.create_shader_module(iced_wgpu::wgpu::include_wgsl!(
         "../shader/2d_liquid_crystal_latice.wgsl"
     ));
@@ -138,12 +166,44 @@ fn build_pipeline(
                 },
                 count: None,
             },
+            BindGroupLayoutEntry {
+                binding: 4,
+                visibility: ShaderStages::VERTEX,
+                ty: BindingType::Buffer {
+                    ty: BufferBindingType::Storage { read_only: true },
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            },
+            BindGroupLayoutEntry {
+                binding: 5,
+                visibility: ShaderStages::VERTEX,
+                ty: BindingType::Buffer {
+                    ty: BufferBindingType::Storage { read_only: true },
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            },
         ],
     });
 
     let storage_buffer = device.create_buffer_init(&util::BufferInitDescriptor {
         label: Some("Storage Buffer"),
-        contents: bytemuck::cast_slice(&angle_data.as_slice()),
+        contents: bytemuck::cast_slice(angle_data.as_slice()),
+        usage: BufferUsages::STORAGE | BufferUsages::COPY_DST,
+    });
+
+    let mask_buffer = device.create_buffer_init(&util::BufferInitDescriptor {
+        label: Some("Mask Buffer"),
+        contents: bytemuck::cast_slice(&vec![0u32; angle_data.len()]),
+        usage: BufferUsages::STORAGE | BufferUsages::COPY_DST,
+    });
+
+    let style_buffer = device.create_buffer_init(&util::BufferInitDescriptor {
+        label: Some("Style Buffer"),
+        contents: bytemuck::cast_slice(&vec![PLAIN_GLYPH; angle_data.len()]),
         usage: BufferUsages::STORAGE | BufferUsages::COPY_DST,
     });
 
@@ -172,6 +232,14 @@ fn build_pipeline(
                 binding: 3,
                 resource: storage_buffer.as_entire_binding(),
             },
+            BindGroupEntry {
+                binding: 4,
+                resource: mask_buffer.as_entire_binding(),
+            },
+            BindGroupEntry {
+                binding: 5,
+                resource: style_buffer.as_entire_binding(),
+            },
         ],
         label: Some("texture_bind_group"),
     });
@@ -219,5 +287,7 @@ fn build_pipeline(
         bind_group,
         uniform_buffer,
         storage_buffer,
+        mask_buffer,
+        style_buffer,
     )
 }
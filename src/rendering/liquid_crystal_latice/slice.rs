@@ -0,0 +1,128 @@
+use crate::algorithm_processor::Dimensions3;
+use std::fmt;
+
+/// Plane through a 3D lattice shown on the 2D display.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SlicePlane {
+    #[default]
+    XY,
+    XZ,
+    YZ,
+}
+
+pub const SLICE_PLANES: [SlicePlane; 3] = [SlicePlane::XY, SlicePlane::XZ, SlicePlane::YZ];
+
+impl fmt::Display for SlicePlane {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::XY => write!(f, "xy"),
+            Self::XZ => write!(f, "xz"),
+            Self::YZ => write!(f, "yz"),
+        }
+    }
+}
+
+/// How a 3D director is drawn within the slice.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DirectorDisplay {
+    /// Full-length glyphs along the in-plane projection.
+    #[default]
+    Slice,
+    /// Glyphs shortened to the in-plane projection and tinted by the out-of-plane tilt.
+    Projection,
+}
+
+pub const DIRECTOR_DISPLAYS: [DirectorDisplay; 2] = [DirectorDisplay::Slice, DirectorDisplay::Projection];
+
+impl fmt::Display for DirectorDisplay {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Slice => write!(f, "slice"),
+            Self::Projection => write!(f, "projection"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct SliceView {
+    pub plane: SlicePlane,
+    /// Position of the slice along the plane normal, clamped to the lattice.
+    pub index: usize,
+    pub display: DirectorDisplay,
+}
+
+/// Glyph length and out-of-plane tilt in `[0, 1]` of one displayed cell.
+pub type GlyphStyle = [f32; 2];
+
+pub const PLAIN_GLYPH: GlyphStyle = [1.0, 0.0];
+
+impl SliceView {
+    /// Number of slices along the plane normal.
+    pub fn extent(plane: SlicePlane, (rows, cols, layers): Dimensions3) -> usize {
+        match plane {
+            SlicePlane::XY => layers,
+            SlicePlane::XZ => rows,
+            SlicePlane::YZ => cols,
+        }
+    }
+
+    /// Rows and columns of the slice; z runs upwards in the xz and yz planes.
+    pub fn dimensions(&self, (rows, cols, layers): Dimensions3) -> (usize, usize) {
+        match self.plane {
+            SlicePlane::XY => (rows, cols),
+            SlicePlane::XZ => (layers, cols),
+            SlicePlane::YZ => (layers, rows),
+        }
+    }
+
+    /// In-plane director angles and glyph styles of the slice, in the display's row-major layout.
+    pub fn extract(&self, directors: &[[f32; 3]], dimensions: Dimensions3) -> (Vec<f32>, Vec<GlyphStyle>) {
+        let (rows, cols, _) = dimensions;
+        let index = self.index.min(Self::extent(self.plane, dimensions).saturating_sub(1));
+        let (slice_rows, slice_cols) = self.dimensions(dimensions);
+
+        let mut angles = Vec::with_capacity(slice_rows * slice_cols);
+        let mut styles = Vec::with_capacity(slice_rows * slice_cols);
+        for slice_row in 0..slice_rows {
+            for slice_col in 0..slice_cols {
+                let (row, col, layer) = match self.plane {
+                    SlicePlane::XY => (slice_row, slice_col, index),
+                    SlicePlane::XZ => (index, slice_col, slice_row),
+                    SlicePlane::YZ => (slice_col, index, slice_row),
+                };
+                let [x, y, z] = directors[(layer * rows + row) * cols + col];
+                let (a, b, normal) = match self.plane {
+                    SlicePlane::XY => (x, y, z),
+                    SlicePlane::XZ => (x, z, y),
+                    SlicePlane::YZ => (y, z, x),
+                };
+
+                angles.push(b.atan2(a));
+                styles.push(match self.display {
+                    DirectorDisplay::Slice => PLAIN_GLYPH,
+                    DirectorDisplay::Projection => [(a * a + b * b).sqrt(), normal.abs()],
+                });
+            }
+        }
+        (angles, styles)
+    }
+
+    /// Per-site values of the slice, e.g. a map computed on the whole 3D lattice.
+    pub fn extract_scalars(&self, values: &[f32], dimensions: Dimensions3) -> Vec<f32> {
+        let (rows, cols, _) = dimensions;
+        let index = self.index.min(Self::extent(self.plane, dimensions).saturating_sub(1));
+        let (slice_rows, slice_cols) = self.dimensions(dimensions);
+        let mut slice = Vec::with_capacity(slice_rows * slice_cols);
+        for slice_row in 0..slice_rows {
+            for slice_col in 0..slice_cols {
+                let (row, col, layer) = match self.plane {
+                    SlicePlane::XY => (slice_row, slice_col, index),
+                    SlicePlane::XZ => (index, slice_col, slice_row),
+                    SlicePlane::YZ => (slice_col, index, slice_row),
+                };
+                slice.push(values[(layer * rows + row) * cols + col]);
+            }
+        }
+        slice
+    }
+}
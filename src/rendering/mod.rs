@@ -1,5 +1,9 @@
-pub use crate::rendering::image_writer::*;
+pub use crate::rendering::compositing::*;
+pub use crate::rendering::defects::*;
 pub use crate::rendering::liquid_crystal_latice::*;
+pub use crate::rendering::particles::*;
+pub use crate::rendering::scalar_field::*;
+pub use crate::rendering::vector_field::*;
 pub use crate::rendering::vertex::*;
 pub use crate::rendering::wgpu_wrapper::*;
 pub use iced::mouse;
@@ -17,10 +21,14 @@ pub use iced_winit::runtime::Debug;
 pub use iced_winit::runtime::program;
 pub use iced_winit::winit;
 pub mod assets;
+pub mod compositing;
+pub mod defects;
 pub mod generic_pipeline;
-mod image_writer;
 pub mod liquid_crystal_latice;
+pub mod particles;
 pub mod renderers;
+pub mod scalar_field;
+pub mod vector_field;
 pub mod vertex;
 pub mod wgpu_wrapper;
 
@@ -0,0 +1,5 @@
+pub mod scene;
+pub mod style;
+
+pub use scene::*;
+pub use style::*;
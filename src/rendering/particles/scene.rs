@@ -0,0 +1,187 @@
+use crate::algorithm_processor::*;
+use crate::rendering::wgpu_wrapper::WGPUWrapper;
+use crate::rendering::*;
+
+const CORNERS: [[f32; 2]; 6] = [[-1.0, -1.0], [1.0, -1.0], [-1.0, 1.0], [-1.0, 1.0], [1.0, -1.0], [1.0, 1.0]];
+const CORNER_ATTRIBS: [VertexAttribute; 1] = iced_wgpu::wgpu::vertex_attr_array![0 => Float32x2];
+
+/// Layout of `Uniforms` in `particles.wgsl`.
+#[derive(Debug, Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+#[repr(C)]
+struct ParticleUniforms {
+    box_size: [f32; 2],
+    radius: f32,
+    aspect: f32,
+    glyph: u32,
+    _padding: [u32; 3],
+}
+
+/// Per-instance data of one particle.
+#[derive(Debug, Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+#[repr(C)]
+struct ParticleInstance {
+    position: [f32; 2],
+    angle: f32,
+    speed: f32,
+}
+
+impl ParticleInstance {
+    const ATTRIBS: [VertexAttribute; 3] = iced_wgpu::wgpu::vertex_attr_array![
+        1 => Float32x2,
+        2 => Float32,
+        3 => Float32,
+    ];
+
+    fn desc<'a>() -> VertexBufferLayout<'a> {
+        VertexBufferLayout {
+            array_stride: std::mem::size_of::<ParticleInstance>() as BufferAddress,
+            step_mode: VertexStepMode::Instance,
+            attributes: &Self::ATTRIBS,
+        }
+    }
+}
+
+/// Draws off-lattice particles as instanced rods or disks.
+pub struct ParticleScene {
+    pipeline: RenderPipeline,
+    bind_group: BindGroup,
+    corner_buffer: Buffer,
+    instance_buffer: Buffer,
+    uniform_buffer: Buffer,
+    count: usize,
+    style: ParticleStyle,
+}
+
+impl ParticleScene {
+    pub fn new(webgpu_wrapper: &WGPUWrapper, count: usize, style: ParticleStyle) -> ParticleScene {
+        let device = &webgpu_wrapper.device;
+        let shader = device.create_shader_module(iced_wgpu::wgpu::include_wgsl!("../shader/particles.wgsl"));
+
+        let uniform_buffer = device.create_buffer(&BufferDescriptor {
+            label: Some("Particle Uniform Buffer"),
+            size: std::mem::size_of::<ParticleUniforms>() as BufferAddress,
+            usage: BufferUsages::UNIFORM | BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let instance_buffer = device.create_buffer(&BufferDescriptor {
+            label: Some("Particle Instance Buffer"),
+            size: (count.max(1) * std::mem::size_of::<ParticleInstance>()) as BufferAddress,
+            usage: BufferUsages::VERTEX | BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let corner_buffer = device.create_buffer_init(&util::BufferInitDescriptor {
+            label: Some("Particle Corner Buffer"),
+            contents: bytemuck::cast_slice(&CORNERS),
+            usage: BufferUsages::VERTEX,
+        });
+
+        let bind_group_layout = device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+            label: Some("particle bind group layout"),
+            entries: &[BindGroupLayoutEntry {
+                binding: 0,
+                visibility: ShaderStages::VERTEX_FRAGMENT,
+                ty: BindingType::Buffer {
+                    ty: BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            }],
+        });
+        let bind_group = device.create_bind_group(&BindGroupDescriptor {
+            layout: &bind_group_layout,
+            entries: &[BindGroupEntry {
+                binding: 0,
+                resource: uniform_buffer.as_entire_binding(),
+            }],
+            label: Some("particle_bind_group"),
+        });
+
+        let pipeline_layout = device.create_pipeline_layout(&PipelineLayoutDescriptor {
+            label: Some("Render particles"),
+            push_constant_ranges: &[],
+            bind_group_layouts: &[&bind_group_layout],
+        });
+        let corner_layout = VertexBufferLayout {
+            array_stride: std::mem::size_of::<[f32; 2]>() as BufferAddress,
+            step_mode: VertexStepMode::Vertex,
+            attributes: &CORNER_ATTRIBS,
+        };
+        let pipeline = device.create_render_pipeline(&RenderPipelineDescriptor {
+            label: None,
+            layout: Some(&pipeline_layout),
+            vertex: VertexState {
+                module: &shader,
+                entry_point: "vs_main",
+                buffers: &[corner_layout, ParticleInstance::desc()],
+            },
+            fragment: Some(FragmentState {
+                module: &shader,
+                entry_point: "fs_main",
+                targets: &[Some(ColorTargetState {
+                    format: TextureFormat::Bgra8UnormSrgb,
+                    blend: Some(BlendState::REPLACE),
+                    write_mask: ColorWrites::ALL,
+                })],
+            }),
+            primitive: PrimitiveState {
+                topology: PrimitiveTopology::TriangleList,
+                front_face: FrontFace::Ccw,
+                ..Default::default()
+            },
+            depth_stencil: None,
+            multisample: MultisampleState::default(),
+            multiview: None,
+        });
+
+        ParticleScene {
+            pipeline,
+            bind_group,
+            corner_buffer,
+            instance_buffer,
+            uniform_buffer,
+            count,
+            style,
+        }
+    }
+
+    pub fn count(&self) -> usize {
+        self.count
+    }
+
+    pub fn draw<'a>(&'a self, render_pass: &mut RenderPass<'a>) {
+        render_pass.set_pipeline(&self.pipeline);
+        render_pass.set_bind_group(0, &self.bind_group, &[]);
+        render_pass.set_vertex_buffer(0, self.corner_buffer.slice(..));
+        render_pass.set_vertex_buffer(1, self.instance_buffer.slice(..));
+        render_pass.draw(0..CORNERS.len() as u32, 0..self.count as u32);
+    }
+
+    /// Uploads a frame with as many particles as the scene was built for.
+    pub fn update(&self, queue: &Queue, particles: &Particles) {
+        let instances: Vec<ParticleInstance> = (0..particles.len())
+            .map(|index| {
+                let [vx, vy] = particles.velocities.get(index).copied().unwrap_or_default();
+                ParticleInstance {
+                    position: particles.positions[index],
+                    angle: particles.orientations.get(index).copied().unwrap_or_default(),
+                    speed: (vx * vx + vy * vy).sqrt(),
+                }
+            })
+            .collect();
+        queue.write_buffer(&self.instance_buffer, 0, bytemuck::cast_slice(&instances));
+        let uniforms = ParticleUniforms {
+            box_size: particles.box_size,
+            radius: self.style.radius,
+            aspect: particles.aspect,
+            glyph: (self.style.glyph == ParticleGlyph::Disks) as u32,
+            _padding: [0; 3],
+        };
+        queue.write_buffer(&self.uniform_buffer, 0, bytemuck::bytes_of(&uniforms));
+    }
+
+    /// Takes effect with the next uploaded frame.
+    pub fn set_style(&mut self, style: ParticleStyle) {
+        self.style = style;
+    }
+}
@@ -0,0 +1,38 @@
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ParticleGlyph {
+    /// Ellipses stretched by the particle aspect ratio, colored by orientation.
+    #[default]
+    Rods,
+    /// Circles colored by speed.
+    Disks,
+}
+
+pub const PARTICLE_GLYPHS: [ParticleGlyph; 2] = [ParticleGlyph::Rods, ParticleGlyph::Disks];
+
+impl fmt::Display for ParticleGlyph {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Rods => write!(f, "rods"),
+            Self::Disks => write!(f, "disks"),
+        }
+    }
+}
+
+/// How particles are drawn.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ParticleStyle {
+    pub glyph: ParticleGlyph,
+    /// Half width of every glyph in simulation units; 0.5 touches at one particle diameter.
+    pub radius: f32,
+}
+
+impl Default for ParticleStyle {
+    fn default() -> Self {
+        Self {
+            glyph: ParticleGlyph::default(),
+            radius: 0.5,
+        }
+    }
+}
@@ -1,13 +1,54 @@
 use crate::algorithm_processor::*;
+use crate::analysis::SpectrumField;
 use crate::application::SharedContext;
 use crate::rendering::wgpu_wrapper::WGPUWrapper;
 use crate::rendering::*;
 
+/// Opacity of the vorticity drawn under the arrows of the vectors layer.
+const VORTICITY_OPACITY: f32 = 0.45;
+
+/// Content of the glyphs layer.
+enum Glyphs {
+    Lattice,
+    /// Kept with the latest frame, to redraw it when the style changes.
+    Particles(Box<ParticleScene>, Particles),
+}
+
 pub struct BackgroundRenderer {
     pub texture: TextureHandle,
     pub texture_view: TextureView,
+    compositor: Compositor,
+    layers: LayerStack,
     scene: Scene,
+    glyphs: Option<Glyphs>,
+    /// Scalar field of the latest frame, e.g. the spins, the temperature or the nematic order.
+    scalar_map: Option<ScalarScene>,
+    defect_scene: Option<DefectScene>,
+    /// Fading trails of the tracked defects, drawn under their markers.
+    trails: Option<ScalarScene>,
+    /// Vorticity of the flow, drawn in the vectors layer.
+    vorticity: Option<ScalarScene>,
+    /// Arrows and LIC of the latest vector field, created when first shown.
+    vector_scene: Option<VectorScene>,
+    vector_overlay: VectorOverlay,
+    /// Flow of the latest frame, or the gradient of a scalar frame.
+    vector_field: Option<VectorField>,
+    /// Painted sources and sinks.
+    annotations: Option<ScalarScene>,
+    /// Director angle the pitch lines are drawn at, `None` while they are off.
+    pitch_reference: Option<f32>,
+    pitch_lines: Option<ScalarScene>,
+    /// Log power spectrum drawn instead of every layer while a spectrum field is chosen.
+    spectrum: Option<ScalarScene>,
     data_handle: ProcessedDataHandle,
+    mask: Option<Mask>,
+    view: SliceView,
+    colormap: Colormap,
+    particle_style: ParticleStyle,
+    /// Latest 3D frame with its Frank term, kept to re-slice them when the view changes.
+    directors: Option<(Vec<[f32; 3]>, Dimensions3, Option<Data>)>,
+    /// Set when the texture is stale even though no new data arrived.
+    redraw_pending: bool,
 }
 
 impl BackgroundRenderer {
@@ -42,43 +83,452 @@ impl BackgroundRenderer {
         Self {
             texture,
             texture_view,
+            compositor: Compositor::new(wgpu, texture_extent),
+            layers: LayerStack::default(),
             scene,
+            glyphs: Some(Glyphs::Lattice),
+            scalar_map: None,
+            defect_scene: None,
+            trails: None,
+            vorticity: None,
+            vector_scene: None,
+            vector_overlay: VectorOverlay::default(),
+            vector_field: None,
+            annotations: None,
+            pitch_reference: None,
+            pitch_lines: None,
+            spectrum: None,
             data_handle,
+            mask: None,
+            view: SliceView::default(),
+            colormap: Colormap::default(),
+            particle_style: ParticleStyle::default(),
+            directors: None,
+            redraw_pending: false,
         }
     }
 
-    pub fn render(&self, wgpu: &mut WGPUWrapper) {
+    pub fn render(&mut self, wgpu: &mut WGPUWrapper) {
         let mut encoder = wgpu
             .device
             .create_command_encoder(&CommandEncoderDescriptor { label: None });
-        let mut job_done = false;
-        for datum in self.data_handle.try_iter() {
-            let mut render_pass = encoder.begin_render_pass(&RenderPassDescriptor {
-                label: Some("Render Pass"),
-                color_attachments: &[Some(RenderPassColorAttachment {
-                    view: &self.texture_view,
-                    resolve_target: None,
-                    ops: Operations::default(),
-                })],
-                depth_stencil_attachment: None,
-                occlusion_query_set: None,
-                timestamp_writes: None,
-            });
-
-            self.scene.update(&wgpu.queue, datum);
-            self.scene.draw(&mut render_pass);
+        let mut job_done = self.redraw_pending;
+        let mut vectors_changed = false;
+        // Every frame replaces the one before, so only the latest is drawn.
+        if let Some(frame) = self.data_handle.try_iter().last() {
+            match frame {
+                Frame::Angles {
+                    angles,
+                    dimensions,
+                    defects,
+                    scalars,
+                    flow,
+                    spectrum,
+                    elastic,
+                    trails,
+                } => {
+                    self.directors = None;
+                    self.glyphs = Some(Glyphs::Lattice);
+                    if dimensions != self.scene.dimentions() {
+                        self.scene = Scene::new(wgpu, dimensions);
+                        self.scene.update_mask(&wgpu.queue, self.visible_mask());
+                    }
+                    self.show_defects(wgpu, &defects, dimensions);
+                    self.show_trails(wgpu, trails.as_ref(), dimensions);
+                    self.show_pitch_lines(wgpu, &angles, dimensions);
+                    self.show_spectrum(wgpu, spectrum.as_ref());
+                    let elastic = elastic.map(|values| elastic_frame(&values, (dimensions.0, dimensions.1, 1), None));
+                    self.scene.update(&wgpu.queue, angles);
+                    self.show_scalars(wgpu, elastic.as_ref().or(scalars.as_ref()));
+                    self.show_vorticity(wgpu, flow.as_ref());
+                    self.vector_field = flow;
+                }
+                Frame::Directors {
+                    directors,
+                    dimensions,
+                    elastic,
+                } => {
+                    self.glyphs = Some(Glyphs::Lattice);
+                    self.spectrum = None;
+                    self.trails = None;
+                    self.pitch_lines = None;
+                    self.scalar_map = None;
+                    self.vorticity = None;
+                    self.vector_field = None;
+                    self.directors = Some((directors, dimensions, elastic));
+                }
+                Frame::Scalars { scalars, spectrum } => {
+                    self.directors = None;
+                    self.trails = None;
+                    self.pitch_lines = None;
+                    self.glyphs = None;
+                    self.defect_scene = None;
+                    self.vorticity = None;
+                    self.vector_field = Some((gradient(&scalars.values, scalars.dimensions), scalars.dimensions));
+                    self.show_spectrum(wgpu, spectrum.as_ref());
+                    self.show_scalars(wgpu, Some(&scalars));
+                }
+                Frame::Particles(particles) => {
+                    self.directors = None;
+                    self.spectrum = None;
+                    self.trails = None;
+                    self.pitch_lines = None;
+                    self.scalar_map = None;
+                    self.defect_scene = None;
+                    self.vorticity = None;
+                    self.vector_field = None;
+                    self.show_particles(wgpu, particles)
+                }
+            }
             job_done = true;
+            vectors_changed = true;
         }
+        if vectors_changed {
+            self.show_vectors(wgpu);
+        }
+        self.redraw_pending = false;
 
         if job_done {
+            self.show_slice(wgpu);
+            self.draw(&wgpu.queue, &mut encoder);
             wgpu.engine.submit(&wgpu.queue, encoder);
         };
     }
 
+    /// Uploads the current slice of the latest 3D frame, resizing the scene to fit it.
+    fn show_slice(&mut self, wgpu: &WGPUWrapper) {
+        let Some((directors, dimensions, elastic)) = &self.directors else {
+            return;
+        };
+        let (angles, styles) = self.view.extract(directors, *dimensions);
+        let slice = self.view.dimensions(*dimensions);
+        if slice != self.scene.dimentions() {
+            self.scene = Scene::new(wgpu, slice);
+            self.scene.update_mask(&wgpu.queue, self.visible_mask());
+        }
+        let elastic = elastic
+            .as_ref()
+            .map(|values| elastic_frame(values, *dimensions, Some(&self.view)));
+        let defects = find_defects(&angles, slice);
+        self.show_defects(wgpu, &defects, slice);
+        self.scene.update(&wgpu.queue, angles);
+        self.scene.update_styles(&wgpu.queue, &styles);
+        self.show_scalars(wgpu, elastic.as_ref());
+    }
+
+    fn show_scalars(&mut self, wgpu: &WGPUWrapper, frame: Option<&ScalarFrame>) {
+        let Some(frame) = frame else {
+            self.scalar_map = None;
+            return;
+        };
+        let scene = match &mut self.scalar_map {
+            Some(scene) if scene.dimentions() == frame.dimensions => scene,
+            _ => {
+                let scene = ScalarScene::new(wgpu, frame.dimensions, self.colormap);
+                scene.update_mask(&wgpu.queue, self.mask.as_ref());
+                self.scalar_map.insert(scene)
+            }
+        };
+        scene.update(&wgpu.queue, &frame.values, frame.range);
+    }
+
+    fn show_defects(&mut self, wgpu: &WGPUWrapper, defects: &[Defect], dimensions: (usize, usize)) {
+        self.defect_scene
+            .get_or_insert_with(|| DefectScene::new(wgpu))
+            .update(wgpu, defects, dimensions);
+    }
+
+    /// Draws the trails the worker sent with the frame, while trails are on.
+    fn show_trails(&mut self, wgpu: &WGPUWrapper, trails: Option<&Data>, dimensions: (usize, usize)) {
+        let Some(cells) = trails else {
+            self.trails = None;
+            return;
+        };
+        let scene = match &mut self.trails {
+            Some(scene) if scene.dimentions() == dimensions => scene,
+            _ => {
+                let mut scene = ScalarScene::new(wgpu, dimensions, Colormap::Coolwarm);
+                scene.set_hide_zero(&wgpu.queue, true);
+                self.trails.insert(scene)
+            }
+        };
+        scene.update(&wgpu.queue, cells, (-1.0, 1.0));
+    }
+
+    /// Marks where the director passes through the pitch reference, while pitch lines are on.
+    fn show_pitch_lines(&mut self, wgpu: &WGPUWrapper, angles: &[f32], dimensions: (usize, usize)) {
+        let Some(reference) = self.pitch_reference else {
+            self.pitch_lines = None;
+            return;
+        };
+        let lines = pitch_lines(angles, dimensions, reference);
+        let scene = match &mut self.pitch_lines {
+            Some(scene) if scene.dimentions() == dimensions => scene,
+            _ => {
+                let mut scene = ScalarScene::new(wgpu, dimensions, Colormap::Viridis);
+                scene.set_hide_zero(&wgpu.queue, true);
+                self.pitch_lines.insert(scene)
+            }
+        };
+        scene.update(&wgpu.queue, &lines, (0.0, 1.0));
+    }
+
+    /// Log power spectrum of the chosen field, when the frame has it.
+    fn show_spectrum(&mut self, wgpu: &WGPUWrapper, spectrum: Option<&ScalarFrame>) {
+        let Some(frame) = spectrum else {
+            self.spectrum = None;
+            return;
+        };
+        let scene = match &mut self.spectrum {
+            Some(scene) if scene.dimentions() == frame.dimensions => scene,
+            _ => self.spectrum.insert(ScalarScene::new(wgpu, frame.dimensions, self.colormap)),
+        };
+        scene.update(&wgpu.queue, &frame.values, frame.range);
+    }
+
+    /// Colors the vorticity of `flow` with a symmetric coolwarm range, or removes it.
+    fn show_vorticity(&mut self, wgpu: &WGPUWrapper, flow: Option<&VectorField>) {
+        let Some((velocity, dimensions)) = flow else {
+            self.vorticity = None;
+            return;
+        };
+        let dimensions = *dimensions;
+        let values = vorticity(velocity, dimensions);
+        let extent = values.iter().fold(1e-6f32, |max, value| max.max(value.abs()));
+        let scene = match &mut self.vorticity {
+            Some(scene) if scene.dimentions() == dimensions => scene,
+            _ => {
+                let mut scene = ScalarScene::new(wgpu, dimensions, Colormap::Coolwarm);
+                scene.set_opacity(&wgpu.queue, VORTICITY_OPACITY);
+                self.vorticity.insert(scene)
+            }
+        };
+        scene.update(&wgpu.queue, &values, (-extent, extent));
+    }
+
+    /// Rebuilds the arrows and LIC from the latest field while they are enabled.
+    fn show_vectors(&mut self, wgpu: &WGPUWrapper) {
+        let (true, Some(field)) = (self.vector_overlay.enabled, &self.vector_field) else {
+            return;
+        };
+        self.vector_scene
+            .get_or_insert_with(|| VectorScene::new(wgpu))
+            .update(wgpu, field, self.vector_overlay);
+    }
+
+    fn show_particles(&mut self, wgpu: &WGPUWrapper, particles: Particles) {
+        let scene = match self.glyphs.take() {
+            Some(Glyphs::Particles(scene, _)) if scene.count() == particles.len() => scene,
+            _ => Box::new(ParticleScene::new(wgpu, particles.len(), self.particle_style)),
+        };
+        scene.update(&wgpu.queue, &particles);
+        self.glyphs = Some(Glyphs::Particles(scene, particles));
+    }
+
+    /// The mask lies in the xy plane, so other slices of a 3D lattice are shown without it.
+    fn visible_mask(&self) -> Option<&Mask> {
+        self.mask
+            .as_ref()
+            .filter(|_| self.directors.is_none() || self.view.plane == SlicePlane::XY)
+    }
+
+    fn arrows_shown(&self) -> bool {
+        self.vector_overlay.enabled && self.vector_field.is_some() && self.vector_scene.is_some()
+    }
+
+    fn has_content(&self, kind: LayerKind) -> bool {
+        match kind {
+            LayerKind::ScalarMap => self.scalar_map.is_some(),
+            LayerKind::Glyphs => self.glyphs.is_some(),
+            LayerKind::Defects => self.defect_scene.is_some() || self.trails.is_some(),
+            LayerKind::Vectors => self.vorticity.is_some() || self.arrows_shown(),
+            LayerKind::Annotations => self.annotations.is_some() || self.pitch_lines.is_some(),
+        }
+    }
+
+    fn draw_layer<'a>(&'a self, kind: LayerKind, render_pass: &mut RenderPass<'a>) {
+        match kind {
+            LayerKind::ScalarMap => {
+                if let Some(scene) = &self.scalar_map {
+                    scene.draw(render_pass);
+                }
+            }
+            LayerKind::Glyphs => match &self.glyphs {
+                Some(Glyphs::Lattice) => self.scene.draw(render_pass),
+                Some(Glyphs::Particles(scene, _)) => scene.draw(render_pass),
+                None => {}
+            },
+            LayerKind::Defects => {
+                if let Some(scene) = &self.trails {
+                    scene.draw(render_pass);
+                }
+                if let Some(scene) = &self.defect_scene {
+                    scene.draw(render_pass);
+                }
+            }
+            LayerKind::Vectors => {
+                if let Some(scene) = &self.vorticity {
+                    scene.draw(render_pass);
+                }
+                if let (true, Some(scene)) = (self.arrows_shown(), &self.vector_scene) {
+                    scene.draw(render_pass);
+                }
+            }
+            LayerKind::Annotations => {
+                if let Some(scene) = &self.annotations {
+                    scene.draw(render_pass);
+                }
+                if let Some(scene) = &self.pitch_lines {
+                    scene.draw(render_pass);
+                }
+            }
+        }
+    }
+
+    /// Draws every visible layer with content into its own texture and composites them, or only
+    /// the power spectrum while one is shown.
+    fn draw(&self, queue: &Queue, encoder: &mut CommandEncoder) {
+        if let Some(scene) = &self.spectrum {
+            let mut render_pass = self.compositor.begin_layer(encoder, LayerKind::ScalarMap);
+            scene.draw(&mut render_pass);
+            drop(render_pass);
+            let visible = [(LayerKind::ScalarMap, LayerSettings::default())];
+            self.compositor.composite(queue, encoder, &self.texture_view, &visible);
+            return;
+        }
+        let mut visible = Vec::new();
+        for kind in LAYER_KINDS {
+            let settings = self.layers.get(kind);
+            if !settings.visible || !self.has_content(kind) {
+                continue;
+            }
+            let mut render_pass = self.compositor.begin_layer(encoder, kind);
+            self.draw_layer(kind, &mut render_pass);
+            drop(render_pass);
+            visible.push((kind, settings));
+        }
+        self.compositor.composite(queue, encoder, &self.texture_view, &visible);
+    }
+
+    /// Highlights the frozen cells of `mask` from the next frame on.
+    pub fn set_mask(&mut self, wgpu: &WGPUWrapper, mask: Option<Mask>) {
+        self.mask = mask;
+        self.scene.update_mask(&wgpu.queue, self.visible_mask());
+        if let Some(scene) = &self.scalar_map {
+            scene.update_mask(&wgpu.queue, self.mask.as_ref());
+        }
+        self.redraw_pending = true;
+    }
+
+    /// Shows the painted sources in red and the sinks in blue on the annotations layer.
+    pub fn set_sources(&mut self, wgpu: &WGPUWrapper, sources: Option<&SourceMap>) {
+        self.annotations = sources.filter(|map| map.rates.iter().any(|&rate| rate != 0.0)).map(|map| {
+            let extent = map.rates.iter().fold(0.0f32, |max, rate| max.max(rate.abs()));
+            let mut scene = ScalarScene::new(wgpu, map.dimensions, Colormap::Coolwarm);
+            scene.set_hide_zero(&wgpu.queue, true);
+            scene.update(&wgpu.queue, &map.rates, (-extent, extent));
+            scene
+        });
+        self.redraw_pending = true;
+    }
+
+    /// Draws pitch lines where the director passes through `reference` from the next frame on,
+    /// or removes them.
+    pub fn set_pitch_lines(&mut self, reference: Option<f32>) {
+        self.pitch_reference = reference;
+        if reference.is_none() {
+            self.pitch_lines = None;
+        }
+        self.redraw_pending = true;
+    }
+
+    /// Removes the power spectrum when `field` is off; the worker sends it with the frames.
+    pub fn set_spectrum(&mut self, field: SpectrumField) {
+        if field == SpectrumField::Off {
+            self.spectrum = None;
+        }
+        self.redraw_pending = true;
+    }
+
+    /// Removes the trails when they are switched off; the worker sends them with the frames.
+    pub fn set_trails(&mut self, enabled: bool) {
+        if !enabled {
+            self.trails = None;
+        }
+        self.redraw_pending = true;
+    }
+
+    /// Selects the slice of 3D lattices shown from the next frame on.
+    pub fn set_view(&mut self, wgpu: &WGPUWrapper, view: SliceView) {
+        self.view = view;
+        self.scene.update_mask(&wgpu.queue, self.visible_mask());
+        self.redraw_pending = true;
+    }
+
+    /// Colors scalar fields with `colormap` from the next frame on.
+    pub fn set_colormap(&mut self, wgpu: &WGPUWrapper, colormap: Colormap) {
+        self.colormap = colormap;
+        if let Some(scene) = &mut self.scalar_map {
+            scene.set_colormap(&wgpu.queue, colormap);
+        }
+        if let Some(scene) = &mut self.spectrum {
+            scene.set_colormap(&wgpu.queue, colormap);
+        }
+        self.redraw_pending = true;
+    }
+
+    /// Draws particles with `style` from the next frame on.
+    pub fn set_particle_style(&mut self, wgpu: &WGPUWrapper, style: ParticleStyle) {
+        self.particle_style = style;
+        if let Some(Glyphs::Particles(scene, particles)) = &mut self.glyphs {
+            scene.set_style(style);
+            scene.update(&wgpu.queue, particles);
+        }
+        self.redraw_pending = true;
+    }
+
+    /// Shows, hides or restyles the arrows and LIC of the vectors layer.
+    pub fn set_vector_overlay(&mut self, wgpu: &WGPUWrapper, overlay: VectorOverlay) {
+        self.vector_overlay = overlay;
+        self.show_vectors(wgpu);
+        self.redraw_pending = true;
+    }
+
+    /// Changes the visibility, opacity and blend mode of the layers.
+    pub fn set_layers(&mut self, layers: LayerStack) {
+        self.layers = layers;
+        self.redraw_pending = true;
+    }
+
     pub fn get_texture_handle(&self) -> TextureHandle {
         self.texture.clone()
     }
     pub fn resize_latice(&mut self, wgpu: &WGPUWrapper, lattice_dimensions: (usize, usize)) {
+        self.directors = None;
+        self.glyphs = Some(Glyphs::Lattice);
+        self.scalar_map = None;
+        self.defect_scene = None;
+        self.vorticity = None;
+        self.vector_field = None;
+        self.pitch_lines = None;
+        self.spectrum = None;
+        self.trails = None;
         self.scene = Scene::new(wgpu, lattice_dimensions);
+        self.scene.update_mask(&wgpu.queue, self.visible_mask());
+    }
+}
+
+/// The Frank term `values` of a lattice with `dimensions`, sliced by `view` on 3D lattices,
+/// colored from zero to its largest value.
+fn elastic_frame(values: &[f32], dimensions: Dimensions3, view: Option<&SliceView>) -> ScalarFrame {
+    let (values, dimensions) = match view {
+        Some(view) => (view.extract_scalars(values, dimensions), view.dimensions(dimensions)),
+        None => (values.to_vec(), (dimensions.0, dimensions.1)),
+    };
+    let max = values.iter().fold(1e-6f32, |max, &value| max.max(value));
+    ScalarFrame {
+        values,
+        dimensions,
+        range: (0.0, max),
     }
 }
@@ -0,0 +1,37 @@
+use std::fmt;
+
+/// Color scale of scalar fields, indexed the same way in `scalar_field.wgsl`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Colormap {
+    /// Blend between two colors, so binary fields such as Ising spins show exactly two.
+    #[default]
+    TwoColor,
+    Viridis,
+    Grayscale,
+    /// Diverging blue-white-red, for fields with a meaningful midpoint.
+    Coolwarm,
+}
+
+pub const COLORMAPS: [Colormap; 4] = [
+    Colormap::TwoColor,
+    Colormap::Viridis,
+    Colormap::Grayscale,
+    Colormap::Coolwarm,
+];
+
+impl Colormap {
+    pub fn shader_index(&self) -> u32 {
+        *self as u32
+    }
+}
+
+impl fmt::Display for Colormap {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::TwoColor => write!(f, "two color"),
+            Self::Viridis => write!(f, "viridis"),
+            Self::Grayscale => write!(f, "grayscale"),
+            Self::Coolwarm => write!(f, "coolwarm"),
+        }
+    }
+}
@@ -0,0 +1,5 @@
+pub mod colormap;
+pub mod scene;
+
+pub use colormap::*;
+pub use scene::*;
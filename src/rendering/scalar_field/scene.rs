@@ -0,0 +1,224 @@
+use crate::algorithm_processor::*;
+use crate::rendering::wgpu_wrapper::WGPUWrapper;
+use crate::rendering::*;
+
+type Dimentions = (usize, usize);
+
+/// Layout of `Uniforms` in `scalar_field.wgsl`.
+#[derive(Debug, Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+#[repr(C)]
+struct ScalarUniforms {
+    dimentions: [f32; 2],
+    range: [f32; 2],
+    colormap: u32,
+    opacity: f32,
+    hide_zero: u32,
+    _padding: u32,
+}
+
+/// Draws a scalar lattice as a grid of colored cells, e.g. Ising spins or concentrations.
+pub struct ScalarScene {
+    pipeline: RenderPipeline,
+    bind_group: BindGroup,
+    vertex_buffer: Buffer,
+    index_buffer: Buffer,
+    uniform_buffer: Buffer,
+    storage_buffer: Buffer,
+    mask_buffer: Buffer,
+    dimentions: Dimentions,
+    range: (f32, f32),
+    colormap: Colormap,
+    opacity: f32,
+    hide_zero: bool,
+}
+
+impl ScalarScene {
+    pub fn new(webgpu_wrapper: &WGPUWrapper, dimentions: Dimentions, colormap: Colormap) -> ScalarScene {
+        let device = &webgpu_wrapper.device;
+        let cells = dimentions.0 * dimentions.1;
+        let shader = device.create_shader_module(iced_wgpu::wgpu::include_wgsl!("../shader/scalar_field.wgsl"));
+
+        let uniform_buffer = device.create_buffer_init(&util::BufferInitDescriptor {
+            label: Some("Scalar Uniform Buffer"),
+            contents: bytemuck::bytes_of(&uniforms(dimentions, (0.0, 1.0), colormap, 1.0, false)),
+            usage: BufferUsages::UNIFORM | BufferUsages::COPY_DST,
+        });
+        let storage_buffer = device.create_buffer_init(&util::BufferInitDescriptor {
+            label: Some("Scalar Storage Buffer"),
+            contents: bytemuck::cast_slice(&vec![0.0f32; cells]),
+            usage: BufferUsages::STORAGE | BufferUsages::COPY_DST,
+        });
+        let mask_buffer = device.create_buffer_init(&util::BufferInitDescriptor {
+            label: Some("Scalar Mask Buffer"),
+            contents: bytemuck::cast_slice(&vec![0u32; cells]),
+            usage: BufferUsages::STORAGE | BufferUsages::COPY_DST,
+        });
+
+        let buffer_entry = |binding, ty| BindGroupLayoutEntry {
+            binding,
+            visibility: ShaderStages::VERTEX_FRAGMENT,
+            ty: BindingType::Buffer {
+                ty,
+                has_dynamic_offset: false,
+                min_binding_size: None,
+            },
+            count: None,
+        };
+        let bind_group_layout = device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+            label: Some("scalar bind group layout"),
+            entries: &[
+                buffer_entry(0, BufferBindingType::Uniform),
+                buffer_entry(1, BufferBindingType::Storage { read_only: true }),
+                buffer_entry(2, BufferBindingType::Storage { read_only: true }),
+            ],
+        });
+        let bind_group = device.create_bind_group(&BindGroupDescriptor {
+            layout: &bind_group_layout,
+            entries: &[
+                BindGroupEntry {
+                    binding: 0,
+                    resource: uniform_buffer.as_entire_binding(),
+                },
+                BindGroupEntry {
+                    binding: 1,
+                    resource: storage_buffer.as_entire_binding(),
+                },
+                BindGroupEntry {
+                    binding: 2,
+                    resource: mask_buffer.as_entire_binding(),
+                },
+            ],
+            label: Some("scalar_bind_group"),
+        });
+
+        let pipeline_layout = device.create_pipeline_layout(&PipelineLayoutDescriptor {
+            label: Some("Render scalar field"),
+            push_constant_ranges: &[],
+            bind_group_layouts: &[&bind_group_layout],
+        });
+        let pipeline = device.create_render_pipeline(&RenderPipelineDescriptor {
+            label: None,
+            layout: Some(&pipeline_layout),
+            vertex: VertexState {
+                module: &shader,
+                entry_point: "vs_main",
+                buffers: &[Vertex::desc()],
+            },
+            fragment: Some(FragmentState {
+                module: &shader,
+                entry_point: "fs_main",
+                targets: &[Some(ColorTargetState {
+                    format: TextureFormat::Bgra8UnormSrgb,
+                    blend: Some(BlendState::ALPHA_BLENDING),
+                    write_mask: ColorWrites::ALL,
+                })],
+            }),
+            primitive: PrimitiveState {
+                topology: PrimitiveTopology::TriangleList,
+                front_face: FrontFace::Ccw,
+                ..Default::default()
+            },
+            depth_stencil: None,
+            multisample: MultisampleState::default(),
+            multiview: None,
+        });
+
+        let vertex_buffer = device.create_buffer_init(&util::BufferInitDescriptor {
+            label: Some("Scalar Vertex Buffer"),
+            contents: bytemuck::cast_slice(VERTICES),
+            usage: BufferUsages::VERTEX,
+        });
+        let index_buffer = device.create_buffer_init(&util::BufferInitDescriptor {
+            label: Some("Scalar Index Buffer"),
+            contents: bytemuck::cast_slice(INDICES),
+            usage: BufferUsages::INDEX,
+        });
+
+        ScalarScene {
+            pipeline,
+            bind_group,
+            vertex_buffer,
+            index_buffer,
+            uniform_buffer,
+            storage_buffer,
+            mask_buffer,
+            dimentions,
+            range: (0.0, 1.0),
+            colormap,
+            opacity: 1.0,
+            hide_zero: false,
+        }
+    }
+
+    pub fn draw<'a>(&'a self, render_pass: &mut RenderPass<'a>) {
+        render_pass.set_pipeline(&self.pipeline);
+        render_pass.set_bind_group(0, &self.bind_group, &[]);
+        render_pass.set_vertex_buffer(0, self.vertex_buffer.slice(..));
+        render_pass.set_index_buffer(self.index_buffer.slice(..), IndexFormat::Uint16);
+        render_pass.draw_indexed(0..INDICES.len() as u32, 0, 0..1);
+    }
+
+    /// Uploads new values; `range` is mapped onto the ends of the colormap.
+    pub fn update(&mut self, queue: &Queue, values: &[f32], range: (f32, f32)) {
+        queue.write_buffer(&self.storage_buffer, 0, bytemuck::cast_slice(values));
+        self.range = range;
+        self.write_uniforms(queue);
+    }
+
+    pub fn set_colormap(&mut self, queue: &Queue, colormap: Colormap) {
+        self.colormap = colormap;
+        self.write_uniforms(queue);
+    }
+
+    /// Blends the field over what is already drawn, e.g. to overlay it on the director glyphs.
+    pub fn set_opacity(&mut self, queue: &Queue, opacity: f32) {
+        self.opacity = opacity;
+        self.write_uniforms(queue);
+    }
+
+    /// Leaves cells holding exactly zero undrawn, e.g. unpainted cells of a source map.
+    pub fn set_hide_zero(&mut self, queue: &Queue, hide_zero: bool) {
+        self.hide_zero = hide_zero;
+        self.write_uniforms(queue);
+    }
+
+    fn write_uniforms(&self, queue: &Queue) {
+        let uniforms = uniforms(self.dimentions, self.range, self.colormap, self.opacity, self.hide_zero);
+        queue.write_buffer(&self.uniform_buffer, 0, bytemuck::bytes_of(&uniforms));
+    }
+
+    pub fn dimentions(&self) -> Dimentions {
+        self.dimentions
+    }
+
+    /// Highlights frozen cells; `None` clears the highlight.
+    pub fn update_mask(&self, queue: &Queue, mask: Option<&Mask>) {
+        let frozen: Vec<u32> = match mask {
+            Some(mask) => mask
+                .resampled(self.dimentions)
+                .frozen
+                .iter()
+                .map(|&frozen| frozen as u32)
+                .collect(),
+            None => vec![0; self.dimentions.0 * self.dimentions.1],
+        };
+        queue.write_buffer(&self.mask_buffer, 0, bytemuck::cast_slice(&frozen));
+    }
+}
+
+fn uniforms(
+    dimentions: Dimentions,
+    range: (f32, f32),
+    colormap: Colormap,
+    opacity: f32,
+    hide_zero: bool,
+) -> ScalarUniforms {
+    ScalarUniforms {
+        dimentions: [dimentions.0 as f32, dimentions.1 as f32],
+        range: [range.0, range.1],
+        colormap: colormap.shader_index(),
+        opacity,
+        hide_zero: hide_zero as u32,
+        _padding: 0,
+    }
+}
@@ -12,6 +12,22 @@ struct Storage {
 @group(0) @binding(3)
 var<storage, read> storageBuffer: Storage;
 
+// Non-zero for cells frozen by the lattice mask.
+struct MaskStorage {
+    frozen: array<u32>,
+};
+
+@group(0) @binding(4)
+var<storage, read> maskBuffer: MaskStorage;
+
+// Glyph length and out-of-plane tilt of each cell.
+struct StyleStorage {
+    styles: array<vec2<f32>>,
+};
+
+@group(0) @binding(5)
+var<storage, read> styleBuffer: StyleStorage;
+
 struct VertexInput {
     @location(0) position: vec3<f32>,
     @location(1) tex_coords: vec2<f32>,
@@ -22,6 +38,8 @@ struct VertexInput {
 struct VertexOutput {
     @builtin(position) clip_position: vec4<f32>,
     @location(0) tex_coords: vec2<f32>,
+    @location(1) @interpolate(flat) frozen: u32,
+    @location(2) @interpolate(flat) tilt: f32,
 }
 
 @vertex
@@ -32,7 +50,8 @@ fn vs_main(
     let instanceAngle: f32 = storageBuffer.angle_data[input.index];
     let pos = vec2<f32>(input.position.xy);
     let offset = vec2<f32>(input.offset.xy);
-    let translated_pos = pos - offset;
+    let style = styleBuffer.styles[input.index];
+    let translated_pos = (pos - offset) * vec2<f32>(style.x, 1.0);
     let sin_a = sin(instanceAngle);
     let cos_a = cos(instanceAngle);
     let rotation_matrix = mat2x2<f32>(
@@ -45,6 +64,8 @@ fn vs_main(
     var out: VertexOutput;
     out.tex_coords = input.tex_coords;
     out.clip_position = vec4<f32>(rotated_pos, 0.0, 1.0);
+    out.frozen = maskBuffer.frozen[input.index];
+    out.tilt = style.y;
     return out;
 }
 
@@ -57,5 +78,10 @@ var s_diffuse: sampler;
 
 @fragment
 fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
-    return textureSample(t_diffuse, s_diffuse, in.tex_coords);
+    var color = textureSample(t_diffuse, s_diffuse, in.tex_coords);
+    color = vec4<f32>(mix(color.rgb, vec3<f32>(0.2, 0.5, 1.0), 0.7 * in.tilt), color.a);
+    if (in.frozen != 0u) {
+        return vec4<f32>(mix(vec3<f32>(0.55, 0.15, 0.15), color.rgb, 0.5), 1.0);
+    }
+    return color;
 }
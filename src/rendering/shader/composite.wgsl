@@ -0,0 +1,41 @@
+struct Uniforms {
+    opacity: f32,
+    // One of `BlendMode`, in declaration order.
+    blend: u32,
+};
+@group(0) @binding(0)
+var t_layer: texture_2d<f32>;
+@group(0) @binding(1)
+var s_layer: sampler;
+@group(0) @binding(2)
+var<uniform> uniforms: Uniforms;
+
+struct VertexInput {
+    @location(0) position: vec3<f32>,
+    @location(1) tex_coords: vec2<f32>,
+}
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) tex_coords: vec2<f32>,
+}
+
+@vertex
+fn vs_main(input: VertexInput) -> VertexOutput {
+    var out: VertexOutput;
+    out.clip_position = vec4<f32>(input.position, 1.0);
+    out.tex_coords = input.tex_coords;
+    return out;
+}
+
+// Layers are cleared to transparent black and drawn with alpha blending, so they hold
+// premultiplied colors. The blend state of each pipeline does the rest of the blend mode.
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let layer = textureSample(t_layer, s_layer, in.tex_coords) * uniforms.opacity;
+    if (uniforms.blend == 2u) {
+        // Multiply: dst * (1 - a + rgb), applied with a (dst, zero) blend.
+        return vec4<f32>(vec3<f32>(1.0 - layer.a) + layer.rgb, 1.0);
+    }
+    return layer;
+}
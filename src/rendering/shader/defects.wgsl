@@ -0,0 +1,49 @@
+struct Uniforms {
+    dimentions: vec2<f32>,
+    // Marker radius in cells.
+    radius: f32,
+};
+@group(0) @binding(0)
+var<uniform> uniforms: Uniforms;
+
+struct VertexInput {
+    @location(0) corner: vec2<f32>,
+    // Defect position in cells from the bottom-left corner.
+    @location(1) position: vec2<f32>,
+    @location(2) charge: f32,
+}
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) corner: vec2<f32>,
+    @location(1) @interpolate(flat) charge: f32,
+}
+
+@vertex
+fn vs_main(input: VertexInput) -> VertexOutput {
+    // Integer charges get bigger markers than half-integer ones.
+    let radius = uniforms.radius * select(1.0, 1.4, abs(input.charge) >= 1.0);
+    let cell = input.position + input.corner * radius;
+    var out: VertexOutput;
+    out.clip_position = vec4<f32>(cell / uniforms.dimentions.yx * 2.0 - 1.0, 0.0, 1.0);
+    out.corner = input.corner;
+    out.charge = input.charge;
+    return out;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let distance = length(in.corner);
+    if (distance > 1.0) {
+        discard;
+    }
+    // Positive defects red, negative blue, both outlined in black.
+    var color = vec3<f32>(0.9, 0.1, 0.05);
+    if (in.charge < 0.0) {
+        color = vec3<f32>(0.05, 0.25, 0.95);
+    }
+    if (distance > 0.75) {
+        color = vec3<f32>(0.0);
+    }
+    return vec4<f32>(color, 1.0);
+}
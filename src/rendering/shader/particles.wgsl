@@ -0,0 +1,65 @@
+struct Uniforms {
+    box_size: vec2<f32>,
+    // Half width of every glyph, in simulation units.
+    radius: f32,
+    // Length over width of rods.
+    aspect: f32,
+    // 0 for rods colored by orientation, 1 for disks colored by speed.
+    glyph: u32,
+};
+@group(0) @binding(0)
+var<uniform> uniforms: Uniforms;
+
+struct VertexInput {
+    @location(0) corner: vec2<f32>,
+    @location(1) position: vec2<f32>,
+    @location(2) angle: f32,
+    @location(3) speed: f32,
+}
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) corner: vec2<f32>,
+    @location(1) @interpolate(flat) angle: f32,
+    @location(2) @interpolate(flat) speed: f32,
+}
+
+@vertex
+fn vs_main(input: VertexInput) -> VertexOutput {
+    var half_size = vec2<f32>(uniforms.radius, uniforms.radius);
+    var angle = 0.0;
+    if (uniforms.glyph == 0u) {
+        half_size.x *= uniforms.aspect;
+        angle = input.angle;
+    }
+    let rotation = mat2x2<f32>(cos(angle), sin(angle), -sin(angle), cos(angle));
+    let world = input.position + rotation * (input.corner * half_size);
+
+    var out: VertexOutput;
+    out.clip_position = vec4<f32>(world / uniforms.box_size * 2.0 - 1.0, 0.0, 1.0);
+    out.corner = input.corner;
+    out.angle = input.angle;
+    out.speed = input.speed;
+    return out;
+}
+
+fn hue(h: f32) -> vec3<f32> {
+    let k = vec3<f32>(0.0, 2.0 / 3.0, 1.0 / 3.0);
+    return clamp(abs(fract(h + k) * 6.0 - 3.0) - 1.0, vec3<f32>(0.0), vec3<f32>(1.0));
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let distance = length(in.corner);
+    if (distance > 1.0) {
+        discard;
+    }
+    var color: vec3<f32>;
+    if (uniforms.glyph == 0u) {
+        // Head-tail symmetric, so the hue goes round once per half turn.
+        color = hue(in.angle / 3.14159265);
+    } else {
+        color = mix(vec3<f32>(0.1, 0.3, 0.9), vec3<f32>(1.0, 0.5, 0.1), in.speed / (in.speed + 1.0));
+    }
+    return vec4<f32>(color * (1.0 - 0.5 * smoothstep(0.75, 1.0, distance)), 1.0);
+}
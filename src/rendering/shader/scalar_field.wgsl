@@ -0,0 +1,106 @@
+struct Uniforms {
+    dimentions: vec2<f32>,
+    // Values mapped to the two ends of the colormap.
+    range: vec2<f32>,
+    // One of `Colormap`, in declaration order.
+    colormap: u32,
+    // Below one when blended over the director glyphs.
+    opacity: f32,
+    // Non-zero to leave cells holding exactly zero undrawn.
+    hide_zero: u32,
+};
+@group(0) @binding(0)
+var<uniform> uniforms: Uniforms;
+
+struct Storage {
+    values: array<f32>,
+};
+
+@group(0) @binding(1)
+var<storage, read> storageBuffer: Storage;
+
+// Non-zero for cells frozen by the lattice mask.
+struct MaskStorage {
+    frozen: array<u32>,
+};
+
+@group(0) @binding(2)
+var<storage, read> maskBuffer: MaskStorage;
+
+struct VertexInput {
+    @location(0) position: vec3<f32>,
+    @location(1) tex_coords: vec2<f32>,
+}
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    // Lattice coordinates, in cells from the bottom-left corner.
+    @location(0) cell: vec2<f32>,
+}
+
+@vertex
+fn vs_main(input: VertexInput) -> VertexOutput {
+    var out: VertexOutput;
+    out.clip_position = vec4<f32>(input.position, 1.0);
+    out.cell = vec2<f32>(input.tex_coords.x, 1.0 - input.tex_coords.y) * uniforms.dimentions.yx;
+    return out;
+}
+
+const LOW_COLOR = vec3<f32>(0.05, 0.1, 0.35);
+const HIGH_COLOR = vec3<f32>(0.95, 0.75, 0.2);
+
+// Polynomial fit of matplotlib's viridis, in sRGB.
+fn viridis(t: f32) -> vec3<f32> {
+    let c0 = vec3<f32>(0.2777273, 0.0054073, 0.3340998);
+    let c1 = vec3<f32>(0.1050930, 1.4046135, 1.3845902);
+    let c2 = vec3<f32>(-0.3308618, 0.2148476, 0.0950952);
+    let c3 = vec3<f32>(-4.6342305, -5.7991010, -19.3324410);
+    let c4 = vec3<f32>(6.2282699, 14.1799334, 56.6905526);
+    let c5 = vec3<f32>(4.7763850, -13.7451454, -65.3530326);
+    let c6 = vec3<f32>(-5.4354559, 4.6458526, 26.3124352);
+    return c0 + t * (c1 + t * (c2 + t * (c3 + t * (c4 + t * (c5 + t * c6)))));
+}
+
+fn coolwarm(t: f32) -> vec3<f32> {
+    let cool = vec3<f32>(0.230, 0.299, 0.754);
+    let middle = vec3<f32>(0.865, 0.865, 0.865);
+    let warm = vec3<f32>(0.706, 0.016, 0.150);
+    if (t < 0.5) {
+        return mix(cool, middle, 2.0 * t);
+    }
+    return mix(middle, warm, 2.0 * t - 1.0);
+}
+
+// The render target is sRGB, so colormaps given in sRGB are converted to linear first.
+fn to_linear(color: vec3<f32>) -> vec3<f32> {
+    return pow(clamp(color, vec3<f32>(0.0), vec3<f32>(1.0)), vec3<f32>(2.2));
+}
+
+fn colormap(t: f32) -> vec3<f32> {
+    switch uniforms.colormap {
+        case 1u: { return to_linear(viridis(t)); }
+        case 2u: { return to_linear(vec3<f32>(t)); }
+        case 3u: { return to_linear(coolwarm(t)); }
+        default: { return mix(LOW_COLOR, HIGH_COLOR, t); }
+    }
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let rows = u32(uniforms.dimentions.x);
+    let cols = u32(uniforms.dimentions.y);
+    let col = min(u32(in.cell.x), cols - 1u);
+    let row = min(u32(in.cell.y), rows - 1u);
+    let index = row * cols + col;
+
+    if (uniforms.hide_zero != 0u && storageBuffer.values[index] == 0.0) {
+        discard;
+    }
+    let span = max(uniforms.range.y - uniforms.range.x, 1e-6);
+    let t = clamp((storageBuffer.values[index] - uniforms.range.x) / span, 0.0, 1.0);
+    let color = colormap(t);
+    if (maskBuffer.frozen[index] != 0u) {
+        return vec4<f32>(mix(vec3<f32>(0.55, 0.15, 0.15), color, 0.5), 1.0);
+    }
+    return vec4<f32>(color, uniforms.opacity);
+}
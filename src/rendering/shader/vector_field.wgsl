@@ -0,0 +1,58 @@
+struct Uniforms {
+    dimentions: vec2<f32>,
+};
+@group(0) @binding(0)
+var<uniform> uniforms: Uniforms;
+
+struct ArrowInput {
+    @location(0) corner: vec2<f32>,
+    // Arrow centre in cells from the bottom-left corner.
+    @location(1) position: vec2<f32>,
+    @location(2) angle: f32,
+    // Length in cells.
+    @location(3) length: f32,
+}
+
+@vertex
+fn vs_arrow(input: ArrowInput) -> @builtin(position) vec4<f32> {
+    let rotation = mat2x2<f32>(cos(input.angle), sin(input.angle), -sin(input.angle), cos(input.angle));
+    let cell = input.position + rotation * (input.corner * input.length);
+    return vec4<f32>(cell / uniforms.dimentions.yx * 2.0 - 1.0, 0.0, 1.0);
+}
+
+@fragment
+fn fs_arrow() -> @location(0) vec4<f32> {
+    return vec4<f32>(0.02, 0.02, 0.02, 1.0);
+}
+
+@group(0) @binding(1)
+var t_lic: texture_2d<f32>;
+@group(0) @binding(2)
+var s_lic: sampler;
+
+struct LicInput {
+    @location(0) position: vec3<f32>,
+    @location(1) tex_coords: vec2<f32>,
+}
+
+struct LicOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) tex_coords: vec2<f32>,
+}
+
+@vertex
+fn vs_lic(input: LicInput) -> LicOutput {
+    var out: LicOutput;
+    out.clip_position = vec4<f32>(input.position, 1.0);
+    out.tex_coords = input.tex_coords;
+    return out;
+}
+
+// Streaks are blended over the base scene rather than hiding it.
+const LIC_OPACITY = 0.6;
+
+@fragment
+fn fs_lic(in: LicOutput) -> @location(0) vec4<f32> {
+    let grey = textureSample(t_lic, s_lic, in.tex_coords).r;
+    return vec4<f32>(vec3<f32>(grey), LIC_OPACITY);
+}
@@ -0,0 +1,82 @@
+/// Integration steps taken each way along the streamline of every pixel.
+const LIC_STEPS: usize = 12;
+
+/// Line integral convolution of white noise along a row-major vector field with row 0 at the
+/// bottom, at `samples` pixels per cell.
+///
+/// Returns the grey levels with the top row first, ready for upload as a texture, together with
+/// the `(width, height)` in pixels. The noise is fixed per pixel, so the streaks follow the field
+/// from frame to frame instead of flickering.
+pub fn line_integral_convolution(
+    field: &[[f32; 2]],
+    (rows, cols): (usize, usize),
+    samples: usize,
+) -> (Vec<u8>, (usize, usize)) {
+    let (width, height) = (cols * samples, rows * samples);
+    let step = 0.5 / samples as f32;
+    let interpolate = |x: f32, y: f32| {
+        // Vectors sit at cell centres; the field is clamped at the edges.
+        let (x, y) = ((x - 0.5).clamp(0.0, (cols - 1) as f32), (y - 0.5).clamp(0.0, (rows - 1) as f32));
+        let (col, row) = ((x as usize).min(cols.saturating_sub(2)), (y as usize).min(rows.saturating_sub(2)));
+        let (fx, fy) = (x - col as f32, y - row as f32);
+        let at = |row: usize, col: usize| field[row.min(rows - 1) * cols + col.min(cols - 1)];
+        let mut value = [0.0; 2];
+        for (weight, vector) in [
+            ((1.0 - fx) * (1.0 - fy), at(row, col)),
+            (fx * (1.0 - fy), at(row, col + 1)),
+            ((1.0 - fx) * fy, at(row + 1, col)),
+            (fx * fy, at(row + 1, col + 1)),
+        ] {
+            value[0] += weight * vector[0];
+            value[1] += weight * vector[1];
+        }
+        value
+    };
+
+    let mut pixels = Vec::with_capacity(width * height);
+    for pixel_row in 0..height {
+        for pixel_col in 0..width {
+            let start = (
+                (pixel_col as f32 + 0.5) / samples as f32,
+                rows as f32 - (pixel_row as f32 + 0.5) / samples as f32,
+            );
+            let mut sum = noise(pixel_col, pixel_row);
+            let mut count = 1usize;
+            for direction in [1.0f32, -1.0] {
+                let (mut x, mut y) = start;
+                for _ in 0..LIC_STEPS {
+                    let [vx, vy] = interpolate(x, y);
+                    let speed = (vx * vx + vy * vy).sqrt();
+                    if speed == 0.0 {
+                        break;
+                    }
+                    x += direction * step * vx / speed;
+                    y += direction * step * vy / speed;
+                    if x < 0.0 || y < 0.0 || x >= cols as f32 || y >= rows as f32 {
+                        break;
+                    }
+                    let along_col = (x * samples as f32) as usize;
+                    let along_row = ((rows as f32 - y) * samples as f32) as usize;
+                    sum += noise(along_col, along_row.min(height - 1));
+                    count += 1;
+                }
+            }
+            // The mean of n uniform samples narrows as 1/sqrt(n); stretch it back out.
+            let mean = sum / count as f32;
+            let stretched = 0.5 + (mean - 0.5) * (count as f32).sqrt() * 0.6;
+            pixels.push((stretched.clamp(0.0, 1.0) * 255.0) as u8);
+        }
+    }
+    (pixels, (width, height))
+}
+
+/// Uniform noise in `[0, 1)` from an integer hash of the pixel.
+fn noise(col: usize, row: usize) -> f32 {
+    let mut hash = (col as u32).wrapping_mul(0x8da6_b343) ^ (row as u32).wrapping_mul(0xd816_3841);
+    hash ^= hash >> 16;
+    hash = hash.wrapping_mul(0x7feb_352d);
+    hash ^= hash >> 15;
+    hash = hash.wrapping_mul(0x846c_a68b);
+    hash ^= hash >> 16;
+    (hash >> 8) as f32 / (1u32 << 24) as f32
+}
@@ -0,0 +1,7 @@
+pub mod lic;
+pub mod scene;
+pub mod style;
+
+pub use lic::*;
+pub use scene::*;
+pub use style::*;
@@ -0,0 +1,377 @@
+use crate::algorithm_processor::*;
+use crate::rendering::wgpu_wrapper::WGPUWrapper;
+use crate::rendering::*;
+
+/// Shaft and head of an arrow of unit length along x, centred on the origin.
+const ARROW: [[f32; 2]; 9] = [
+    [-0.5, -0.06],
+    [0.2, -0.06],
+    [-0.5, 0.06],
+    [-0.5, 0.06],
+    [0.2, -0.06],
+    [0.2, 0.06],
+    [0.2, -0.2],
+    [0.5, 0.0],
+    [0.2, 0.2],
+];
+const CORNER_ATTRIBS: [VertexAttribute; 1] = iced_wgpu::wgpu::vertex_attr_array![0 => Float32x2];
+
+/// Largest side of the LIC texture in pixels.
+const LIC_RESOLUTION: usize = 384;
+/// Arrows shorter than this fraction of the spacing are left out.
+const SHORTEST_ARROW: f32 = 0.05;
+
+/// Layout of `Uniforms` in `vector_field.wgsl`.
+#[derive(Debug, Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+#[repr(C)]
+struct VectorUniforms {
+    dimentions: [f32; 2],
+    _padding: [u32; 2],
+}
+
+/// Per-instance data of one arrow.
+#[derive(Debug, Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+#[repr(C)]
+struct ArrowInstance {
+    position: [f32; 2],
+    angle: f32,
+    length: f32,
+}
+
+impl ArrowInstance {
+    const ATTRIBS: [VertexAttribute; 3] = iced_wgpu::wgpu::vertex_attr_array![
+        1 => Float32x2,
+        2 => Float32,
+        3 => Float32,
+    ];
+
+    fn desc<'a>() -> VertexBufferLayout<'a> {
+        VertexBufferLayout {
+            array_stride: std::mem::size_of::<ArrowInstance>() as BufferAddress,
+            step_mode: VertexStepMode::Instance,
+            attributes: &Self::ATTRIBS,
+        }
+    }
+}
+
+/// Line integral convolution texture and the bind group sampling it.
+struct LicTexture {
+    texture: Texture,
+    bind_group: BindGroup,
+    size: (usize, usize),
+}
+
+/// Draws a lattice vector field as arrows scaled by magnitude, optionally over a line integral
+/// convolution of the field. Everything is blended over whatever was drawn before.
+pub struct VectorScene {
+    arrow_pipeline: RenderPipeline,
+    lic_pipeline: RenderPipeline,
+    uniform_bind_group: BindGroup,
+    lic_layout: BindGroupLayout,
+    sampler: Sampler,
+    corner_buffer: Buffer,
+    quad_vertex_buffer: Buffer,
+    quad_index_buffer: Buffer,
+    uniform_buffer: Buffer,
+    instance_buffer: Buffer,
+    capacity: usize,
+    count: usize,
+    lic: Option<LicTexture>,
+}
+
+impl VectorScene {
+    pub fn new(webgpu_wrapper: &WGPUWrapper) -> VectorScene {
+        let device = &webgpu_wrapper.device;
+        let shader = device.create_shader_module(iced_wgpu::wgpu::include_wgsl!("../shader/vector_field.wgsl"));
+
+        let uniform_buffer = device.create_buffer(&BufferDescriptor {
+            label: Some("Vector Uniform Buffer"),
+            size: std::mem::size_of::<VectorUniforms>() as BufferAddress,
+            usage: BufferUsages::UNIFORM | BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let corner_buffer = device.create_buffer_init(&util::BufferInitDescriptor {
+            label: Some("Arrow Corner Buffer"),
+            contents: bytemuck::cast_slice(&ARROW),
+            usage: BufferUsages::VERTEX,
+        });
+        let quad_vertex_buffer = device.create_buffer_init(&util::BufferInitDescriptor {
+            label: Some("LIC Vertex Buffer"),
+            contents: bytemuck::cast_slice(VERTICES),
+            usage: BufferUsages::VERTEX,
+        });
+        let quad_index_buffer = device.create_buffer_init(&util::BufferInitDescriptor {
+            label: Some("LIC Index Buffer"),
+            contents: bytemuck::cast_slice(INDICES),
+            usage: BufferUsages::INDEX,
+        });
+        let capacity = 1;
+        let instance_buffer = Self::instance_buffer(device, capacity);
+
+        let uniform_layout = device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+            label: Some("vector bind group layout"),
+            entries: &[BindGroupLayoutEntry {
+                binding: 0,
+                visibility: ShaderStages::VERTEX,
+                ty: BindingType::Buffer {
+                    ty: BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            }],
+        });
+        let uniform_bind_group = device.create_bind_group(&BindGroupDescriptor {
+            layout: &uniform_layout,
+            entries: &[BindGroupEntry {
+                binding: 0,
+                resource: uniform_buffer.as_entire_binding(),
+            }],
+            label: Some("vector_bind_group"),
+        });
+        let lic_layout = device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+            label: Some("lic bind group layout"),
+            entries: &[
+                BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: ShaderStages::FRAGMENT,
+                    ty: BindingType::Texture {
+                        sample_type: TextureSampleType::Float { filterable: true },
+                        view_dimension: TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+                BindGroupLayoutEntry {
+                    binding: 2,
+                    visibility: ShaderStages::FRAGMENT,
+                    ty: BindingType::Sampler(SamplerBindingType::Filtering),
+                    count: None,
+                },
+            ],
+        });
+        let sampler = device.create_sampler(&SamplerDescriptor {
+            address_mode_u: AddressMode::ClampToEdge,
+            address_mode_v: AddressMode::ClampToEdge,
+            address_mode_w: AddressMode::ClampToEdge,
+            mag_filter: FilterMode::Linear,
+            min_filter: FilterMode::Linear,
+            mipmap_filter: FilterMode::Nearest,
+            ..Default::default()
+        });
+
+        let target = [Some(ColorTargetState {
+            format: TextureFormat::Bgra8UnormSrgb,
+            blend: Some(BlendState::ALPHA_BLENDING),
+            write_mask: ColorWrites::ALL,
+        })];
+        let pipeline = |label, layout: &BindGroupLayout, entry: (&str, &str), buffers: &[VertexBufferLayout]| {
+            let pipeline_layout = device.create_pipeline_layout(&PipelineLayoutDescriptor {
+                label: Some(label),
+                push_constant_ranges: &[],
+                bind_group_layouts: &[layout],
+            });
+            device.create_render_pipeline(&RenderPipelineDescriptor {
+                label: None,
+                layout: Some(&pipeline_layout),
+                vertex: VertexState {
+                    module: &shader,
+                    entry_point: entry.0,
+                    buffers,
+                },
+                fragment: Some(FragmentState {
+                    module: &shader,
+                    entry_point: entry.1,
+                    targets: &target,
+                }),
+                primitive: PrimitiveState {
+                    topology: PrimitiveTopology::TriangleList,
+                    front_face: FrontFace::Ccw,
+                    ..Default::default()
+                },
+                depth_stencil: None,
+                multisample: MultisampleState::default(),
+                multiview: None,
+            })
+        };
+        let corner_layout = VertexBufferLayout {
+            array_stride: std::mem::size_of::<[f32; 2]>() as BufferAddress,
+            step_mode: VertexStepMode::Vertex,
+            attributes: &CORNER_ATTRIBS,
+        };
+        let arrow_pipeline = pipeline(
+            "Render vector arrows",
+            &uniform_layout,
+            ("vs_arrow", "fs_arrow"),
+            &[corner_layout, ArrowInstance::desc()],
+        );
+        let lic_pipeline = pipeline("Render vector LIC", &lic_layout, ("vs_lic", "fs_lic"), &[Vertex::desc()]);
+
+        VectorScene {
+            arrow_pipeline,
+            lic_pipeline,
+            uniform_bind_group,
+            lic_layout,
+            sampler,
+            corner_buffer,
+            quad_vertex_buffer,
+            quad_index_buffer,
+            uniform_buffer,
+            instance_buffer,
+            capacity,
+            count: 0,
+            lic: None,
+        }
+    }
+
+    fn instance_buffer(device: &Device, capacity: usize) -> Buffer {
+        device.create_buffer(&BufferDescriptor {
+            label: Some("Arrow Instance Buffer"),
+            size: (capacity * std::mem::size_of::<ArrowInstance>()) as BufferAddress,
+            usage: BufferUsages::VERTEX | BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        })
+    }
+
+    /// Rebuilds the arrows, and the LIC texture when `overlay.lic` is set, from `field`.
+    pub fn update(&mut self, wgpu: &WGPUWrapper, (vectors, dimensions): &VectorField, overlay: VectorOverlay) {
+        let instances = arrows(vectors, *dimensions, overlay.spacing.max(1));
+        if instances.len() > self.capacity {
+            self.capacity = instances.len().next_power_of_two();
+            self.instance_buffer = Self::instance_buffer(&wgpu.device, self.capacity);
+        }
+        self.count = instances.len();
+        wgpu.queue
+            .write_buffer(&self.instance_buffer, 0, bytemuck::cast_slice(&instances));
+        let uniforms = VectorUniforms {
+            dimentions: [dimensions.0 as f32, dimensions.1 as f32],
+            _padding: [0; 2],
+        };
+        wgpu.queue
+            .write_buffer(&self.uniform_buffer, 0, bytemuck::bytes_of(&uniforms));
+
+        if overlay.lic {
+            let samples = (LIC_RESOLUTION / dimensions.0.max(dimensions.1).max(1)).clamp(1, 4);
+            let (grey, size) = line_integral_convolution(vectors, *dimensions, samples);
+            self.upload_lic(wgpu, &grey, size);
+        } else {
+            self.lic = None;
+        }
+    }
+
+    fn upload_lic(&mut self, wgpu: &WGPUWrapper, grey: &[u8], size: (usize, usize)) {
+        let extent = Extent3d {
+            width: size.0 as u32,
+            height: size.1 as u32,
+            depth_or_array_layers: 1,
+        };
+        if self.lic.as_ref().is_none_or(|lic| lic.size != size) {
+            let texture = wgpu.device.create_texture(&TextureDescriptor {
+                label: Some("LIC Texture"),
+                size: extent,
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: TextureDimension::D2,
+                format: TextureFormat::R8Unorm,
+                usage: TextureUsages::TEXTURE_BINDING | TextureUsages::COPY_DST,
+                view_formats: &[],
+            });
+            let view = texture.create_view(&TextureViewDescriptor::default());
+            let bind_group = wgpu.device.create_bind_group(&BindGroupDescriptor {
+                layout: &self.lic_layout,
+                entries: &[
+                    BindGroupEntry {
+                        binding: 1,
+                        resource: BindingResource::TextureView(&view),
+                    },
+                    BindGroupEntry {
+                        binding: 2,
+                        resource: BindingResource::Sampler(&self.sampler),
+                    },
+                ],
+                label: Some("lic_bind_group"),
+            });
+            self.lic = Some(LicTexture {
+                texture,
+                bind_group,
+                size,
+            });
+        }
+        let Some(lic) = &self.lic else {
+            return;
+        };
+        wgpu.queue.write_texture(
+            ImageCopyTexture {
+                texture: &lic.texture,
+                mip_level: 0,
+                origin: Origin3d::ZERO,
+                aspect: TextureAspect::All,
+            },
+            grey,
+            ImageDataLayout {
+                offset: 0,
+                bytes_per_row: Some(size.0 as u32),
+                rows_per_image: Some(size.1 as u32),
+            },
+            extent,
+        );
+    }
+
+    pub fn draw<'a>(&'a self, render_pass: &mut RenderPass<'a>) {
+        if let Some(lic) = &self.lic {
+            render_pass.set_pipeline(&self.lic_pipeline);
+            render_pass.set_bind_group(0, &lic.bind_group, &[]);
+            render_pass.set_vertex_buffer(0, self.quad_vertex_buffer.slice(..));
+            render_pass.set_index_buffer(self.quad_index_buffer.slice(..), IndexFormat::Uint16);
+            render_pass.draw_indexed(0..INDICES.len() as u32, 0, 0..1);
+        }
+        if self.count > 0 {
+            render_pass.set_pipeline(&self.arrow_pipeline);
+            render_pass.set_bind_group(0, &self.uniform_bind_group, &[]);
+            render_pass.set_vertex_buffer(0, self.corner_buffer.slice(..));
+            render_pass.set_vertex_buffer(1, self.instance_buffer.slice(..));
+            render_pass.draw(0..ARROW.len() as u32, 0..self.count as u32);
+        }
+    }
+}
+
+/// One arrow per `spacing` x `spacing` block showing its mean vector, the longest arrow spanning
+/// most of a block.
+fn arrows(vectors: &[[f32; 2]], (rows, cols): (usize, usize), spacing: usize) -> Vec<ArrowInstance> {
+    let mut blocks = Vec::new();
+    for row in (0..rows).step_by(spacing) {
+        for col in (0..cols).step_by(spacing) {
+            let (row_end, col_end) = ((row + spacing).min(rows), (col + spacing).min(cols));
+            let mut sum = [0.0f32; 2];
+            for block_row in row..row_end {
+                for block_col in col..col_end {
+                    let [x, y] = vectors[block_row * cols + block_col];
+                    sum[0] += x;
+                    sum[1] += y;
+                }
+            }
+            let cells = ((row_end - row) * (col_end - col)) as f32;
+            let center = [(col + col_end) as f32 / 2.0, (row + row_end) as f32 / 2.0];
+            blocks.push((center, [sum[0] / cells, sum[1] / cells]));
+        }
+    }
+
+    let longest = blocks
+        .iter()
+        .map(|(_, [x, y])| (x * x + y * y).sqrt())
+        .fold(0.0f32, f32::max);
+    if longest == 0.0 {
+        return Vec::new();
+    }
+    blocks
+        .into_iter()
+        .filter_map(|(position, [x, y])| {
+            let length = (x * x + y * y).sqrt() / longest * 0.9 * spacing as f32;
+            (length >= SHORTEST_ARROW * spacing as f32).then(|| ArrowInstance {
+                position,
+                angle: y.atan2(x),
+                length,
+            })
+        })
+        .collect()
+}
@@ -0,0 +1,19 @@
+/// How vector fields, e.g. flow velocities or scalar gradients, are drawn over the base scene.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct VectorOverlay {
+    pub enabled: bool,
+    /// Cells between arrows; each arrow shows the mean vector of its block.
+    pub spacing: usize,
+    /// Adds a line integral convolution texture under the arrows.
+    pub lic: bool,
+}
+
+impl Default for VectorOverlay {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            spacing: 4,
+            lic: false,
+        }
+    }
+}
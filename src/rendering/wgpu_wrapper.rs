@@ -30,7 +30,7 @@ impl WGPUWrapper {
         );
         let renderer = Renderer::new(&device, &engine, Font::default(), Pixels::from(16));
 
-        Self::configure_surface(&mut surface, &device, &*window, format);
+        Self::configure_surface(&mut surface, &device, &window, format);
         Self {
             _backends: backends,
             _instance: instance,
@@ -61,7 +61,7 @@ impl WGPUWrapper {
         surface: &Surface,
     ) -> (TextureFormat, Adapter, Device, Queue) {
         futures::futures::executor::block_on(async {
-            let adapter = util::initialize_adapter_from_env_or_default(&instance, Some(&surface))
+            let adapter = util::initialize_adapter_from_env_or_default(instance, Some(surface))
                 .await
                 .expect("Create adapter");
 
@@ -104,7 +104,7 @@ impl WGPUWrapper {
     ) {
         let physical_size = window.inner_size();
         surface.configure(
-            &device,
+            device,
             &SurfaceConfiguration {
                 usage: TextureUsages::RENDER_ATTACHMENT,
                 format,
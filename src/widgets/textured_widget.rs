@@ -1,5 +1,6 @@
 use crate::rendering::generic_pipeline::Pipeline;
 use crate::rendering::*;
+use iced_winit::core::{Shell, event};
 
 #[derive(Clone)]
 pub struct TexturedWidget {
@@ -12,10 +13,63 @@ impl TexturedWidget {
     }
 }
 
-impl<Message> shader::Program<Message> for TexturedWidget {
-    type State = ();
+/// A press or drag on the displayed lattice. `position` is a fraction of the display with
+/// `(0, 0)` at the bottom-left corner, matching the lattice layout.
+#[derive(Debug, Clone, Copy)]
+pub struct DisplayPress {
+    pub position: (f32, f32),
+    pub button: mouse::Button,
+}
+
+#[derive(Default)]
+pub struct PressState {
+    held: Option<mouse::Button>,
+}
+
+impl<Message: From<DisplayPress>> shader::Program<Message> for TexturedWidget {
+    type State = PressState;
     type Primitive = Primitive;
 
+    fn update(
+        &self,
+        state: &mut Self::State,
+        event: shader::Event,
+        bounds: Rectangle,
+        cursor: mouse::Cursor,
+        _shell: &mut Shell<'_, Message>,
+    ) -> (event::Status, Option<Message>) {
+        let shader::Event::Mouse(event) = event else {
+            return (event::Status::Ignored, None);
+        };
+        let button = match event {
+            mouse::Event::ButtonPressed(button) if cursor.is_over(bounds) => {
+                state.held = Some(button);
+                button
+            }
+            mouse::Event::ButtonReleased(_) => {
+                state.held = None;
+                return (event::Status::Ignored, None);
+            }
+            mouse::Event::CursorMoved { .. } => match state.held {
+                Some(button) => button,
+                None => return (event::Status::Ignored, None),
+            },
+            _ => return (event::Status::Ignored, None),
+        };
+        let Some(position) = cursor.position_in(bounds) else {
+            return (event::Status::Ignored, None);
+        };
+
+        let press = DisplayPress {
+            position: (
+                position.x / bounds.width,
+                1.0 - position.y / bounds.height,
+            ),
+            button,
+        };
+        (event::Status::Captured, Some(press.into()))
+    }
+
     fn draw(
         &self,
         _state: &Self::State,